@@ -0,0 +1,93 @@
+//! qi provides procedural macros to generate code from OpenAPI specs in
+//! source, which is an alternative to `qi_rust::Builder` in `build.rs`.
+//!
+//! ```ignore
+//! mod api {
+//!     qi::include_spec!("api.yaml");
+//! }
+//! ```
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use std::env;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use syn::{parse_macro_input, LitStr};
+
+use qi_ir::Service;
+use qi_rust::{load, sources, validate_file, ActixWebGenerator, Generator};
+
+/// Expand to the models, server trait and client generated from the spec,
+/// whose path is relative to `CARGO_MANIFEST_DIR`.
+///
+/// Errors in the spec are reported at the macro invocation with their
+/// locations in the spec.
+#[proc_macro]
+pub fn include_spec(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+
+    let dir = env::var_os("CARGO_MANIFEST_DIR").map_or_else(PathBuf::new, PathBuf::from);
+    expand(&dir.join(path.value()), path.span()).into()
+}
+
+fn expand(path: &Path, span: Span) -> TokenStream {
+    let error = |message: String| syn::Error::new(span, message).to_compile_error();
+
+    let (issues, spans) = match validate_file(path, None) {
+        Ok(v) => v,
+        Err(e) => return error(format!("{}: {}", path.display(), e)),
+    };
+    // Errors are reported with the locations of their pointers in the spec.
+    let located =
+        |pointer: &str, message: &dyn Display| match spans.as_ref().and_then(|m| m.locate(pointer))
+        {
+            None => error(format!("{}: {}", path.display(), message)),
+            Some(v) => error(format!(
+                "{}:{}:{}: {}",
+                path.display(),
+                v.line,
+                v.column,
+                message
+            )),
+        };
+    if !issues.is_empty() {
+        return issues
+            .iter()
+            .map(|issue| located(&issue.pointer, issue))
+            .collect();
+    }
+
+    let spec = match load(path, None) {
+        Ok(v) => v,
+        Err(e) => return error(format!("{}: {}", path.display(), e)),
+    };
+    let srv = match Service::new(spec) {
+        Ok(v) => v,
+        Err(e) => return located(&e.pointer, &e),
+    };
+    let code = match ActixWebGenerator::new(Generator::new(srv)).generate() {
+        Ok(v) => v,
+        Err(e) => return error(format!("{}: {}", path.display(), e)),
+    };
+    let code: TokenStream = match code.parse() {
+        Ok(v) => v,
+        Err(e) => return error(format!("{}: invalid generated code: {}", path.display(), e)),
+    };
+
+    // Include the spec and files referenced by it so that the code is
+    // regenerated once any of them is changed.
+    let sources = match sources(path) {
+        Ok(v) => v,
+        Err(e) => return error(e.to_string()),
+    };
+    let sources = sources.iter().map(|v| v.display().to_string());
+
+    quote! {
+        #(const _: &[u8] = include_bytes!(#sources);)*
+
+        #code
+    }
+}
+
+#[cfg(test)]
+mod tests;
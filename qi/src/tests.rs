@@ -0,0 +1,56 @@
+use super::*;
+
+#[test]
+fn expand_spec() {
+    let code = expand(
+        Path::new("../qi_rust/tests/petstore.json"),
+        Span::call_site(),
+    )
+    .to_string();
+
+    assert!(code.contains("include_bytes ! (\"../qi_rust/tests/petstore.json\")"));
+    assert!(code.contains("pub trait Server"));
+    assert!(code.contains("pub struct Client"));
+    assert!(!code.contains("compile_error"));
+}
+
+#[test]
+fn expand_invalid_spec() {
+    let code = expand(
+        Path::new("../qi_openapi/tests/invalid.yaml"),
+        Span::call_site(),
+    )
+    .to_string();
+    assert!(code.contains("compile_error !"));
+    assert!(code.contains("invalid.yaml:"));
+
+    let code = expand(Path::new("missing.yaml"), Span::call_site()).to_string();
+    assert!(code.contains("compile_error !"));
+    assert!(code.contains("missing.yaml: "));
+}
+
+#[test]
+fn expand_unsupported_spec() {
+    // The spec is valid, but its remote references could not be built into
+    // the IR.
+    let code = expand(
+        Path::new("../qi_rust/tests/external/remote.yaml"),
+        Span::call_site(),
+    )
+    .to_string();
+    assert!(code.contains("compile_error !"));
+    assert!(code.contains("remote.yaml:14:15: #/paths/~1pets/get/responses/200/content/application~1json/schema: external reference"));
+}
+
+#[test]
+fn expand_external_spec() {
+    // Relative references are bundled, and their files are tracked.
+    let code = expand(
+        Path::new("../qi_rust/tests/external/api.yaml"),
+        Span::call_site(),
+    )
+    .to_string();
+    assert!(!code.contains("compile_error"));
+    assert!(code.contains("pub struct Pet"));
+    assert!(code.contains("include_bytes ! (\"../qi_rust/tests/external/schemas/pet.yaml\")"));
+}
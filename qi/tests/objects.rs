@@ -0,0 +1,128 @@
+use actix_web::{web, App, HttpServer};
+
+mod api {
+    qi::include_spec!("tests/objects.yaml");
+}
+
+use api::*;
+
+struct Objects;
+
+impl Server for Objects {
+    async fn list_objects(&self, input: ListObjectsInput) -> Result<ListObjectsOutput, BoxError> {
+        Ok(ListObjectsOutput {
+            body: models::Listing {
+                ids: input.ids.unwrap_or_default(),
+                tags: input.tags.unwrap_or_default(),
+                versions: input.x_versions.unwrap_or_default(),
+            },
+        })
+    }
+
+    async fn get_object(&self, input: GetObjectInput) -> Result<GetObjectOutput, BoxError> {
+        Ok(GetObjectOutput {
+            body: models::Object {
+                bucket: input.bucket,
+                key: input.key,
+            },
+        })
+    }
+}
+
+/// Start the server on a random port, and returns its endpoint.
+fn serve() -> (String, actix_web::dev::ServerHandle) {
+    let server = HttpServer::new(|| {
+        App::new()
+            .app_data(web::Data::new(Objects))
+            .configure(configure::<Objects>)
+    })
+    .bind("127.0.0.1:0")
+    .unwrap();
+    let endpoint = format!("http://{}", server.addrs()[0]);
+    let server = server.run();
+    let handle = server.handle();
+    actix_web::rt::spawn(server);
+
+    (endpoint, handle)
+}
+
+#[actix_web::test]
+async fn path_parameters() {
+    let (endpoint, handle) = serve();
+    let client = Client::new(&endpoint);
+
+    for key in ["a b?c", "dir/a#b%20c+d", "100%", "ü"] {
+        let out = client
+            .get_object(GetObjectInput {
+                bucket: "my bucket".to_string(),
+                key: key.to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!("my bucket", out.body.bucket);
+        assert_eq!(key, out.body.key);
+    }
+
+    // Segments are encoded so that they are not split by `/` or `?`.
+    let resp = reqwest::get(format!("{}/buckets/b/objects/a%20b%3Fc", endpoint))
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::json!({"bucket": "b", "key": "a b?c"}),
+        resp.json::<serde_json::Value>().await.unwrap()
+    );
+
+    handle.stop(true).await;
+}
+
+#[actix_web::test]
+async fn query_parameters() {
+    let (endpoint, handle) = serve();
+    let client = Client::new(&endpoint);
+
+    let out = client
+        .list_objects(ListObjectsInput {
+            bucket: "b".to_string(),
+            ids: Some(vec![1, 2, 3]),
+            tags: Some(vec!["a".to_string(), "b c".to_string()]),
+            x_versions: Some(vec![4, 5]),
+        })
+        .await
+        .unwrap();
+    assert_eq!(vec![1, 2, 3], out.body.ids);
+    assert_eq!(vec!["a", "b c"], out.body.tags);
+    assert_eq!(vec![4, 5], out.body.versions);
+
+    let out = client
+        .list_objects(ListObjectsInput {
+            bucket: "b".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(out.body.ids.is_empty());
+    assert!(out.body.tags.is_empty());
+
+    // Arrays in `form` style are exploded, and those in `pipeDelimited`
+    // style are joined by `|`.
+    let resp = reqwest::Client::new()
+        .get(format!(
+            "{}/buckets/b/objects?ids=1&tags=x%7Cy&ids=2",
+            endpoint
+        ))
+        .header("x-versions", "6,7")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::json!({"ids": [1, 2], "tags": ["x", "y"], "versions": [6, 7]}),
+        resp.json::<serde_json::Value>().await.unwrap()
+    );
+
+    let resp = reqwest::get(format!("{}/buckets/b/objects?ids=1,2", endpoint))
+        .await
+        .unwrap();
+    assert_eq!(400, resp.status().as_u16());
+
+    handle.stop(true).await;
+}
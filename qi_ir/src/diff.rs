@@ -0,0 +1,526 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::{Model, ModelKind, Operation, Output, Parameter, Service};
+
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    OperationRemoved,
+    OperationAdded,
+    ParameterAdded,
+    ParameterRemoved,
+    ParameterRequired,
+    ParameterOptional,
+    TypeChanged,
+    EnumNarrowed,
+    EnumWidened,
+    FieldRemoved,
+    FieldAdded,
+    FieldRequired,
+    FieldOptional,
+    StatusCodeChanged,
+}
+
+impl ChangeKind {
+    /// Whether clients generated from the old spec may break, by the
+    /// change of a model used in requests if `input` is set, or responses
+    /// otherwise.
+    ///
+    /// Clients break if they could not send what the server accepts, like
+    /// removed enum values, or could not read what the server responds,
+    /// like removed fields or added enum values.
+    pub fn is_breaking(&self, input: bool) -> bool {
+        match self {
+            ChangeKind::OperationRemoved
+            | ChangeKind::ParameterRemoved
+            | ChangeKind::ParameterRequired
+            | ChangeKind::TypeChanged
+            | ChangeKind::StatusCodeChanged => true,
+            ChangeKind::EnumNarrowed | ChangeKind::FieldRequired => input,
+            ChangeKind::EnumWidened | ChangeKind::FieldRemoved | ChangeKind::FieldOptional => {
+                !input
+            }
+            ChangeKind::OperationAdded
+            | ChangeKind::ParameterAdded
+            | ChangeKind::ParameterOptional
+            | ChangeKind::FieldAdded => false,
+        }
+    }
+}
+
+/// Usage of a model, whether it's sent in requests or responses, or both.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+struct Usage {
+    input: bool,
+    output: bool,
+}
+
+const INPUT: Usage = Usage {
+    input: true,
+    output: false,
+};
+const OUTPUT: Usage = Usage {
+    input: false,
+    output: true,
+};
+/// Models unused by any operation may be used in both ways by other
+/// consumers.
+const BOTH: Usage = Usage {
+    input: true,
+    output: true,
+};
+
+/// Change between two services at `location`, like
+/// `listPets.input.query.limit` or `models.Pet.name`.
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub breaking: bool,
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = if self.breaking {
+            "breaking"
+        } else {
+            "non-breaking"
+        };
+        write!(f, "{}: {}: {}", level, self.location, self.message)
+    }
+}
+
+/// Compare two services, operations are matched by their ids and models by
+/// their names.
+///
+/// Changes of models are classified by how they are used by operations of
+/// the old service, like removed fields only break responses.
+pub fn diff(old: &Service, new: &Service) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    let new_ops: BTreeMap<&str, &Operation> =
+        new.operations.iter().map(|v| (v.id.as_str(), v)).collect();
+    for op in old.operations.iter() {
+        match new_ops.get(op.id.as_str()) {
+            None => push(
+                &mut changes,
+                ChangeKind::OperationRemoved,
+                BOTH,
+                &op.id,
+                format!(
+                    "operation {} {} is removed",
+                    op.method.as_str().to_uppercase(),
+                    op.uri
+                ),
+            ),
+            Some(v) => diff_operation(op, v, &mut changes),
+        }
+    }
+    for op in new.operations.iter() {
+        if !old.operations.iter().any(|v| v.id == op.id) {
+            push(
+                &mut changes,
+                ChangeKind::OperationAdded,
+                BOTH,
+                &op.id,
+                format!(
+                    "operation {} {} is added",
+                    op.method.as_str().to_uppercase(),
+                    op.uri
+                ),
+            );
+        }
+    }
+
+    let usages = usages(old);
+    for (name, model) in old.models.iter() {
+        // Removed models are reported by the operations using them.
+        if let Some(v) = new.models.get(name) {
+            let usage = usages.get(name).copied().unwrap_or(BOTH);
+            diff_model(model, v, usage, &format!("models.{}", name), &mut changes);
+        }
+    }
+
+    changes
+}
+
+/// Returns the usages of models referenced by operations transitively.
+fn usages(srv: &Service) -> BTreeMap<String, Usage> {
+    let mut usages: BTreeMap<String, Usage> = BTreeMap::new();
+
+    for op in srv.operations.iter() {
+        let outputs = std::iter::once(&op.output).chain(op.errors.iter());
+        let inputs = op
+            .input
+            .path
+            .iter()
+            .chain(op.input.query.iter())
+            .chain(op.input.header.iter())
+            .chain(op.input.cookie.iter())
+            .chain(op.input.encoding.values().flat_map(|v| v.headers.iter()))
+            .map(|v| (&v.model, INPUT))
+            .chain(op.input.body.iter().map(|v| (v, INPUT)));
+        let outputs = outputs.flat_map(|v| {
+            v.header
+                .iter()
+                .map(|v| (&v.model, OUTPUT))
+                .chain(v.body.iter().map(|v| (v, OUTPUT)))
+        });
+
+        for (m, usage) in inputs.chain(outputs) {
+            let mut used = BTreeSet::new();
+            srv.collect_models(m, &mut used);
+            for name in used {
+                let v = usages.entry(name).or_default();
+                v.input |= usage.input;
+                v.output |= usage.output;
+            }
+        }
+    }
+
+    usages
+}
+
+/// Push a change, which is breaking if it breaks any usage.
+fn push(
+    changes: &mut Vec<Change>,
+    kind: ChangeKind,
+    usage: Usage,
+    location: &str,
+    message: String,
+) {
+    changes.push(Change {
+        kind,
+        breaking: (usage.input && kind.is_breaking(true))
+            || (usage.output && kind.is_breaking(false)),
+        location: location.to_string(),
+        message,
+    })
+}
+
+fn diff_operation(old: &Operation, new: &Operation, changes: &mut Vec<Change>) {
+    let input = format!("{}.input", old.id);
+    diff_parameters(
+        &old.input.path,
+        &new.input.path,
+        &format!("{}.path", input),
+        changes,
+    );
+    diff_parameters(
+        &old.input.query,
+        &new.input.query,
+        &format!("{}.query", input),
+        changes,
+    );
+    diff_parameters(
+        &old.input.header,
+        &new.input.header,
+        &format!("{}.header", input),
+        changes,
+    );
+    diff_parameters(
+        &old.input.cookie,
+        &new.input.cookie,
+        &format!("{}.cookie", input),
+        changes,
+    );
+    diff_body(
+        &old.input.body,
+        &new.input.body,
+        &format!("{}.body", input),
+        true,
+        changes,
+    );
+
+    if old.expect != new.expect {
+        push(
+            changes,
+            ChangeKind::StatusCodeChanged,
+            BOTH,
+            &format!("{}.output", old.id),
+            format!(
+                "status codes changed from {:?} to {:?}",
+                old.expect, new.expect
+            ),
+        );
+    }
+    diff_output(
+        &old.output,
+        &new.output,
+        &format!("{}.output", old.id),
+        changes,
+    );
+}
+
+fn diff_parameters(
+    old: &[Parameter],
+    new: &[Parameter],
+    location: &str,
+    changes: &mut Vec<Change>,
+) {
+    for p in old {
+        let location = format!("{}.{}", location, p.name);
+        match new.iter().find(|v| v.name == p.name) {
+            None => push(
+                changes,
+                ChangeKind::ParameterRemoved,
+                INPUT,
+                &location,
+                format!("parameter {} is removed", p.name),
+            ),
+            Some(v) => {
+                if !p.mandatory && v.mandatory {
+                    push(
+                        changes,
+                        ChangeKind::ParameterRequired,
+                        INPUT,
+                        &location,
+                        format!("parameter {} becomes required", p.name),
+                    );
+                }
+                if p.mandatory && !v.mandatory {
+                    push(
+                        changes,
+                        ChangeKind::ParameterOptional,
+                        INPUT,
+                        &location,
+                        format!("parameter {} becomes optional", p.name),
+                    );
+                }
+                diff_model(&p.model, &v.model, INPUT, &location, changes);
+            }
+        }
+    }
+    for p in new {
+        if old.iter().any(|v| v.name == p.name) {
+            continue;
+        }
+        let (kind, message) = if p.mandatory {
+            (
+                ChangeKind::ParameterRequired,
+                format!("required parameter {} is added", p.name),
+            )
+        } else {
+            (
+                ChangeKind::ParameterAdded,
+                format!("optional parameter {} is added", p.name),
+            )
+        };
+        push(
+            changes,
+            kind,
+            INPUT,
+            &format!("{}.{}", location, p.name),
+            message,
+        );
+    }
+}
+
+fn diff_output(old: &Output, new: &Output, location: &str, changes: &mut Vec<Change>) {
+    for h in old.header.iter() {
+        let location = format!("{}.header.{}", location, h.name);
+        match new.header.iter().find(|v| v.name == h.name) {
+            None => push(
+                changes,
+                ChangeKind::FieldRemoved,
+                OUTPUT,
+                &location,
+                format!("response header {} is removed", h.name),
+            ),
+            Some(v) => diff_model(&h.model, &v.model, OUTPUT, &location, changes),
+        }
+    }
+    diff_body(
+        &old.body,
+        &new.body,
+        &format!("{}.body", location),
+        false,
+        changes,
+    );
+}
+
+/// Request bodies are compared like parameters, and response bodies like
+/// fields.
+fn diff_body(
+    old: &Option<Model>,
+    new: &Option<Model>,
+    location: &str,
+    input: bool,
+    changes: &mut Vec<Change>,
+) {
+    let (removed, added, usage) = if input {
+        (
+            ChangeKind::ParameterRemoved,
+            ChangeKind::ParameterRequired,
+            INPUT,
+        )
+    } else {
+        (ChangeKind::FieldRemoved, ChangeKind::FieldAdded, OUTPUT)
+    };
+
+    match (old, new) {
+        (Some(old), Some(new)) => diff_model(old, new, usage, location, changes),
+        (Some(_), None) => push(
+            changes,
+            removed,
+            usage,
+            location,
+            "body is removed".to_string(),
+        ),
+        (None, Some(_)) => push(changes, added, usage, location, "body is added".to_string()),
+        (None, None) => {}
+    }
+}
+
+/// Compare two models used as `usage`, references are compared by names
+/// only since the referenced models are compared under `models`.
+fn diff_model(old: &Model, new: &Model, usage: Usage, location: &str, changes: &mut Vec<Change>) {
+    let (old_type, new_type) = (type_name(old), type_name(new));
+    if old_type != new_type {
+        push(
+            changes,
+            ChangeKind::TypeChanged,
+            usage,
+            location,
+            format!("type changed from {} to {}", old_type, new_type),
+        );
+        return;
+    }
+
+    if let (Some(old), Some(new)) = (old.element.as_ref(), new.element.as_ref()) {
+        diff_model(old, new, usage, &format!("{}[]", location), changes);
+    }
+
+    for (name, m) in old.properties.iter().flatten() {
+        let location = format!("{}.{}", location, name);
+        let v = match new.properties.as_ref().and_then(|v| v.get(name)) {
+            None => {
+                push(
+                    changes,
+                    ChangeKind::FieldRemoved,
+                    usage,
+                    &location,
+                    format!("field {} is removed", name),
+                );
+                continue;
+            }
+            Some(v) => v,
+        };
+        match (old.required.contains(name), new.required.contains(name)) {
+            (false, true) => push(
+                changes,
+                ChangeKind::FieldRequired,
+                usage,
+                &location,
+                format!("field {} becomes required", name),
+            ),
+            (true, false) => push(
+                changes,
+                ChangeKind::FieldOptional,
+                usage,
+                &location,
+                format!("field {} becomes optional", name),
+            ),
+            _ => {}
+        }
+        diff_model(m, v, usage, &location, changes);
+    }
+    for name in new.properties.iter().flat_map(|v| v.keys()) {
+        if old
+            .properties
+            .as_ref()
+            .is_some_and(|v| v.contains_key(name))
+        {
+            continue;
+        }
+        let (kind, message) = if new.required.contains(name) {
+            (
+                ChangeKind::FieldRequired,
+                format!("required field {} is added", name),
+            )
+        } else {
+            (
+                ChangeKind::FieldAdded,
+                format!("optional field {} is added", name),
+            )
+        };
+        push(
+            changes,
+            kind,
+            usage,
+            &format!("{}.{}", location, name),
+            message,
+        );
+    }
+
+    match (old.values.as_ref(), new.values.as_ref()) {
+        // Values are no longer restricted.
+        (Some(_), None) => push(
+            changes,
+            ChangeKind::EnumWidened,
+            usage,
+            location,
+            "values are no longer restricted by enum".to_string(),
+        ),
+        (None, Some(_)) => push(
+            changes,
+            ChangeKind::EnumNarrowed,
+            usage,
+            location,
+            "values are restricted by enum".to_string(),
+        ),
+        (Some(old), Some(new)) => {
+            let removed: Vec<String> = old
+                .iter()
+                .filter(|v| !new.contains(v))
+                .map(|v| v.to_string())
+                .collect();
+            let added: Vec<String> = new
+                .iter()
+                .filter(|v| !old.contains(v))
+                .map(|v| v.to_string())
+                .collect();
+            if !removed.is_empty() {
+                push(
+                    changes,
+                    ChangeKind::EnumNarrowed,
+                    usage,
+                    location,
+                    format!("enum values {} are removed", removed.join(", ")),
+                );
+            }
+            if !added.is_empty() {
+                push(
+                    changes,
+                    ChangeKind::EnumWidened,
+                    usage,
+                    location,
+                    format!("enum values {} are added", added.join(", ")),
+                );
+            }
+        }
+        (None, None) => {}
+    }
+}
+
+/// Name of the model's own type without its element and properties, like
+/// `int64`, `array` or `Pet` for references.
+fn type_name(m: &Model) -> String {
+    let name = match m.kind {
+        ModelKind::Reference => m.name.clone().unwrap_or_default(),
+        kind => serde_json::to_value(kind)
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string(),
+    };
+
+    if m.nullable {
+        format!("{}?", name)
+    } else {
+        name
+    }
+}
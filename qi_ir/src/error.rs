@@ -0,0 +1,38 @@
+use std::fmt;
+
+/// Error is a problem which stops a spec from being normalized, like a
+/// reference to a missing component.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    /// JSON pointer to the value which has this error.
+    pub pointer: String,
+    pub message: String,
+}
+
+impl Error {
+    pub(crate) fn new(pointer: &str, message: impl Into<String>) -> Error {
+        Error {
+            pointer: pointer.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}: {}", self.pointer, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Append segments to a JSON pointer, escaping `~` and `/` in them.
+pub(crate) fn pointer(base: &str, segments: &[&str]) -> String {
+    let mut ptr = base.to_string();
+    for v in segments {
+        ptr.push('/');
+        ptr.push_str(&v.replace('~', "~0").replace('/', "~1"));
+    }
+
+    ptr
+}
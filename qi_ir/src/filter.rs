@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use crate::{Method, Model, ModelKind, Operation, Service};
+
+/// Filter selects operations to keep in a service, like:
+///
+/// ```toml
+/// include = [{ tags = ["bucket"] }, { operation_id = "*Object" }]
+/// exclude = [{ path_prefix = "/admin", methods = ["delete"] }]
+/// ```
+///
+/// Operations are kept if they match any rule in `include`, or `include` is
+/// empty, and match no rule in `exclude`.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Filter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<Rule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<Rule>,
+}
+
+/// Rule matches operations by all criteria set in it, a rule without any
+/// criteria matches all operations.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Matches operations with any of these tags.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Glob of `operationId`, `*` matches any characters and `?` matches
+    /// one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<String>,
+    /// Matches operations whose path starts with it, like `/buckets`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_prefix: Option<String>,
+    /// Matches operations with any of these methods.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<Method>,
+}
+
+impl Filter {
+    /// Whether nothing will be filtered out.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn matches(&self, op: &Operation) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|v| v.matches(op)))
+            && !self.exclude.iter().any(|v| v.matches(op))
+    }
+}
+
+impl Rule {
+    pub fn matches(&self, op: &Operation) -> bool {
+        let mut tags = op.tags.iter().flatten();
+
+        (self.tags.is_empty() || tags.any(|v| self.tags.contains(v)))
+            && self
+                .operation_id
+                .as_ref()
+                .is_none_or(|v| glob_match(v, &op.id))
+            && self
+                .path_prefix
+                .as_ref()
+                .is_none_or(|v| op.uri.starts_with(v.as_str()))
+            && (self.methods.is_empty() || self.methods.contains(&op.method))
+    }
+}
+
+impl Service {
+    /// Keep operations matched by the filter, and remove models which are
+    /// not used by them anymore.
+    ///
+    /// Nothing is changed if the filter is empty, so that models unused by
+    /// any operation are still kept by default.
+    pub fn filter(&mut self, filter: &Filter) {
+        if filter.is_empty() {
+            return;
+        }
+
+        self.operations.retain(|op| filter.matches(op));
+
+        let mut used = BTreeSet::new();
+        for op in self.operations.iter() {
+            for m in op.models() {
+                self.collect_models(m, &mut used);
+            }
+        }
+        self.models.retain(|k, _| used.contains(k));
+    }
+
+    /// Collect names of models referenced by the model transitively.
+    pub(crate) fn collect_models(&self, m: &Model, used: &mut BTreeSet<String>) {
+        if m.kind == ModelKind::Reference {
+            let name = m.name.as_ref().unwrap();
+            if !used.insert(name.clone()) {
+                return;
+            }
+            if let Some(v) = self.models.get(name) {
+                self.collect_models(v, used);
+            }
+        }
+
+        for v in m
+            .properties
+            .iter()
+            .flat_map(|v| v.values())
+            .chain(m.element.as_deref())
+        {
+            self.collect_models(v, used);
+        }
+    }
+}
+
+/// Match text against a glob with `*` and `?`.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+
+    // Position after the last `*` in pattern, and the position in text it
+    // is matched to, which will be extended on mismatches.
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        match p.get(pi) {
+            Some('*') => {
+                pi += 1;
+                star = Some((pi, ti));
+            }
+            Some(c) if *c == '?' || *c == t[ti] => {
+                pi += 1;
+                ti += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    pi = sp;
+                    ti = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    p[pi..].iter().all(|v| *v == '*')
+}
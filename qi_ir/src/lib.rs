@@ -0,0 +1,47 @@
+//! qi_ir normalizes OpenAPI specs into services which are consumed by
+//! generators, linters and other tools.
+//!
+//! ```ignore
+//! let spec = qi_openapi::v3::from_yaml_reader(file)?;
+//! let srv = qi_ir::Builder::new(spec).build()?;
+//! ```
+
+pub mod diff;
+mod error;
+mod filter;
+mod model;
+mod security;
+mod service;
+
+pub use error::Error;
+pub use filter::*;
+pub use model::*;
+pub use qi_openapi::v3::Method;
+pub use security::*;
+pub use service::*;
+
+use serde::Serialize;
+
+#[cfg(test)]
+mod tests;
+
+/// Version of the IR dumped by `qi ir`, which will be bumped on
+/// incompatible changes. The schema is documented in `IR.md`.
+pub const IR_VERSION: u32 = 1;
+
+/// The dumped IR, which is the service with its version.
+#[derive(Serialize)]
+pub struct Ir<'a> {
+    pub version: u32,
+    #[serde(flatten)]
+    pub service: &'a Service,
+}
+
+impl<'a> Ir<'a> {
+    pub fn new(service: &'a Service) -> Ir<'a> {
+        Ir {
+            version: IR_VERSION,
+            service,
+        }
+    }
+}
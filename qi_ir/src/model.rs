@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use qi_openapi::v3::{self, Schema, SchemaType};
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelKind {
+    // Any used for dynamic type
+    Any,
+    Boolean,
+    // UTF-8 string
+    String,
+    Byte,
+
+    Date,
+    Time,
+    Datetime,
+
+    Int,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Uint,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+
+    Float32,
+    Float64,
+
+    Array,
+    Map,
+    Enum,
+    Struct,
+    Iterator,
+
+    Reference,
+}
+
+impl ModelKind {
+    pub fn is_simple(&self) -> bool {
+        !matches!(
+            self,
+            ModelKind::Any
+                | ModelKind::Array
+                | ModelKind::Map
+                | ModelKind::Enum
+                | ModelKind::Struct
+                | ModelKind::Iterator
+                | ModelKind::Reference
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub struct Model {
+    #[serde(rename = "type")]
+    pub kind: ModelKind,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<Annotation>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub properties: Option<BTreeMap<String, Model>>,
+    /// Names of required properties from `required`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub element: Option<Box<Model>>,
+    /// Allowed values from `enum`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<Value>>,
+
+    /// Whether `null` is allowed, from `nullable: true` in 3.0, or unions
+    /// with `null` in 3.1.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub nullable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Model {
+    pub fn new(kind: ModelKind) -> Model {
+        Model {
+            kind,
+            annotation: None,
+            name: None,
+            properties: None,
+            required: Vec::new(),
+            element: None,
+            values: None,
+            nullable: false,
+            description: None,
+        }
+    }
+
+    /// Path of the existing type mapped by `x-qi-type`.
+    pub fn type_path(&self) -> Option<&str> {
+        self.annotation
+            .as_ref()
+            .and_then(|v| v.type_path.as_deref())
+    }
+
+    /// Name override set by `x-qi-name`.
+    pub fn display(&self) -> Option<&str> {
+        self.annotation.as_ref().and_then(|v| v.display.as_deref())
+    }
+
+    /// Whether the schema, property or parameter is deprecated.
+    pub fn deprecated(&self) -> bool {
+        self.annotation.as_ref().is_some_and(|v| v.deprecated)
+    }
+
+    /// A stream of bytes which is used for `format: binary` and raw bodies.
+    pub fn byte_stream() -> Model {
+        Model {
+            element: Some(Box::new(Model::new(ModelKind::Byte))),
+            ..Model::new(ModelKind::Iterator)
+        }
+    }
+}
+
+/// Annotation carries hints for generators which are not part of the type.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Annotation {
+    /// Name used in generated code instead of the one derived from spec.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display: Option<String>,
+    /// Path of an existing type used instead of generating one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_path: Option<String>,
+    /// Extra traits derived by the generated type.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub derives: Vec<String>,
+    /// Whether it's marked by `deprecated: true`.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub deprecated: bool,
+}
+
+// Vendor extensions honored by qi, which are documented for users in the
+// crate docs of qi_rust:
+//
+//   - `x-qi-name`: on schemas, properties, parameters and operations,
+//     overrides the name of the generated type, field or function.
+//   - `x-qi-type`: on schemas, maps the schema to an existing type like
+//     `uuid::Uuid`, no type will be generated for it.
+//   - `x-qi-skip`: on operations, omits the operation if `true`.
+//   - `x-qi-derive`: on schemas, a list of extra traits to derive, like
+//     `[PartialEq, Eq]`.
+pub const X_QI_NAME: &str = "x-qi-name";
+pub const X_QI_TYPE: &str = "x-qi-type";
+pub const X_QI_SKIP: &str = "x-qi-skip";
+pub const X_QI_DERIVE: &str = "x-qi-derive";
+
+pub(crate) fn extension_str(extensions: &v3::Extensions, key: &str) -> Option<String> {
+    extensions
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+fn parse_annotation(schema: &Schema) -> Option<Annotation> {
+    let derives = match schema.extensions.get(X_QI_DERIVE) {
+        None => vec![],
+        Some(Value::String(v)) => v.split(',').map(|v| v.trim().to_string()).collect(),
+        Some(Value::Array(v)) => v
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        // Invalid values are rejected by the builder with their location.
+        Some(_) => vec![],
+    };
+
+    let annotation = Annotation {
+        display: extension_str(&schema.extensions, X_QI_NAME),
+        type_path: extension_str(&schema.extensions, X_QI_TYPE),
+        derives,
+        deprecated: schema.deprecated.unwrap_or(false),
+    };
+    if annotation == Annotation::default() {
+        return None;
+    }
+
+    Some(annotation)
+}
+
+pub(crate) fn collect_defs<'a>(schema: &'a Schema, defs: &mut Vec<(&'a String, &'a Schema)>) {
+    for (name, schema) in schema.defs.iter().flatten() {
+        collect_defs(schema, defs);
+        defs.push((name, schema));
+    }
+}
+
+pub(crate) fn parse_ref(s: &str) -> String {
+    s.split('/').next_back().unwrap().to_string()
+}
+
+/// Returns the non-null member of unions like
+/// `anyOf: [{$ref: '#/components/schemas/Pet'}, {type: 'null'}]`.
+fn nullable_union(schema: &Schema) -> Option<&Schema> {
+    let members = schema.any_of.as_ref().or(schema.one_of.as_ref())?;
+    let is_null = |v: &Schema| v.ref_.is_none() && v.types().is_empty() && v.is_nullable();
+
+    match members.as_slice() {
+        [v, null] | [null, v] if is_null(null) && !is_null(v) => Some(v),
+        _ => None,
+    }
+}
+
+/// Infer the type of a schema with only `const`, like `const: foo`.
+fn const_type(schema: &Schema) -> Option<&'static SchemaType> {
+    let typ = match schema.const_.as_ref()? {
+        Value::Bool(_) => &SchemaType::Boolean,
+        Value::String(_) => &SchemaType::String,
+        Value::Number(v) if v.is_f64() => &SchemaType::Number,
+        Value::Number(_) => &SchemaType::Integer,
+        _ => return None,
+    };
+
+    Some(typ)
+}
+
+pub(crate) fn parse_schema_type(schema: &Schema) -> Model {
+    if let Some(v) = nullable_union(schema) {
+        let mut model = parse_schema_type(v);
+        model.nullable = true;
+        return model;
+    }
+
+    if let Some(r) = schema.ref_.as_ref() {
+        return Model {
+            name: Some(parse_ref(r)),
+            annotation: parse_annotation(schema),
+            nullable: schema.is_nullable(),
+            description: schema.description.clone(),
+            ..Model::new(ModelKind::Reference)
+        };
+    }
+
+    let mut model = Model {
+        annotation: parse_annotation(schema),
+        values: schema.enum_.clone(),
+        nullable: schema.is_nullable(),
+        description: schema.description.clone(),
+        ..Model::new(ModelKind::Any)
+    };
+
+    let schema_type = match schema.types().as_slice() {
+        [v] => *v,
+        [] => match const_type(schema) {
+            Some(v) => v,
+            None => return model,
+        },
+        // Unions of multiple types like `[string, integer]` are dynamic.
+        _ => return model,
+    };
+
+    match schema_type {
+        SchemaType::Boolean => model.kind = ModelKind::Boolean,
+        SchemaType::Object => {
+            model.kind = ModelKind::Struct;
+
+            if let Some(props) = schema.properties.as_ref() {
+                let mut m = BTreeMap::new();
+
+                for (name, property) in props {
+                    m.insert(name.to_string(), parse_schema_type(property));
+                }
+
+                model.properties = Some(m);
+            }
+            model.required = schema.required.clone().unwrap_or_default();
+        }
+        SchemaType::Array => {
+            model.kind = ModelKind::Array;
+
+            // Tuples defined by `prefixItems` are treated as arrays of any.
+            let element = match schema.items.as_ref() {
+                None => Model::new(ModelKind::Any),
+                Some(items) => parse_schema_type(items.as_ref()),
+            };
+            model.element = Some(Box::new(element));
+        }
+        SchemaType::Number => match schema.format.as_ref() {
+            None => model.kind = ModelKind::Float32,
+            Some(v) => match v.as_str() {
+                "double" => model.kind = ModelKind::Float64,
+                _ => model.kind = ModelKind::Float32,
+            },
+        },
+        SchemaType::String => {
+            model.kind = ModelKind::String;
+
+            // Binary contents are described by `contentMediaType` in 3.1.
+            // TODO: base64 encoded characters of `format: byte`
+            if schema.format.as_deref() == Some("binary")
+                || schema.content_media_type.as_deref() == Some("application/octet-stream")
+            {
+                // Keep annotations, enum and nullable of the property.
+                let stream = Model::byte_stream();
+                model.kind = stream.kind;
+                model.element = stream.element;
+            }
+        }
+        SchemaType::Integer => {
+            match schema.format.as_ref() {
+                None => model.kind = ModelKind::Int,
+                Some(v) => {
+                    match v.as_str() {
+                        "int32" => model.kind = ModelKind::Int32,
+                        "int64" => model.kind = ModelKind::Int64,
+                        _ => model.kind = ModelKind::Int,
+                    };
+                }
+            };
+        }
+        SchemaType::Null => {}
+    };
+
+    model
+}
@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use qi_openapi::v3;
+
+pub use qi_openapi::v3::SecurityRequirement;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Copy, Clone)]
+pub enum SecurityKind {
+    #[serde(rename = "apiKey")]
+    ApiKey,
+    #[serde(rename = "http")]
+    Http,
+    #[serde(rename = "oauth2")]
+    OAuth2,
+    #[serde(rename = "openIdConnect")]
+    OpenIdConnect,
+}
+
+/// Where the key of an `apiKey` scheme is sent.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyLocation {
+    Header,
+    Query,
+    Cookie,
+}
+
+/// SecurityScheme tells how credentials of requests are sent, fields which
+/// do not belong to its kind are not set.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub struct SecurityScheme {
+    #[serde(rename = "type")]
+    pub kind: SecurityKind,
+    /// Name of the header, query parameter or cookie of `apiKey`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<KeyLocation>,
+    /// Lowercase scheme of `http` like `basic` or `bearer`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bearer_format: Option<String>,
+    /// Flows of `oauth2` keyed by names like `clientCredentials`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub flows: BTreeMap<String, OAuthFlow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_id_connect_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub struct OAuthFlow {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorization_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_url: Option<String>,
+    /// Descriptions of scopes keyed by their names.
+    pub scopes: BTreeMap<String, String>,
+}
+
+/// Returns the security scheme, or `None` if its type is unknown or not
+/// supported like `mutualTLS`.
+pub(crate) fn parse_security_scheme(v: &v3::SecurityScheme) -> Option<SecurityScheme> {
+    let kind = match v.type_.as_deref()? {
+        "apiKey" => SecurityKind::ApiKey,
+        "http" => SecurityKind::Http,
+        "oauth2" => SecurityKind::OAuth2,
+        "openIdConnect" => SecurityKind::OpenIdConnect,
+        _ => return None,
+    };
+    let mut scheme = SecurityScheme {
+        kind,
+        name: None,
+        location: None,
+        scheme: None,
+        bearer_format: None,
+        flows: BTreeMap::new(),
+        open_id_connect_url: None,
+        description: v.description.clone(),
+    };
+
+    match kind {
+        SecurityKind::ApiKey => {
+            scheme.name = v.name.clone();
+            scheme.location = match v.in_.as_deref() {
+                Some("header") => Some(KeyLocation::Header),
+                Some("query") => Some(KeyLocation::Query),
+                Some("cookie") => Some(KeyLocation::Cookie),
+                _ => None,
+            };
+        }
+        SecurityKind::Http => {
+            // Schemes are case-insensitive by RFC 7235.
+            scheme.scheme = v.scheme.as_ref().map(|v| v.to_ascii_lowercase());
+            scheme.bearer_format = v.bearer_format.clone();
+        }
+        SecurityKind::OAuth2 => {
+            let flows = v.flows.clone().unwrap_or_default();
+            let flows = [
+                ("implicit", flows.implicit),
+                ("password", flows.password),
+                ("clientCredentials", flows.client_credentials),
+                ("authorizationCode", flows.authorization_code),
+            ];
+            for (name, flow) in flows {
+                if let Some(flow) = flow {
+                    scheme.flows.insert(
+                        name.to_string(),
+                        OAuthFlow {
+                            authorization_url: flow.authorization_url,
+                            token_url: flow.token_url,
+                            refresh_url: flow.refresh_url,
+                            scopes: flow.scopes,
+                        },
+                    );
+                }
+            }
+        }
+        SecurityKind::OpenIdConnect => {
+            scheme.open_id_connect_url = v.open_id_connect_url.clone();
+        }
+    }
+
+    Some(scheme)
+}
@@ -0,0 +1,941 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use qi_openapi::v3::{self, Method, Reference, Schema, Spec};
+
+use crate::error::{pointer, Error};
+use crate::filter::Filter;
+use crate::model::*;
+use crate::security::*;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub struct Service {
+    /// Models of component schemas keyed by their names.
+    pub models: BTreeMap<String, Model>,
+    pub operations: Vec<Operation>,
+
+    /// Component parameters keyed by their names.
+    pub parameters: BTreeMap<String, Parameter>,
+    /// Component security schemes keyed by their names.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub security_schemes: BTreeMap<String, SecurityScheme>,
+    #[serde(skip)]
+    spec: Spec,
+}
+
+/// Builder normalizes a spec into a service.
+pub struct Builder {
+    spec: Spec,
+    filter: Filter,
+}
+
+impl Builder {
+    pub fn new(spec: Spec) -> Builder {
+        Builder {
+            spec,
+            filter: Filter::default(),
+        }
+    }
+
+    /// Keep only operations matched by the filter, and models used by them.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Build the service, or returns the error of the first value which
+    /// could not be normalized.
+    pub fn build(self) -> Result<Service, Error> {
+        let mut srv = Service {
+            models: Default::default(),
+            operations: vec![],
+            parameters: Default::default(),
+            security_schemes: Default::default(),
+            spec: self.spec,
+        };
+
+        srv.format_models()?;
+        srv.format_parameters()?;
+        srv.format_security_schemes()?;
+        srv.format_operations()?;
+        srv.resolve_dynamic();
+        srv.filter(&self.filter);
+
+        Ok(srv)
+    }
+}
+
+impl Service {
+    /// Build a service from spec with default options.
+    pub fn new(spec: Spec) -> Result<Service, Error> {
+        Builder::new(spec).build()
+    }
+
+    /// The spec which this service is built from.
+    pub fn spec(&self) -> &Spec {
+        &self.spec
+    }
+
+    /// Returns all models in the service, which are models of components,
+    /// and those of component parameters and operations. Models nested in
+    /// them like properties are not included.
+    pub fn all_models_mut(&mut self) -> impl Iterator<Item = &mut Model> {
+        self.models
+            .values_mut()
+            .chain(self.parameters.values_mut().map(|v| &mut v.model))
+            .chain(self.operations.iter_mut().flat_map(Operation::models_mut))
+    }
+
+    /// Replace references to schemas without type, which are not stored as
+    /// models, with dynamic values, so that every reference in the service
+    /// points to a model.
+    fn resolve_dynamic(&mut self) {
+        let names: BTreeSet<String> = self.models.keys().cloned().collect();
+        for m in self.all_models_mut() {
+            resolve_dynamic(m, &names);
+        }
+    }
+
+    fn format_security_schemes(&mut self) -> Result<(), Error> {
+        let schemes = self
+            .spec
+            .components
+            .as_ref()
+            .and_then(|c| c.security_schemes.as_ref());
+
+        let mut ss = BTreeMap::new();
+        for (name, scheme) in schemes.into_iter().flatten() {
+            let ptr = pointer("", &["components", "securitySchemes", name]);
+            let (scheme, _) = self.resolve(scheme, &ptr, |c| c.security_schemes.as_ref())?;
+            if let Some(v) = parse_security_scheme(scheme) {
+                ss.insert(name.clone(), v);
+            }
+        }
+
+        self.security_schemes = ss;
+        Ok(())
+    }
+
+    fn format_parameters(&mut self) -> Result<(), Error> {
+        let mut gp = BTreeMap::new();
+
+        let params = self
+            .spec
+            .components
+            .as_ref()
+            .and_then(|c| c.parameters.as_ref());
+        for (name, param) in params.into_iter().flatten() {
+            let ptr = pointer("", &["components", "parameters", name]);
+            let (param, ptr) = self.resolve(param, &ptr, |c| c.parameters.as_ref())?;
+            gp.insert(name.to_string(), self.parse_parameter(param, &ptr)?);
+        }
+
+        self.parameters = gp;
+        Ok(())
+    }
+
+    /// Parse a resolved parameter at `ptr`.
+    fn parse_parameter(&self, param: &v3::Parameter, ptr: &str) -> Result<Parameter, Error> {
+        check_extensions(&param.extensions, ptr)?;
+        let name = match &param.name {
+            Some(v) => v.clone(),
+            None => return Err(Error::new(ptr, "parameter requires name")),
+        };
+        let m = self.parse_param_schema(param.schema.as_ref(), param.content.as_ref(), ptr)?;
+
+        let mut model = self.deref_model(&m);
+        // The name override of a parameter is set on the parameter object
+        // instead of its schema.
+        if let Some(v) = extension_str(&param.extensions, X_QI_NAME) {
+            model
+                .annotation
+                .get_or_insert_with(Default::default)
+                .display = Some(v);
+        }
+        if param.deprecated == Some(true) {
+            model
+                .annotation
+                .get_or_insert_with(Default::default)
+                .deprecated = true;
+        }
+
+        // Query and cookie parameters are in `form` style by default, and
+        // the others are in `simple` style.
+        let style = param.style.clone().unwrap_or_else(|| {
+            match param.in_.as_deref() {
+                Some("query") | Some("cookie") => "form",
+                _ => "simple",
+            }
+            .to_string()
+        });
+        let explode = param.explode.unwrap_or(style == "form");
+
+        Ok(Parameter {
+            name,
+            model,
+            mandatory: param.required.unwrap_or(false),
+            description: param.description.clone(),
+            style,
+            explode,
+        })
+    }
+
+    fn parse_header(&self, name: &str, header: &v3::Header, ptr: &str) -> Result<Parameter, Error> {
+        let (header, ptr) = self.resolve(header, ptr, |c| c.headers.as_ref())?;
+        let m = self.parse_param_schema(header.schema.as_ref(), header.content.as_ref(), &ptr)?;
+
+        let mut model = self.deref_model(&m);
+        if header.deprecated == Some(true) {
+            model
+                .annotation
+                .get_or_insert_with(Default::default)
+                .deprecated = true;
+        }
+
+        Ok(Parameter {
+            name: name.to_string(),
+            model,
+            mandatory: header.required.unwrap_or(false),
+            description: header.description.clone(),
+            style: header.style.clone().unwrap_or_else(|| "simple".to_string()),
+            explode: header.explode.unwrap_or(false),
+        })
+    }
+
+    /// Parse the schema of a parameter or header, which is set by `schema`
+    /// or the only media type in `content`.
+    fn parse_param_schema(
+        &self,
+        schema: Option<&Schema>,
+        content: Option<&BTreeMap<String, v3::MediaType>>,
+        ptr: &str,
+    ) -> Result<Model, Error> {
+        if let Some(v) = schema {
+            self.check_schema(v, &pointer(ptr, &["schema"]))?;
+            return Ok(self.parse_schema(v));
+        }
+
+        let (content_type, media_type) = match content.and_then(|v| v.iter().next()) {
+            Some(v) => v,
+            None => return Err(Error::new(ptr, "schema or content is required")),
+        };
+        match media_type.schema.as_ref() {
+            Some(v) => {
+                self.check_schema(v, &pointer(ptr, &["content", content_type, "schema"]))?;
+                Ok(self.parse_schema(v))
+            }
+            None => Ok(Model::new(ModelKind::Any)),
+        }
+    }
+
+    /// Check a schema at `ptr` and its subschemas: references point to
+    /// schemas in the components or `$defs`, so that no model refers to a
+    /// missing one, and vendor extensions are valid.
+    fn check_schema(&self, schema: &Schema, ptr: &str) -> Result<(), Error> {
+        check_extensions(&schema.extensions, ptr)?;
+        if let Some(r) = schema.ref_.as_deref() {
+            if !r.starts_with("#/") {
+                return Err(Error::new(
+                    ptr,
+                    format!("external reference {} is not supported", r),
+                ));
+            }
+            if self.find_schema(r).is_none() {
+                return Err(Error::new(ptr, format!("reference {} is not found", r)));
+            }
+        }
+
+        let mut children: Vec<(String, &Schema)> = Vec::new();
+        for (key, schemas) in [("properties", &schema.properties), ("$defs", &schema.defs)] {
+            for (name, v) in schemas.iter().flatten() {
+                children.push((pointer(ptr, &[key, name]), v));
+            }
+        }
+        for (key, schemas) in [
+            ("allOf", &schema.all_of),
+            ("oneOf", &schema.one_of),
+            ("anyOf", &schema.any_of),
+            ("prefixItems", &schema.prefix_items),
+        ] {
+            for (i, v) in schemas.iter().flatten().enumerate() {
+                children.push((pointer(ptr, &[key, &i.to_string()]), v));
+            }
+        }
+        if let Some(v) = schema.items.as_deref() {
+            children.push((pointer(ptr, &["items"]), v));
+        }
+        if let Some(v) = schema.not.as_deref() {
+            children.push((pointer(ptr, &["not"]), v));
+        }
+        if let Some(v3::AdditionalProperties::Schema(v)) = schema.additional_properties.as_ref() {
+            children.push((pointer(ptr, &["additionalProperties"]), v));
+        }
+
+        for (ptr, v) in children {
+            self.check_schema(v, &ptr)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the schema referred by `r` in the components or `$defs`,
+    /// which are referred by their names.
+    fn find_schema(&self, r: &str) -> Option<&Schema> {
+        let schemas = self
+            .spec
+            .components
+            .as_ref()
+            .and_then(|c| c.schemas.as_ref())?;
+        let name = parse_ref(r);
+        if let Some(v) = schemas.get(&name) {
+            return Some(v);
+        }
+
+        let mut defs = Vec::new();
+        for schema in schemas.values() {
+            collect_defs(schema, &mut defs);
+        }
+        defs.into_iter().find(|(k, _)| **k == name).map(|(_, v)| v)
+    }
+
+    /// Parse a schema and rename references to schemas with `x-qi-name`.
+    fn parse_schema(&self, schema: &Schema) -> Model {
+        let mut m = parse_schema_type(schema);
+        self.rename_references(&mut m);
+
+        m
+    }
+
+    fn rename_references(&self, m: &mut Model) {
+        if m.kind == ModelKind::Reference {
+            let name = m.name.as_ref().unwrap();
+            if let Some(v) = self.model_name(name) {
+                m.name = Some(v);
+            }
+        }
+        for prop in m.properties.iter_mut().flatten() {
+            self.rename_references(prop.1);
+        }
+        if let Some(element) = m.element.as_mut() {
+            self.rename_references(element);
+        }
+    }
+
+    /// Returns the name override of a schema in components.
+    fn model_name(&self, name: &str) -> Option<String> {
+        self.spec
+            .components
+            .as_ref()
+            .and_then(|c| c.schemas.as_ref())
+            .and_then(|m| m.get(name))
+            .and_then(|v| extension_str(&v.extensions, X_QI_NAME))
+    }
+
+    /// Resolve a reference object at `ptr` into the object it refers to in
+    /// the components and its pointer, references to references will be
+    /// followed.
+    ///
+    /// Only local references like `#/components/responses/NotFound` are
+    /// supported for now.
+    fn resolve<'a, T, F>(&'a self, v: &'a T, ptr: &str, f: F) -> Result<(&'a T, String), Error>
+    where
+        T: Reference,
+        F: Fn(&'a v3::Components) -> Option<&'a BTreeMap<String, T>>,
+    {
+        let mut v = v;
+        let mut ptr = ptr.to_string();
+        // Limit the depth so that circular references will not hang.
+        for _ in 0..16 {
+            let r = match v.reference() {
+                None => return Ok((v, ptr)),
+                Some(r) => r,
+            };
+            if !r.starts_with("#/") {
+                return Err(Error::new(
+                    &ptr,
+                    format!("external reference {} is not supported", r),
+                ));
+            }
+
+            v = self
+                .spec
+                .components
+                .as_ref()
+                .and_then(&f)
+                .and_then(|m| m.get(&parse_ref(r)))
+                .ok_or_else(|| Error::new(&ptr, format!("reference {} is not found", r)))?;
+            ptr = r[1..].to_string();
+        }
+
+        Err(Error::new(
+            &ptr,
+            format!(
+                "reference {} is too deep",
+                v.reference().unwrap_or_default()
+            ),
+        ))
+    }
+
+    fn format_models(&mut self) -> Result<(), Error> {
+        let mut shapes: BTreeMap<String, Model> = BTreeMap::new();
+
+        let components = match self.spec.components.as_ref() {
+            None => return Ok(()),
+            Some(v) => v,
+        };
+        for (name, schema) in components.schemas.iter().flatten() {
+            self.check_schema(schema, &pointer("", &["components", "schemas", name]))?;
+        }
+
+        // Schemas in `$defs` are referenced by the last segment like
+        // components, while components take precedence on conflicts.
+        let mut schemas: Vec<(&String, &Schema)> = Vec::new();
+        for (_, schema) in components.schemas.iter().flatten() {
+            collect_defs(schema, &mut schemas);
+        }
+        schemas.extend(components.schemas.iter().flatten());
+
+        for (name, schema) in schemas {
+            let mut model = self.parse_schema(schema);
+            if model.kind == ModelKind::Any && model.type_path().is_none() {
+                continue;
+            }
+            // The name override of a model is used as its key instead.
+            let name = self.model_name(name).unwrap_or_else(|| name.to_string());
+            if let Some(annotation) = model.annotation.as_mut() {
+                annotation.display = None;
+            }
+            shapes.insert(name, model);
+        }
+
+        self.models = shapes;
+        Ok(())
+    }
+
+    fn deref_model(&self, m: &Model) -> Model {
+        match m.kind {
+            // Schemas without type are not stored as models, like `{}`, while
+            // references to missing schemas are rejected by `check_schema`.
+            // References to them are replaced by `resolve_dynamic` at last.
+            ModelKind::Reference => self
+                .models
+                .get(m.name.as_ref().unwrap().as_str())
+                .cloned()
+                .unwrap_or_else(|| Model::new(ModelKind::Any)),
+            _ => m.clone(),
+        }
+    }
+
+    /// Parse a request or response body schema.
+    ///
+    /// References to structs are kept as is so that generators could use
+    /// the model's name, while raw string and binary bodies are turned into
+    /// a byte stream so that we never buffer whole payloads in memory.
+    /// Media types without a schema are raw bodies as well.
+    fn parse_body(&self, media_type: &v3::MediaType, ptr: &str) -> Result<Model, Error> {
+        let m = match media_type.schema.as_ref() {
+            None => return Ok(Model::byte_stream()),
+            Some(v) => {
+                self.check_schema(v, &pointer(ptr, &["schema"]))?;
+                self.parse_schema(v)
+            }
+        };
+
+        match self.deref_model(&m).kind {
+            ModelKind::String | ModelKind::Iterator => Ok(Model::byte_stream()),
+            _ => Ok(m),
+        }
+    }
+
+    /// Parse the encoding of every property in a form or multipart body of
+    /// the media type at `ptr`.
+    ///
+    /// Properties without an explicit encoding get the defaults from the
+    /// spec: `form` style which is exploded, and a content type derived from
+    /// the property's type.
+    fn parse_encoding(
+        &self,
+        media_type: &v3::MediaType,
+        ptr: &str,
+    ) -> Result<BTreeMap<String, Encoding>, Error> {
+        let mut encoding = BTreeMap::new();
+
+        let body = match media_type.schema.as_ref() {
+            None => return Ok(encoding),
+            Some(v) => self.deref_model(&self.parse_schema(v)),
+        };
+        let props = match body.properties {
+            None => return Ok(encoding),
+            Some(props) => props,
+        };
+
+        for (name, prop) in props {
+            let prop = self.deref_model(&prop);
+            let e = media_type.encoding.as_ref().and_then(|v| v.get(&name));
+
+            let content_type = e.and_then(|v| v.content_type.clone()).unwrap_or_else(|| {
+                match prop.kind {
+                    ModelKind::Iterator => "application/octet-stream",
+                    ModelKind::Struct | ModelKind::Map | ModelKind::Any => "application/json",
+                    _ => "text/plain",
+                }
+                .to_string()
+            });
+            let style = e
+                .and_then(|v| v.style.clone())
+                .unwrap_or_else(|| "form".to_string());
+            let explode = e.and_then(|v| v.explode).unwrap_or(style == "form");
+
+            let mut headers = Vec::new();
+            if let Some(hs) = e.and_then(|v| v.headers.as_ref()) {
+                for (header, v) in hs {
+                    let ptr = pointer(ptr, &["encoding", &name, "headers", header]);
+                    headers.push(self.parse_header(header, v, &ptr)?);
+                }
+            }
+
+            encoding.insert(
+                name,
+                Encoding {
+                    content_type,
+                    headers,
+                    style,
+                    explode,
+                },
+            );
+        }
+
+        Ok(encoding)
+    }
+
+    /// Returns the example of a body from `example`, the first one in
+    /// `examples`, or the example of its schema, which is `examples` in 3.1.
+    fn parse_example(&self, media_type: &v3::MediaType, ptr: &str) -> Result<Option<Value>, Error> {
+        if let Some(v) = media_type.example.as_ref() {
+            return Ok(Some(v.clone()));
+        }
+        for (name, example) in media_type.examples.iter().flatten() {
+            let ptr = pointer(ptr, &["examples", name]);
+            let (example, _) = self.resolve(example, &ptr, |c| c.examples.as_ref())?;
+            if let Some(v) = example.value.as_ref() {
+                return Ok(Some(v.clone()));
+            }
+        }
+
+        let schema = match media_type.schema.as_ref() {
+            None => return Ok(None),
+            Some(v) => v,
+        };
+        let schema = match schema.ref_.as_deref() {
+            None => Some(schema),
+            Some(r) => self.find_schema(r),
+        };
+        Ok(schema.and_then(|v| {
+            v.example
+                .clone()
+                .or_else(|| v.examples.iter().flatten().next().cloned())
+        }))
+    }
+
+    fn parse_response(
+        &self,
+        status: (usize, bool),
+        response: &v3::Response,
+        ptr: &str,
+    ) -> Result<Output, Error> {
+        let (response, ptr) = self.resolve(response, ptr, |c| c.responses.as_ref())?;
+
+        let mut output = Output {
+            description: response.description.clone(),
+            example: None,
+            status_code: status.0,
+            range: status.1,
+            header: Vec::new(),
+            body: None,
+            content_type: None,
+        };
+
+        if let Some(headers) = response.headers.as_ref() {
+            for (name, header) in headers.iter() {
+                let ptr = pointer(&ptr, &["headers", name]);
+                output.header.push(self.parse_header(name, header, &ptr)?);
+            }
+        }
+
+        if let Some((content_type, media_type)) = response.content.iter().flatten().next() {
+            let ptr = pointer(&ptr, &["content", content_type]);
+            output.example = self.parse_example(media_type, &ptr)?;
+            output.body = Some(self.parse_body(media_type, &ptr)?);
+            output.content_type = Some(content_type.clone());
+        }
+
+        Ok(output)
+    }
+
+    fn format_operations(&mut self) -> Result<(), Error> {
+        let mut ops: Vec<Operation> = Vec::new();
+
+        for (path, item) in self.spec.paths.items.iter() {
+            for (method, o) in item.operations.iter() {
+                let op_ptr = pointer("", &["paths", path, method.as_str()]);
+                check_extensions(&o.extensions, &op_ptr)?;
+                if o.extensions.get(X_QI_SKIP) == Some(&Value::Bool(true)) {
+                    continue;
+                }
+
+                let mut op = Operation {
+                    id: o.id(path, *method),
+                    annotation: operation_annotation(o),
+                    method: *method,
+                    uri: path.clone(),
+                    expect: Vec::new(),
+                    // Operations share the summary and description of their
+                    // path item by default.
+                    summary: o.summary.clone().or_else(|| item.summary.clone()),
+                    description: o.description.clone().or_else(|| item.description.clone()),
+                    tags: o.tags.clone(),
+                    // Operations without `security` use the top level one.
+                    security: o
+                        .security
+                        .clone()
+                        .or_else(|| self.spec.security.clone())
+                        .unwrap_or_default(),
+                    input: Input {
+                        description: None,
+                        example: None,
+                        path: vec![],
+                        query: vec![],
+                        header: vec![],
+                        cookie: vec![],
+                        body: None,
+                        content_type: None,
+                        encoding: BTreeMap::new(),
+                    },
+                    output: Output {
+                        description: None,
+                        example: None,
+                        status_code: 0,
+                        range: false,
+                        header: vec![],
+                        body: None,
+                        content_type: None,
+                    },
+                    errors: Vec::new(),
+                };
+
+                // Parameters defined in path item are shared by all operations
+                // under this path, and could be overridden by operation's own.
+                let item_params = item.parameters.iter().flatten().enumerate().map(|(i, v)| {
+                    let ptr = pointer("", &["paths", path, "parameters", &i.to_string()]);
+                    (v, ptr)
+                });
+                let op_params = o.parameters.iter().flatten().enumerate().map(|(i, v)| {
+                    let ptr = pointer(&op_ptr, &["parameters", &i.to_string()]);
+                    (v, ptr)
+                });
+                let mut params: Vec<(&v3::Parameter, String)> = Vec::new();
+                for (param, ptr) in item_params.chain(op_params) {
+                    let (param, ptr) = self.resolve(param, &ptr, |c| c.parameters.as_ref())?;
+                    params.retain(|(v, _)| v.name != param.name || v.in_ != param.in_);
+                    params.push((param, ptr));
+                }
+
+                for (param, ptr) in params {
+                    let mut p = self.parse_parameter(param, &ptr)?;
+
+                    match param.in_.as_deref() {
+                        // Path parameters are always required.
+                        Some("path") => {
+                            p.mandatory = true;
+                            op.input.path.push(p)
+                        }
+                        Some("query") => op.input.query.push(p),
+                        Some("header") => op.input.header.push(p),
+                        Some("cookie") => op.input.cookie.push(p),
+                        Some(v) => {
+                            return Err(Error::new(
+                                &pointer(&ptr, &["in"]),
+                                format!("invalid parameter location {}", v),
+                            ))
+                        }
+                        None => return Err(Error::new(&ptr, "parameter requires in")),
+                    }
+                }
+
+                if let Some(body) = o.request_body.as_ref() {
+                    let ptr = pointer(&op_ptr, &["requestBody"]);
+                    let (body, ptr) = self.resolve(body, &ptr, |c| c.request_bodies.as_ref())?;
+                    let (content_type, media_type) = match body.content.iter().next() {
+                        Some(v) => v,
+                        None => return Err(Error::new(&ptr, "request body requires content")),
+                    };
+                    let ptr = pointer(&ptr, &["content", content_type]);
+
+                    op.input.description = body.description.clone();
+                    op.input.example = self.parse_example(media_type, &ptr)?;
+                    op.input.body = Some(self.parse_body(media_type, &ptr)?);
+                    op.input.content_type = Some(content_type.clone());
+                    op.input.encoding = self.parse_encoding(media_type, &ptr)?;
+                }
+
+                for (name, response) in o.responses.iter().flat_map(|v| v.items.iter()) {
+                    let ptr = pointer(&op_ptr, &["responses", name]);
+                    let status = match parse_status_code(name) {
+                        Some(v) => v,
+                        None => {
+                            return Err(Error::new(&ptr, format!("invalid status code {}", name)))
+                        }
+                    };
+
+                    let output = self.parse_response(status, response, &ptr)?;
+                    if !(100..300).contains(&output.status_code) {
+                        op.errors.push(output);
+                        continue;
+                    }
+
+                    if !op.expect.contains(&output.status_code) {
+                        op.expect.push(output.status_code);
+                    }
+                    // Responses of exact status codes take precedence over
+                    // ranges like `2XX`.
+                    if op.output.status_code == 0 || op.output.range {
+                        op.output = output;
+                    }
+                }
+
+                ops.push(op);
+            }
+        }
+
+        self.operations = ops;
+        Ok(())
+    }
+}
+
+/// Check the types of vendor extensions honored by qi, which are ignored
+/// by the normalization if invalid.
+fn check_extensions(extensions: &v3::Extensions, ptr: &str) -> Result<(), Error> {
+    for (key, v) in extensions {
+        let valid = match key.as_str() {
+            X_QI_NAME | X_QI_TYPE => v.is_string(),
+            X_QI_SKIP => v.is_boolean(),
+            X_QI_DERIVE => match v {
+                Value::String(_) => true,
+                Value::Array(v) => v.iter().all(Value::is_string),
+                _ => false,
+            },
+            _ => continue,
+        };
+        if !valid {
+            let expected = match key.as_str() {
+                X_QI_SKIP => "a boolean",
+                X_QI_DERIVE => "a string or an array of strings",
+                _ => "a string",
+            };
+            return Err(Error::new(
+                &pointer(ptr, &[key]),
+                format!("{} must be {}", key, expected),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn resolve_dynamic(m: &mut Model, names: &BTreeSet<String>) {
+    if m.kind == ModelKind::Reference && !m.name.as_ref().is_some_and(|v| names.contains(v)) {
+        m.kind = ModelKind::Any;
+        m.name = None;
+    }
+
+    for v in m
+        .properties
+        .iter_mut()
+        .flat_map(|v| v.values_mut())
+        .chain(m.element.as_deref_mut())
+    {
+        resolve_dynamic(v, names);
+    }
+}
+
+/// Parse a response key into its status code and whether it's a range,
+/// ranges like `2XX` are recorded as the first code of their class, and
+/// `default` as 0.
+fn parse_status_code(name: &str) -> Option<(usize, bool)> {
+    if name == "default" {
+        return Some((0, false));
+    }
+    if let Ok(v) = usize::from_str(name) {
+        return (100..600).contains(&v).then_some((v, false));
+    }
+
+    match name.as_bytes() {
+        [class @ b'1'..=b'5', b'X' | b'x', b'X' | b'x'] => {
+            Some((usize::from(class - b'0') * 100, true))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the annotation of an operation set by `x-qi-name` and
+/// `deprecated`.
+fn operation_annotation(o: &v3::Operation) -> Option<Annotation> {
+    let annotation = Annotation {
+        display: extension_str(&o.extensions, X_QI_NAME),
+        deprecated: o.deprecated.unwrap_or(false),
+        ..Default::default()
+    };
+    if annotation == Annotation::default() {
+        return None;
+    }
+
+    Some(annotation)
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub struct Operation {
+    /// `operationId` of the operation, or one generated from its method
+    /// and path like `get_pets_petId` if absent.
+    pub id: String,
+    /// Set by `x-qi-name` and `deprecated` on the operation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<Annotation>,
+
+    pub method: Method,
+    /// Path template like `/pets/{petId}`.
+    pub uri: String,
+    /// Status codes of successful responses.
+    pub expect: Vec<usize>,
+
+    pub input: Input,
+    pub output: Output,
+    /// Responses whose status code is not 1XX or 2XX, including `default`.
+    pub errors: Vec<Output>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Description of the operation, or its path item if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// Alternatives of security requirements, any of which must be
+    /// satisfied, and an empty one allows anonymous requests. It's the top
+    /// level `security` if the operation does not override it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub security: Vec<SecurityRequirement>,
+}
+
+impl Operation {
+    /// Returns models of parameters, headers and bodies in the operation,
+    /// including those of errors.
+    pub fn models(&self) -> impl Iterator<Item = &Model> {
+        let outputs = std::iter::once(&self.output).chain(self.errors.iter());
+
+        self.input
+            .path
+            .iter()
+            .chain(self.input.query.iter())
+            .chain(self.input.header.iter())
+            .chain(self.input.cookie.iter())
+            .chain(self.input.encoding.values().flat_map(|v| v.headers.iter()))
+            .chain(outputs.clone().flat_map(|v| v.header.iter()))
+            .map(|v| &v.model)
+            .chain(self.input.body.iter())
+            .chain(outputs.flat_map(|v| v.body.iter()))
+    }
+
+    /// Mutable version of `models`.
+    pub fn models_mut(&mut self) -> impl Iterator<Item = &mut Model> {
+        let input = &mut self.input;
+        let params = input
+            .path
+            .iter_mut()
+            .chain(input.query.iter_mut())
+            .chain(input.header.iter_mut())
+            .chain(input.cookie.iter_mut())
+            .chain(
+                input
+                    .encoding
+                    .values_mut()
+                    .flat_map(|v| v.headers.iter_mut()),
+            )
+            .map(|v| &mut v.model)
+            .chain(input.body.iter_mut());
+        let outputs = std::iter::once(&mut self.output)
+            .chain(self.errors.iter_mut())
+            .flat_map(|v| {
+                v.header
+                    .iter_mut()
+                    .map(|v| &mut v.model)
+                    .chain(v.body.iter_mut())
+            });
+
+        params.chain(outputs)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub struct Input {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub path: Vec<Parameter>,
+    pub query: Vec<Parameter>,
+    pub header: Vec<Parameter>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cookie: Vec<Parameter>,
+    pub body: Option<Model>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub encoding: BTreeMap<String, Encoding>,
+    /// Example of the body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub struct Output {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Status code, or the first one of the class for ranges like `2XX`,
+    /// and 0 for `default`.
+    pub status_code: usize,
+    /// Whether the response covers the whole class of `status_code`, like
+    /// `2XX`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub range: bool,
+    pub header: Vec<Parameter>,
+    pub body: Option<Model>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Example of the body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<Value>,
+}
+
+/// Encoding of a property in form or multipart body.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub struct Encoding {
+    pub content_type: String,
+    pub headers: Vec<Parameter>,
+    pub style: String,
+    pub explode: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub struct Parameter {
+    pub name: String,
+    pub model: Model,
+    /// Whether the parameter is required.
+    pub mandatory: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Serialization style like `form` or `simple`.
+    pub style: String,
+    /// Whether arrays and objects are exploded, like `ids=1&ids=2` in
+    /// `form` style.
+    pub explode: bool,
+}
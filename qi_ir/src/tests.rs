@@ -0,0 +1,357 @@
+use super::*;
+use std::error::Error;
+use std::fs::File;
+
+use qi_openapi::v3::{self, Schema};
+
+fn load(path: &str) -> Result<Service, Box<dyn Error>> {
+    let spec = v3::from_yaml_reader(File::open(path)?)?;
+
+    Ok(Builder::new(spec).build()?)
+}
+
+/// Build a service from a spec with the given paths and components.
+fn build_spec(paths: &str, components: &str) -> Result<Service, crate::Error> {
+    let content = format!(
+        "openapi: 3.0.3\ninfo: {{title: test, version: 1.0.0}}\npaths: {}\ncomponents: {}\n",
+        paths, components
+    );
+    let spec = v3::from_yaml_reader(content.as_bytes()).unwrap();
+
+    Builder::new(spec).build()
+}
+
+#[test]
+fn binary_as_byte_stream() {
+    let schema: Schema = serde_json::from_str(r#"{"type": "string", "format": "binary"}"#).unwrap();
+
+    assert_eq!(Model::byte_stream(), parse_schema_type(&schema));
+}
+
+#[test]
+fn binary_keeps_property_settings() {
+    let schema: Schema = serde_json::from_str(
+        r#"{"type": "string", "format": "binary", "nullable": true, "deprecated": true, "x-qi-name": "data", "description": "Content"}"#,
+    )
+    .unwrap();
+    let model = parse_schema_type(&schema);
+    assert_eq!(ModelKind::Iterator, model.kind);
+    assert_eq!(Some(Box::new(Model::new(ModelKind::Byte))), model.element);
+    assert!(model.nullable);
+    assert!(model.deprecated());
+    assert_eq!(Some("data"), model.display());
+    assert_eq!(Some("Content"), model.description.as_deref());
+
+    let schema: Schema = serde_json::from_str(
+        r#"{"type": "string", "contentMediaType": "application/octet-stream", "enum": ["a"], "x-qi-name": "raw"}"#,
+    )
+    .unwrap();
+    let model = parse_schema_type(&schema);
+    assert_eq!(ModelKind::Iterator, model.kind);
+    assert_eq!(Some(vec![serde_json::json!("a")]), model.values);
+    assert_eq!(Some("raw"), model.display());
+}
+
+#[test]
+fn diff_breaking_changes() -> Result<(), Box<dyn Error>> {
+    let old = load("tests/diff_old.yaml")?;
+    let new = load("tests/diff_new.yaml")?;
+
+    let changes = diff::diff(&old, &new);
+    let kinds = |kind: diff::ChangeKind| changes.iter().filter(|v| v.kind == kind).count();
+    assert_eq!(1, kinds(diff::ChangeKind::OperationRemoved));
+    assert_eq!(1, kinds(diff::ChangeKind::OperationAdded));
+    assert_eq!(1, kinds(diff::ChangeKind::ParameterRequired));
+    assert_eq!(1, kinds(diff::ChangeKind::ParameterAdded));
+    assert_eq!(1, kinds(diff::ChangeKind::TypeChanged));
+    assert_eq!(1, kinds(diff::ChangeKind::EnumNarrowed));
+    assert_eq!(1, kinds(diff::ChangeKind::FieldRemoved));
+    assert_eq!(1, kinds(diff::ChangeKind::StatusCodeChanged));
+    assert_eq!(6, changes.iter().filter(|v| v.breaking).count());
+
+    let removed = changes
+        .iter()
+        .find(|v| v.kind == diff::ChangeKind::FieldRemoved)
+        .unwrap();
+    assert_eq!("models.Pet.tag", removed.location);
+
+    assert!(diff::diff(&old, &old).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn diff_by_usage() -> Result<(), Box<dyn Error>> {
+    use diff::ChangeKind::*;
+
+    let paths = r#"{/pets: {post: {
+        requestBody: {content: {application/json: {schema: {$ref: '#/components/schemas/NewPet'}}}},
+        responses: {'200': {description: OK.,
+            content: {application/json: {schema: {$ref: '#/components/schemas/Pet'}}}}}}}}"#;
+    let old = build_spec(
+        paths,
+        r#"{schemas: {
+            NewPet: {type: object, properties: {
+                name: {type: string}, tag: {type: string}, kind: {type: string, enum: [cat, dog]}}},
+            Pet: {type: object, required: [id], properties: {
+                id: {type: integer}, tag: {type: string}, kind: {type: string, enum: [cat, dog]}}}}}"#,
+    )?;
+    let new = build_spec(
+        paths,
+        r#"{schemas: {
+            NewPet: {type: object, required: [name, age], properties: {
+                name: {type: string}, age: {type: integer}, kind: {type: string, enum: [cat]}}},
+            Pet: {type: object, properties: {
+                id: {type: integer}, age: {type: integer}, kind: {type: string, enum: [cat]}}}}}"#,
+    )?;
+
+    let changes = diff::diff(&old, &new);
+    let breaking = |location: &str, kind: diff::ChangeKind| {
+        changes
+            .iter()
+            .find(|v| v.location == location && v.kind == kind)
+            .map(|v| v.breaking)
+    };
+    // Clients could not send what the server accepts.
+    assert_eq!(Some(true), breaking("models.NewPet.name", FieldRequired));
+    assert_eq!(Some(true), breaking("models.NewPet.age", FieldRequired));
+    assert_eq!(Some(false), breaking("models.NewPet.tag", FieldRemoved));
+    assert_eq!(Some(true), breaking("models.NewPet.kind", EnumNarrowed));
+    // Clients could not read what the server responds.
+    assert_eq!(Some(true), breaking("models.Pet.id", FieldOptional));
+    assert_eq!(Some(false), breaking("models.Pet.age", FieldAdded));
+    assert_eq!(Some(true), breaking("models.Pet.tag", FieldRemoved));
+    assert_eq!(Some(false), breaking("models.Pet.kind", EnumNarrowed));
+    assert_eq!(8, changes.len());
+
+    Ok(())
+}
+
+#[test]
+fn dump_ir_snapshot() -> Result<(), Box<dyn Error>> {
+    let spec = v3::from_json_reader(File::open("tests/petstore.json")?)?;
+    let srv = Service::new(spec)?;
+
+    let expected = std::fs::read_to_string("tests/petstore.ir.json")?;
+    assert_eq!(
+        expected.trim_end(),
+        serde_json::to_string_pretty(&Ir::new(&srv))?
+    );
+    assert_eq!(IR_VERSION, Ir::new(&srv).version);
+
+    Ok(())
+}
+
+#[test]
+fn filter_operations() -> Result<(), Box<dyn Error>> {
+    let spec = v3::from_json_reader(File::open("tests/petstore.json")?)?;
+    let build = |filter: &str| {
+        let filter: Filter = serde_json::from_str(filter).unwrap();
+        let srv = Builder::new(spec.clone()).filter(filter).build().unwrap();
+        let ids: Vec<String> = srv.operations.iter().map(|v| v.id.clone()).collect();
+        (ids, srv.models.keys().cloned().collect::<Vec<_>>())
+    };
+
+    // Models are kept if nothing is filtered out.
+    let (ids, models) = build("{}");
+    assert_eq!(3, ids.len());
+    assert_eq!(vec!["Error", "Pet", "Pets"], models);
+
+    let (ids, models) = build(r#"{"include": [{"operation_id": "show*"}]}"#);
+    assert_eq!(vec!["showPetById"], ids);
+    assert_eq!(vec!["Error", "Pet"], models);
+
+    let (ids, _) = build(r#"{"include": [{"tags": ["pets"], "methods": ["get"]}]}"#);
+    assert_eq!(vec!["listPets", "showPetById"], ids);
+
+    let (ids, models) = build(r#"{"exclude": [{"path_prefix": "/pets"}]}"#);
+    assert!(ids.is_empty());
+    assert!(models.is_empty());
+
+    let (ids, _) = build(r#"{"include": [{"operation_id": "*Pet?"}, {"path_prefix": "/pets/"}]}"#);
+    assert_eq!(vec!["listPets", "createPets", "showPetById"], ids);
+    let (ids, _) =
+        build(r#"{"include": [{"operation_id": "list*s"}], "exclude": [{"tags": ["pets"]}]}"#);
+    assert!(ids.is_empty());
+
+    assert!(serde_json::from_str::<Filter>(r#"{"include": [{"tag": "pets"}]}"#).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn build_without_components() -> Result<(), Box<dyn Error>> {
+    let content = r#"
+openapi: 3.0.3
+info: {title: test, version: 1.0.0}
+paths:
+  /health:
+    get:
+      responses:
+        '204': {description: Healthy.}
+"#;
+    let srv = Builder::new(v3::from_yaml_reader(content.as_bytes())?).build()?;
+
+    assert_eq!("get_health", srv.operations[0].id);
+    assert_eq!(vec![204], srv.operations[0].expect);
+    assert!(srv.models.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn build_status_code_ranges() -> Result<(), Box<dyn Error>> {
+    let srv = build_spec(
+        r#"{/pets: {get: {responses: {
+            '2XX': {description: Any success.},
+            '200': {description: OK.},
+            '4xx': {description: Client error.}}}}}"#,
+        "{}",
+    )?;
+
+    let op = &srv.operations[0];
+    assert_eq!((200, false), (op.output.status_code, op.output.range));
+    assert_eq!(vec![200], op.expect);
+    assert_eq!((400, true), (op.errors[0].status_code, op.errors[0].range));
+
+    let srv = build_spec(
+        r#"{/pets: {get: {responses: {'2XX': {description: Any success.}}}}}"#,
+        "{}",
+    )?;
+    assert!(srv.operations[0].output.range);
+
+    let err = build_spec(
+        r#"{/pets: {get: {responses: {'6XX': {description: Unknown.}}}}}"#,
+        "{}",
+    )
+    .unwrap_err();
+    assert_eq!("/paths/~1pets/get/responses/6XX", err.pointer);
+
+    Ok(())
+}
+
+#[test]
+fn build_parameters() -> Result<(), Box<dyn Error>> {
+    let srv = build_spec(
+        r#"{/pets: {get: {
+            parameters: [
+                {name: session, in: cookie, schema: {type: string}},
+                {name: filter, in: query, content: {application/json: {schema: {type: object}}}}],
+            responses: {'200': {description: OK.,
+                headers: {X-Rate: {content: {text/plain: {schema: {type: integer}}}}}}}}}}"#,
+        "{}",
+    )?;
+
+    let op = &srv.operations[0];
+    assert_eq!("session", op.input.cookie[0].name);
+    assert_eq!(ModelKind::Struct, op.input.query[0].model.kind);
+    assert_eq!(ModelKind::Int, op.output.header[0].model.kind);
+
+    let err = build_spec(
+        r#"{/pets: {get: {parameters: [{name: id, in: query}], responses: {}}}}"#,
+        "{}",
+    )
+    .unwrap_err();
+    assert_eq!("/paths/~1pets/get/parameters/0", err.pointer);
+
+    let err = build_spec(
+        r#"{/pets: {get: {parameters: [{name: id, in: body, schema: {type: string}}], responses: {}}}}"#,
+        "{}",
+    )
+    .unwrap_err();
+    assert_eq!("/paths/~1pets/get/parameters/0/in", err.pointer);
+
+    Ok(())
+}
+
+#[test]
+fn build_invalid_references() {
+    let build = |components: &str| {
+        let paths = r##"{/pets: {get: {parameters: [{$ref: '#/components/parameters/Limit'}], responses: {}}}}"##;
+        build_spec(paths, components).unwrap_err()
+    };
+
+    let err = build("{}");
+    assert_eq!("/paths/~1pets/get/parameters/0", err.pointer);
+    assert!(err.message.contains("is not found"), "{}", err);
+
+    let err = build(r#"{parameters: {Limit: {$ref: 'common.yaml#/components/parameters/Limit'}}}"#);
+    assert_eq!("/components/parameters/Limit", err.pointer);
+    assert!(err.message.contains("external reference"), "{}", err);
+
+    let err = build(r##"{parameters: {Limit: {$ref: '#/components/parameters/Limit'}}}"##);
+    assert!(err.message.contains("too deep"), "{}", err);
+
+    // References to missing schemas are not turned into dynamic types.
+    let err = build_spec(
+        r##"{/pets: {get: {responses: {'200': {description: OK.,
+            content: {application/json: {schema: {$ref: '#/components/schemas/Pets'}}}}}}}}"##,
+        "{}",
+    )
+    .unwrap_err();
+    assert_eq!(
+        "/paths/~1pets/get/responses/200/content/application~1json/schema",
+        err.pointer
+    );
+    let err = build_spec(
+        "{}",
+        r##"{schemas: {Pet: {type: object, properties: {
+            tags: {type: array, items: {$ref: '#/components/schemas/Tag'}}}}}}"##,
+    )
+    .unwrap_err();
+    assert_eq!("/components/schemas/Pet/properties/tags/items", err.pointer);
+}
+
+#[test]
+fn build_invalid_extensions() {
+    let err = build_spec("{}", "{schemas: {Pet: {type: object, x-qi-derive: 1}}}").unwrap_err();
+    assert_eq!("/components/schemas/Pet/x-qi-derive", err.pointer);
+    assert_eq!(
+        "x-qi-derive must be a string or an array of strings",
+        err.message
+    );
+
+    let err = build_spec(
+        "{}",
+        "{schemas: {Pet: {type: object, properties: {tag: {type: string, x-qi-name: [tag]}}}}}",
+    )
+    .unwrap_err();
+    assert_eq!(
+        "/components/schemas/Pet/properties/tag/x-qi-name",
+        err.pointer
+    );
+
+    let err = build_spec(r#"{/pets: {get: {x-qi-skip: "yes", responses: {}}}}"#, "{}").unwrap_err();
+    assert_eq!("/paths/~1pets/get/x-qi-skip", err.pointer);
+
+    let srv = build_spec(
+        "{}",
+        "{schemas: {Pet: {type: object, x-qi-derive: 'Eq, Hash'}}}",
+    )
+    .unwrap();
+    let derives = &srv.models["Pet"].annotation.as_ref().unwrap().derives;
+    assert_eq!(&vec!["Eq".to_string(), "Hash".to_string()], derives);
+}
+
+#[test]
+fn dynamic_references() {
+    let srv = build_spec(
+        r##"{/pets: {get: {
+            parameters: [{name: session, in: cookie, schema: {$ref: '#/components/schemas/Any'}}],
+            responses: {default: {description: Error,
+                content: {application/json: {schema: {$ref: '#/components/schemas/Any'}}}}}}}}"##,
+        r##"{schemas: {Any: {}, Pet: {type: object, properties: {
+            extra: {type: array, items: {$ref: '#/components/schemas/Any'}}}}}}"##,
+    )
+    .unwrap();
+
+    // Schemas without type are not stored, and references to them become
+    // dynamic values.
+    assert!(!srv.models.contains_key("Any"));
+    let op = &srv.operations[0];
+    assert_eq!(ModelKind::Any, op.input.cookie[0].model.kind);
+    assert_eq!(ModelKind::Any, op.errors[0].body.as_ref().unwrap().kind);
+    let extra = &srv.models["Pet"].properties.as_ref().unwrap()["extra"];
+    assert_eq!(ModelKind::Any, extra.element.as_ref().unwrap().kind);
+    assert_eq!(None, extra.element.as_ref().unwrap().name);
+}
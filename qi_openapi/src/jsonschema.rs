@@ -0,0 +1,420 @@
+//! A minimal JSON Schema draft 4 validator for the OpenAPI meta-schemas.
+//!
+//! It is used instead of a general validator to locate errors precisely:
+//! errors of `anyOf` and `oneOf` are those of the closest alternative, like
+//! `/paths/~1pets/get/responses/200/description` instead of the response.
+//!
+//! Only keywords in [`KEYWORDS`] are validated, `$ref`s are resolved in the
+//! root schema only. Keywords in [`IGNORED`] are annotations or unused by
+//! the meta-schemas, and all others are ignored as well.
+
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+
+/// Keywords which are validated.
+pub const KEYWORDS: &[&str] = &[
+    "$ref",
+    "type",
+    "enum",
+    "required",
+    "minProperties",
+    "maxProperties",
+    "properties",
+    "patternProperties",
+    "additionalProperties",
+    "items",
+    "minItems",
+    "uniqueItems",
+    "pattern",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "allOf",
+    "anyOf",
+    "oneOf",
+    "not",
+];
+
+/// Keywords which are known but not validated, `format` is only an
+/// annotation for the meta-schemas.
+pub const IGNORED: &[&str] = &[
+    "$schema",
+    "id",
+    "definitions",
+    "title",
+    "description",
+    "default",
+    "format",
+];
+
+/// Error is a violation of the schema at the value pointed by `pointer`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub pointer: String,
+    pub message: String,
+}
+
+pub struct Validator<'a> {
+    root: &'a Value,
+    patterns: HashMap<&'a str, Regex>,
+}
+
+impl<'a> Validator<'a> {
+    pub fn new(root: &'a Value) -> Validator<'a> {
+        debug_assert!(
+            unknown_keywords(root).is_empty(),
+            "schema uses unsupported keywords {:?}",
+            unknown_keywords(root)
+        );
+        Validator {
+            root,
+            patterns: HashMap::new(),
+        }
+    }
+
+    pub fn validate(&mut self, value: &Value) -> Vec<Error> {
+        let mut errs = Vec::new();
+        self.check(self.root, value, "", &mut errs);
+
+        errs
+    }
+
+    fn check(&mut self, schema: &'a Value, value: &Value, pointer: &str, errs: &mut Vec<Error>) {
+        let schema = match schema.as_object() {
+            None => return,
+            Some(v) => v,
+        };
+
+        // Siblings of `$ref` are ignored in draft 4.
+        if let Some(r) = schema.get("$ref").and_then(Value::as_str) {
+            match self.root.pointer(r.trim_start_matches('#')) {
+                None => error(errs, pointer, format!("schema {} is not found", r)),
+                Some(v) => self.check(v, value, pointer, errs),
+            }
+            return;
+        }
+
+        if let Some(typ) = schema.get("type") {
+            let types: Vec<&str> = match typ {
+                Value::Array(v) => v.iter().filter_map(Value::as_str).collect(),
+                v => v.as_str().into_iter().collect(),
+            };
+            if !types.iter().any(|v| is_type(value, v)) {
+                error(
+                    errs,
+                    pointer,
+                    format!("expected {}, found {}", types.join(" or "), type_of(value)),
+                );
+                return;
+            }
+        }
+
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            if !values.contains(value) {
+                let values: Vec<_> = values.iter().map(Value::to_string).collect();
+                error(
+                    errs,
+                    pointer,
+                    format!("expected one of {}", values.join(", ")),
+                );
+            }
+        }
+
+        match value {
+            Value::Object(v) => self.check_object(schema, v, pointer, errs),
+            Value::Array(v) => self.check_array(schema, v, pointer, errs),
+            Value::String(v) => self.check_string(schema, v, pointer, errs),
+            Value::Number(v) => check_number(schema, v.as_f64().unwrap(), pointer, errs),
+            _ => {}
+        }
+
+        self.check_combinators(schema, value, pointer, errs);
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        value: &Map<String, Value>,
+        pointer: &str,
+        errs: &mut Vec<Error>,
+    ) {
+        for v in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if !value.contains_key(v) {
+                error(errs, pointer, format!("property {} is required", v));
+            }
+        }
+
+        let size = value.len() as u64;
+        if let Some(v) = schema.get("minProperties").and_then(Value::as_u64) {
+            if size < v {
+                error(errs, pointer, format!("expected at least {} properties", v));
+            }
+        }
+        if let Some(v) = schema.get("maxProperties").and_then(Value::as_u64) {
+            if size > v {
+                error(errs, pointer, format!("expected at most {} properties", v));
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let patterns = schema.get("patternProperties").and_then(Value::as_object);
+        let additional = schema.get("additionalProperties");
+
+        for (k, v) in value {
+            let child = format!("{}/{}", pointer, escape(k));
+            let mut matched = false;
+
+            if let Some(s) = properties.and_then(|m| m.get(k)) {
+                matched = true;
+                self.check(s, v, &child, errs);
+            }
+            for (pattern, s) in patterns.into_iter().flatten() {
+                if self.is_match(pattern, k) {
+                    matched = true;
+                    self.check(s, v, &child, errs);
+                }
+            }
+            if matched {
+                continue;
+            }
+
+            match additional {
+                Some(Value::Bool(false)) => {
+                    error(errs, &child, format!("property {} is not allowed", k))
+                }
+                Some(s) => self.check(s, v, &child, errs),
+                None => {}
+            }
+        }
+    }
+
+    fn check_array(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        value: &[Value],
+        pointer: &str,
+        errs: &mut Vec<Error>,
+    ) {
+        if let Some(s) = schema.get("items") {
+            for (idx, v) in value.iter().enumerate() {
+                self.check(s, v, &format!("{}/{}", pointer, idx), errs);
+            }
+        }
+
+        if let Some(v) = schema.get("minItems").and_then(Value::as_u64) {
+            if (value.len() as u64) < v {
+                error(errs, pointer, format!("expected at least {} items", v));
+            }
+        }
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            for (idx, v) in value.iter().enumerate() {
+                if value[..idx].contains(v) {
+                    error(
+                        errs,
+                        &format!("{}/{}", pointer, idx),
+                        "items must be unique".to_string(),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_string(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        value: &str,
+        pointer: &str,
+        errs: &mut Vec<Error>,
+    ) {
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            if !self.is_match(pattern, value) {
+                error(
+                    errs,
+                    pointer,
+                    format!("{:?} does not match {}", value, pattern),
+                );
+            }
+        }
+    }
+
+    fn check_combinators(
+        &mut self,
+        schema: &'a Map<String, Value>,
+        value: &Value,
+        pointer: &str,
+        errs: &mut Vec<Error>,
+    ) {
+        for s in schema
+            .get("allOf")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            self.check(s, value, pointer, errs);
+        }
+
+        if let Some(Value::Array(schemas)) = schema.get("anyOf") {
+            let results = self.check_each(schemas, value, pointer);
+            if results.iter().all(|v| !v.is_empty()) {
+                errs.extend(best_match(results));
+            }
+        }
+
+        if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+            let results = self.check_each(schemas, value, pointer);
+            match results.iter().filter(|v| v.is_empty()).count() {
+                0 => errs.extend(best_match(results)),
+                1 => {}
+                _ => error(
+                    errs,
+                    pointer,
+                    "expected exactly one schema in oneOf to match".to_string(),
+                ),
+            }
+        }
+
+        if let Some(s) = schema.get("not") {
+            let mut v = Vec::new();
+            self.check(s, value, pointer, &mut v);
+            if v.is_empty() {
+                let message = s
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("must not match {}", s));
+                error(errs, pointer, message);
+            }
+        }
+    }
+
+    fn check_each(
+        &mut self,
+        schemas: &'a [Value],
+        value: &Value,
+        pointer: &str,
+    ) -> Vec<Vec<Error>> {
+        schemas
+            .iter()
+            .map(|s| {
+                let mut errs = Vec::new();
+                self.check(s, value, pointer, &mut errs);
+                errs
+            })
+            .collect()
+    }
+
+    fn is_match(&mut self, pattern: &'a str, value: &str) -> bool {
+        self.patterns
+            .entry(pattern)
+            // Escaped slashes are allowed in ECMA 262 regexes but not here.
+            .or_insert_with(|| Regex::new(&pattern.replace("\\/", "/")).unwrap())
+            .is_match(value)
+    }
+}
+
+fn error(errs: &mut Vec<Error>, pointer: &str, message: String) {
+    errs.push(Error {
+        pointer: pointer.to_string(),
+        message,
+    });
+}
+
+fn check_number(schema: &Map<String, Value>, value: f64, pointer: &str, errs: &mut Vec<Error>) {
+    let exclusive = |k: &str| schema.get(k) == Some(&Value::Bool(true));
+
+    if let Some(v) = schema.get("minimum").and_then(Value::as_f64) {
+        if value < v || (exclusive("exclusiveMinimum") && value == v) {
+            error(
+                errs,
+                pointer,
+                format!("{} is less than minimum {}", value, v),
+            );
+        }
+    }
+    if let Some(v) = schema.get("maximum").and_then(Value::as_f64) {
+        if value > v || (exclusive("exclusiveMaximum") && value == v) {
+            error(
+                errs,
+                pointer,
+                format!("{} is greater than maximum {}", value, v),
+            );
+        }
+    }
+}
+
+/// Errors of the alternative which is closest to the value are reported,
+/// which is the one with fewest errors.
+fn best_match(results: Vec<Vec<Error>>) -> Vec<Error> {
+    results.into_iter().min_by_key(Vec::len).unwrap_or_default()
+}
+
+fn is_type(value: &Value, typ: &str) -> bool {
+    match typ {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64()
+                || value.is_u64()
+                || value.as_f64().map(|v| v.fract() == 0.0) == Some(true)
+        }
+        _ => true,
+    }
+}
+
+fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Returns keywords used by the schema and its subschemas which are neither
+/// in [`KEYWORDS`] nor [`IGNORED`].
+pub fn unknown_keywords(schema: &Value) -> BTreeSet<String> {
+    let mut keywords = BTreeSet::new();
+    collect_keywords(schema, &mut keywords);
+    keywords.retain(|v| !KEYWORDS.contains(&v.as_str()) && !IGNORED.contains(&v.as_str()));
+
+    keywords
+}
+
+fn collect_keywords(schema: &Value, keywords: &mut BTreeSet<String>) {
+    for (k, v) in schema.as_object().into_iter().flatten() {
+        keywords.insert(k.clone());
+        match k.as_str() {
+            "properties" | "patternProperties" | "definitions" => {
+                for s in v.as_object().into_iter().flat_map(|m| m.values()) {
+                    collect_keywords(s, keywords);
+                }
+            }
+            "items" | "additionalProperties" | "not" => collect_keywords(v, keywords),
+            "allOf" | "anyOf" | "oneOf" => {
+                for s in v.as_array().into_iter().flatten() {
+                    collect_keywords(s, keywords);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Escape a key as a segment of JSON pointer.
+pub fn escape(v: &str) -> String {
+    v.replace('~', "~0").replace('/', "~1")
+}
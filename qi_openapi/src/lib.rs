@@ -1,4 +1,11 @@
+pub mod v2;
 pub mod v3;
 
+mod jsonschema;
+mod span;
+mod version;
+pub use span::{SourceMap, Span};
+pub use version::Version;
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,191 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Write;
+
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+
+use crate::jsonschema::escape;
+
+/// Span is a location in the source, line and column start from 1.
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// SourceMap records spans of every value and map key parsed from a JSON or
+/// YAML source, keyed by their JSON pointers like `/paths/~1pets/get`.
+///
+/// It's built by a separate parse of the source so that the typed model is
+/// not affected, and only needed while rendering diagnostics.
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    pub file: String,
+    source: String,
+    values: HashMap<String, Span>,
+    keys: HashMap<String, Span>,
+}
+
+impl SourceMap {
+    /// Parse the source of `file`, JSON is parsed as YAML which is a
+    /// superset of it.
+    pub fn parse(file: &str, source: &str) -> Result<SourceMap, Box<dyn Error>> {
+        let mut recorder = Recorder::default();
+        Parser::new(source.chars()).load(&mut recorder, false)?;
+
+        Ok(SourceMap {
+            file: file.to_string(),
+            source: source.to_string(),
+            values: recorder.values,
+            keys: recorder.keys,
+        })
+    }
+
+    /// Span of the value pointed by `pointer`.
+    pub fn value(&self, pointer: &str) -> Option<Span> {
+        self.values.get(pointer).copied()
+    }
+
+    /// Span of the map key of the value pointed by `pointer`.
+    pub fn key(&self, pointer: &str) -> Option<Span> {
+        self.keys.get(pointer).copied()
+    }
+
+    /// Span used to report diagnostics of `pointer`: the key if it's a map
+    /// entry, or the value, or the closest ancestor which exists in source.
+    pub fn locate(&self, pointer: &str) -> Option<Span> {
+        let mut pointer = pointer;
+        loop {
+            if let Some(v) = self.key(pointer).or_else(|| self.value(pointer)) {
+                return Some(v);
+            }
+            match pointer.rfind('/') {
+                None => return None,
+                Some(idx) => pointer = &pointer[..idx],
+            }
+        }
+    }
+
+    /// Render lines around the span with a marker under its column, like:
+    ///
+    /// ```text
+    ///   7 |   /pets:
+    ///   8 |     get:
+    /// > 9 |       operationId: list_pets
+    ///     |       ^
+    ///  10 |       summary: List pets
+    /// ```
+    pub fn code_frame(&self, span: Span) -> String {
+        const CONTEXT: usize = 2;
+
+        let lines: Vec<&str> = self.source.lines().collect();
+        let start = span.line.saturating_sub(CONTEXT).max(1);
+        let end = (span.line + CONTEXT).min(lines.len());
+        let width = end.to_string().len();
+
+        let mut frame = String::new();
+        for line in start..=end {
+            let mark = if line == span.line { '>' } else { ' ' };
+            writeln!(
+                frame,
+                "{} {:>width$} | {}",
+                mark,
+                line,
+                lines[line - 1],
+                width = width
+            )
+            .unwrap();
+            if line == span.line {
+                writeln!(
+                    frame,
+                    "  {:width$} | {:>column$}",
+                    "",
+                    "^",
+                    width = width,
+                    column = span.column
+                )
+                .unwrap();
+            }
+        }
+
+        frame
+    }
+}
+
+enum Frame {
+    Map {
+        pointer: String,
+        key: Option<String>,
+    },
+    Seq {
+        pointer: String,
+        index: usize,
+    },
+}
+
+#[derive(Default)]
+struct Recorder {
+    stack: Vec<Frame>,
+    values: HashMap<String, Span>,
+    keys: HashMap<String, Span>,
+}
+
+impl Recorder {
+    /// Whether the next node is a key of the current map.
+    fn expect_key(&self) -> bool {
+        matches!(self.stack.last(), Some(Frame::Map { key: None, .. }))
+    }
+
+    /// Pointer of the next value node.
+    fn next_pointer(&mut self) -> String {
+        match self.stack.last_mut() {
+            None => String::new(),
+            Some(Frame::Map { pointer, key }) => {
+                format!("{}/{}", pointer, escape(&key.take().unwrap_or_default()))
+            }
+            Some(Frame::Seq { pointer, index }) => {
+                *index += 1;
+                format!("{}/{}", pointer, *index - 1)
+            }
+        }
+    }
+}
+
+impl MarkedEventReceiver for Recorder {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        let span = Span {
+            line: mark.line(),
+            column: mark.col() + 1,
+        };
+
+        match ev {
+            Event::Scalar(v, ..) if self.expect_key() => {
+                if let Some(Frame::Map { pointer, key }) = self.stack.last_mut() {
+                    self.keys
+                        .insert(format!("{}/{}", pointer, escape(&v)), span);
+                    *key = Some(v);
+                }
+            }
+            Event::Scalar(..) | Event::Alias(_) => {
+                let pointer = self.next_pointer();
+                self.values.insert(pointer, span);
+            }
+            Event::MappingStart(_) => {
+                let pointer = self.next_pointer();
+                self.values.insert(pointer.clone(), span);
+                self.stack.push(Frame::Map { pointer, key: None });
+            }
+            Event::SequenceStart(_) => {
+                let pointer = self.next_pointer();
+                self.values.insert(pointer.clone(), span);
+                self.stack.push(Frame::Seq { pointer, index: 0 });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+}
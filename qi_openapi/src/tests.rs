@@ -8,7 +8,7 @@ fn json_deserialize() -> Result<(), Box<dyn Error>> {
 
     let spec = v3::from_json_reader(file)?;
 
-    print!("{}", format!("{:?}", spec));
+    print!("{:?}", spec);
 
     Ok(())
 }
@@ -18,7 +18,623 @@ fn yaml_deserialize() -> Result<(), Box<dyn Error>> {
     let file = File::open("tests/s3.yaml")?;
 
     let spec = v3::from_yaml_reader(file)?;
-    print!("{}", format!("{:?}", spec));
+    print!("{:?}", spec);
+
+    Ok(())
+}
+
+#[test]
+fn json_round_trip() -> Result<(), Box<dyn Error>> {
+    let expected: serde_json::Value = serde_json::from_reader(File::open("tests/petstore.json")?)?;
+
+    let spec = v3::from_json_reader(File::open("tests/petstore.json")?)?;
+    let mut buf = Vec::new();
+    v3::to_json_writer(&mut buf, &spec)?;
+    let actual: serde_json::Value = serde_json::from_slice(&buf)?;
+
+    assert_eq!(expected, actual);
+    assert_eq!(spec, v3::from_json_reader(buf.as_slice())?);
+
+    Ok(())
+}
+
+#[test]
+fn yaml_round_trip() -> Result<(), Box<dyn Error>> {
+    let expected: serde_json::Value = serde_yaml::from_reader(File::open("tests/s3.yaml")?)?;
+
+    let spec = v3::from_yaml_reader(File::open("tests/s3.yaml")?)?;
+    let mut buf = Vec::new();
+    v3::to_yaml_writer(&mut buf, &spec)?;
+    let actual: serde_json::Value = serde_yaml::from_slice(&buf)?;
+
+    assert_eq!(expected, actual);
+    assert_eq!(spec, v3::from_yaml_reader(buf.as_slice())?);
+
+    Ok(())
+}
+
+#[test]
+fn optional_fields_round_trip() -> Result<(), Box<dyn Error>> {
+    let content = r##"
+openapi: 3.0.3
+info:
+  title: Optional fields
+  version: 1.0.0
+paths:
+  /objects:
+    put:
+      requestBody:
+        $ref: '#/components/requestBodies/Object'
+      responses:
+        '200':
+          description: The raw object.
+          content:
+            application/octet-stream: {}
+components:
+  requestBodies:
+    Object:
+      content:
+        application/octet-stream: {}
+"##;
+    let expected: serde_json::Value = serde_yaml::from_str(content)?;
+
+    let spec = v3::from_yaml_reader(content.as_bytes())?;
+    let op = &spec.paths.items["/objects"].operations[&v3::Method::Put];
+    assert_eq!(None, op.operation_id);
+    assert_eq!("put_objects", op.id("/objects", v3::Method::Put));
+    assert!(op.request_body.as_ref().unwrap().content.is_empty());
+    let media = &op.responses.as_ref().unwrap().items["200"]
+        .content
+        .as_ref()
+        .unwrap()["application/octet-stream"];
+    assert_eq!(None, media.schema);
+
+    let mut buf = Vec::new();
+    v3::to_yaml_writer(&mut buf, &spec)?;
+    let actual: serde_json::Value = serde_yaml::from_slice(&buf)?;
+    assert_eq!(expected, actual);
+
+    Ok(())
+}
+
+#[test]
+fn patterned_extensions_round_trip() -> Result<(), Box<dyn Error>> {
+    let content = r##"
+openapi: 3.0.3
+info:
+  title: Extensions
+  version: 1.0.0
+paths:
+  x-internal: {}
+  x-owner: storage
+  /objects:
+    post:
+      operationId: createObject
+      callbacks:
+        created:
+          x-retries: 3
+          '{$request.body#/url}':
+            post:
+              responses:
+                '204':
+                  description: Notified.
+                x-amz-foo: bar
+      responses:
+        '201':
+          description: Created.
+        x-amz-foo: bar
+"##;
+    let expected: serde_json::Value = serde_yaml::from_str(content)?;
+
+    let spec = v3::from_yaml_reader(content.as_bytes())?;
+    assert_eq!(
+        vec!["/objects"],
+        spec.paths.items.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["x-internal", "x-owner"],
+        spec.paths.extensions.keys().collect::<Vec<_>>()
+    );
+    let op = &spec.paths.items["/objects"].operations[&v3::Method::Post];
+    let responses = op.responses.as_ref().unwrap();
+    assert_eq!(vec!["201"], responses.items.keys().collect::<Vec<_>>());
+    assert_eq!(serde_json::json!("bar"), responses.extensions["x-amz-foo"]);
+    let callback = &op.callbacks.as_ref().unwrap()["created"];
+    assert_eq!(1, callback.paths.items.len());
+    assert_eq!(serde_json::json!(3), callback.paths.extensions["x-retries"]);
+
+    let mut buf = Vec::new();
+    v3::to_yaml_writer(&mut buf, &spec)?;
+    let actual: serde_json::Value = serde_yaml::from_slice(&buf)?;
+    assert_eq!(expected, actual);
+
+    let content = r##"
+swagger: '2.0'
+info:
+  title: Extensions
+  version: 1.0.0
+paths:
+  x-owner: storage
+  /objects:
+    get:
+      responses:
+        '200':
+          description: Objects.
+        x-amz-foo: bar
+"##;
+    let spec: v3::Spec = v2::from_yaml_reader(content.as_bytes())?.into();
+    assert_eq!(
+        serde_json::json!("storage"),
+        spec.paths.extensions["x-owner"]
+    );
+    let op = &spec.paths.items["/objects"].operations[&v3::Method::Get];
+    let responses = op.responses.as_ref().unwrap();
+    assert_eq!(serde_json::json!("bar"), responses.extensions["x-amz-foo"]);
+
+    Ok(())
+}
+
+#[test]
+fn v2_convert() -> Result<(), Box<dyn Error>> {
+    let spec = v2::from_yaml_reader(File::open("tests/petstore_v2.yaml")?)?;
+    let spec: v3::Spec = spec.into();
+
+    let servers: Vec<_> = spec.servers.iter().flatten().map(|v| &v.url).collect();
+    assert_eq!(
+        vec![
+            "https://petstore.swagger.io/v1",
+            "http://petstore.swagger.io/v1"
+        ],
+        servers
+    );
+
+    let components = spec.components.as_ref().unwrap();
+    assert!(components.schemas.as_ref().unwrap().contains_key("Pet"));
+    assert!(components
+        .parameters
+        .as_ref()
+        .unwrap()
+        .contains_key("Limit"));
+    assert!(components
+        .request_bodies
+        .as_ref()
+        .unwrap()
+        .contains_key("Pet"));
+    let schemes = components.security_schemes.as_ref().unwrap();
+    assert_eq!(Some("http"), schemes["basic"].type_.as_deref());
+    assert!(schemes["petstore_auth"]
+        .flows
+        .as_ref()
+        .unwrap()
+        .implicit
+        .is_some());
+
+    let pets = &spec.paths.items["/pets"].operations;
+    let list = &pets[&v3::Method::Get];
+    let response = &list.responses.as_ref().unwrap().items["200"];
+    assert_eq!(
+        Some("#/components/schemas/Pets"),
+        response.content.as_ref().unwrap()["application/json"]
+            .schema
+            .as_ref()
+            .unwrap()
+            .ref_
+            .as_deref()
+    );
+    let tags = &list.parameters.as_ref().unwrap()[1];
+    assert_eq!(
+        (Some("form"), Some(true)),
+        (tags.style.as_deref(), tags.explode)
+    );
+    assert_eq!(
+        Some("#/components/requestBodies/Pet"),
+        pets[&v3::Method::Post]
+            .request_body
+            .as_ref()
+            .unwrap()
+            .ref_
+            .as_deref()
+    );
+
+    // Operations without id are named after method and path.
+    let update = &spec.paths.items["/pets/{petId}"].operations[&v3::Method::Put];
+    assert_eq!(Some("put_pets_petId"), update.operation_id.as_deref());
+    let form = &update.request_body.as_ref().unwrap().content;
+    assert!(form.contains_key("application/x-www-form-urlencoded"));
+
+    let photo = &spec.paths.items["/pets/{petId}/photo"].operations;
+    let upload = &photo[&v3::Method::Post]
+        .request_body
+        .as_ref()
+        .unwrap()
+        .content;
+    let file = &upload["multipart/form-data"]
+        .schema
+        .as_ref()
+        .unwrap()
+        .properties
+        .as_ref()
+        .unwrap()["file"];
+    assert_eq!(Some("binary"), file.format.as_deref());
+    let download = &photo[&v3::Method::Get].responses.as_ref().unwrap().items["200"];
+    assert!(download.content.as_ref().unwrap().contains_key("image/png"));
+
+    Ok(())
+}
+
+#[test]
+fn v31_deserialize() -> Result<(), Box<dyn Error>> {
+    let expected: serde_json::Value =
+        serde_yaml::from_reader(File::open("tests/petstore_31.yaml")?)?;
+    assert_eq!(Version::V3_1, Version::detect(&expected)?);
+
+    let spec = v3::from_yaml_reader(File::open("tests/petstore_31.yaml")?)?;
+    assert_eq!(Some(Version::V3_1), spec.version());
+    assert!(spec.webhooks.as_ref().unwrap().contains_key("newPet"));
+
+    let pet = &spec.components.as_ref().unwrap().schemas.as_ref().unwrap()["Pet"];
+    let props = pet.properties.as_ref().unwrap();
+    assert_eq!(vec![&v3::SchemaType::String], props["tag"].types());
+    assert!(props["tag"].is_nullable());
+    assert!(!props["name"].is_nullable());
+    assert_eq!(2, props["position"].prefix_items.as_ref().unwrap().len());
+
+    let mut buf = Vec::new();
+    v3::to_yaml_writer(&mut buf, &spec)?;
+    let actual: serde_json::Value = serde_yaml::from_slice(&buf)?;
+    assert_eq!(expected, actual);
+
+    Ok(())
+}
+
+#[test]
+fn validate_valid() -> Result<(), Box<dyn Error>> {
+    let specs = vec![
+        v3::from_json_reader(File::open("tests/petstore.json")?)?,
+        v3::from_yaml_reader(File::open("tests/s3.yaml")?)?,
+        v3::from_yaml_reader(File::open("tests/petstore_31.yaml")?)?,
+        v2::from_yaml_reader(File::open("tests/petstore_v2.yaml")?)?.into(),
+    ];
+
+    for spec in specs {
+        assert_eq!(Vec::<v3::Issue>::new(), v3::validate(&spec));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn validate_invalid() -> Result<(), Box<dyn Error>> {
+    let spec = v3::from_yaml_reader(File::open("tests/invalid.yaml")?)?;
+
+    let issues = v3::validate(&spec);
+    let rules = |rule: &str| issues.iter().filter(|v| v.rule == rule).count();
+    assert_eq!(4, rules("schema"));
+    assert_eq!(1, rules("unique-operation-id"));
+    assert_eq!(3, rules("path-params"));
+    assert_eq!(1, rules("ref"));
+    assert_eq!(1, rules("status-code"));
+
+    let missing = issues.iter().find(|v| v.rule == "ref").unwrap();
+    assert_eq!(
+        "/paths/~1pets~1{petId}~1{other}/get/responses/200/content/application~1json/schema/$ref",
+        missing.pointer
+    );
+
+    Ok(())
+}
+
+#[test]
+fn validate_value() -> Result<(), Box<dyn Error>> {
+    // The misspelled operationId would be dropped, and the description
+    // could not be parsed.
+    let content = r##"
+openapi: 3.0.3
+info:
+  title: Invalid
+  version: 1.0.0
+paths:
+  /pets:
+    get:
+      operationid: listPets
+      responses:
+        '200':
+          description: 200
+"##;
+    let doc: serde_json::Value = serde_yaml::from_str(content)?;
+    assert!(serde_json::from_value::<v3::Spec>(doc.clone()).is_err());
+
+    let issues = v3::validate_value(&doc);
+    let pointers: Vec<_> = issues
+        .iter()
+        .map(|v| (v.rule, v.pointer.as_str()))
+        .collect();
+    assert_eq!(
+        vec![
+            ("schema", "/paths/~1pets/get/operationid"),
+            ("schema", "/paths/~1pets/get/responses/200/description"),
+        ],
+        pointers
+    );
+
+    // 3.1 documents have no meta-schema to locate errors of parsing.
+    let doc = serde_json::json!({
+        "openapi": "3.1.0",
+        "info": {"title": "Invalid", "version": "1.0.0"},
+        "paths": {"/pets": {"get": {"responses": {"200": {"description": 200}}}}},
+    });
+    let issues = v3::validate_value(&doc);
+    assert_eq!(
+        vec!["parse"],
+        issues.iter().map(|v| v.rule).collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn jsonschema_keywords() {
+    use crate::jsonschema::{Validator, KEYWORDS};
+    use serde_json::json;
+
+    // Each keyword with a schema, a valid value, an invalid value and the
+    // pointer of the error.
+    let cases = vec![
+        (
+            "$ref",
+            json!({"definitions": {"a": {"type": "string"}}, "$ref": "#/definitions/a"}),
+            json!("x"),
+            json!(1),
+            "",
+        ),
+        ("type", json!({"type": "integer"}), json!(1), json!(1.5), ""),
+        (
+            "enum",
+            json!({"enum": ["a", "b"]}),
+            json!("a"),
+            json!("c"),
+            "",
+        ),
+        (
+            "required",
+            json!({"required": ["a"]}),
+            json!({"a": 1}),
+            json!({}),
+            "",
+        ),
+        (
+            "minProperties",
+            json!({"minProperties": 1}),
+            json!({"a": 1}),
+            json!({}),
+            "",
+        ),
+        (
+            "maxProperties",
+            json!({"maxProperties": 1}),
+            json!({"a": 1}),
+            json!({"a": 1, "b": 2}),
+            "",
+        ),
+        (
+            "properties",
+            json!({"properties": {"a/b": {"type": "string"}}}),
+            json!({"a/b": "x"}),
+            json!({"a/b": 1}),
+            "/a~1b",
+        ),
+        (
+            "patternProperties",
+            json!({"patternProperties": {"^x-": {"type": "string"}}}),
+            json!({"x-a": "v", "b": 1}),
+            json!({"x-a": 1}),
+            "/x-a",
+        ),
+        (
+            "additionalProperties",
+            json!({"properties": {"a": {}}, "additionalProperties": false}),
+            json!({"a": 1}),
+            json!({"b": 1}),
+            "/b",
+        ),
+        (
+            "items",
+            json!({"items": {"type": "string"}}),
+            json!(["a"]),
+            json!(["a", 1]),
+            "/1",
+        ),
+        (
+            "minItems",
+            json!({"minItems": 1}),
+            json!(["a"]),
+            json!([]),
+            "",
+        ),
+        (
+            "uniqueItems",
+            json!({"uniqueItems": true}),
+            json!([1, 2]),
+            json!([1, 1]),
+            "/1",
+        ),
+        // Escaped slashes of ECMA 262 are supported.
+        (
+            "pattern",
+            json!({"pattern": "^\\/"}),
+            json!("/a"),
+            json!("a"),
+            "",
+        ),
+        ("minimum", json!({"minimum": 1}), json!(1), json!(0), ""),
+        ("maximum", json!({"maximum": 1}), json!(1), json!(2), ""),
+        (
+            "exclusiveMinimum",
+            json!({"minimum": 1, "exclusiveMinimum": true}),
+            json!(2),
+            json!(1),
+            "",
+        ),
+        (
+            "exclusiveMaximum",
+            json!({"maximum": 1, "exclusiveMaximum": true}),
+            json!(0),
+            json!(1),
+            "",
+        ),
+        (
+            "allOf",
+            json!({"allOf": [{"type": "object"}, {"required": ["a"]}]}),
+            json!({"a": 1}),
+            json!({}),
+            "",
+        ),
+        // Errors of the closest alternative are reported.
+        (
+            "anyOf",
+            json!({"anyOf": [
+                {"type": "object", "required": ["b", "c"]},
+                {"type": "object", "properties": {"a": {"type": "string"}}},
+            ]}),
+            json!({"a": "x"}),
+            json!({"a": 1}),
+            "/a",
+        ),
+        (
+            "oneOf",
+            json!({"oneOf": [{"type": "string"}, {"type": "integer"}, {"type": "number"}]}),
+            json!("x"),
+            json!(1),
+            "",
+        ),
+        (
+            "not",
+            json!({"not": {"required": ["$ref"]}}),
+            json!({}),
+            json!({"$ref": "x"}),
+            "",
+        ),
+    ];
+
+    let mut covered: Vec<&str> = cases.iter().map(|v| v.0).collect();
+    covered.sort_unstable();
+    let mut keywords = KEYWORDS.to_vec();
+    keywords.sort_unstable();
+    assert_eq!(keywords, covered);
+
+    for (keyword, schema, valid, invalid, pointer) in cases.iter() {
+        let mut validator = Validator::new(schema);
+        assert_eq!(
+            Vec::<crate::jsonschema::Error>::new(),
+            validator.validate(valid),
+            "{}",
+            keyword
+        );
+
+        let errs = validator.validate(invalid);
+        assert_eq!(1, errs.len(), "{}: {:?}", keyword, errs);
+        assert_eq!(*pointer, errs[0].pointer, "{}", keyword);
+    }
+}
+
+#[test]
+fn jsonschema_meta_schema_keywords() -> Result<(), Box<dyn Error>> {
+    use crate::jsonschema::unknown_keywords;
+
+    // All keywords used by the vendored meta-schema are known.
+    let schema: serde_json::Value = serde_json::from_str(include_str!("v3/openapi-3.0.json"))?;
+    assert!(unknown_keywords(&schema).is_empty());
+
+    let schema = serde_json::json!({
+        "properties": {"a": {"dependencies": {"a": ["b"]}}},
+        "oneOf": [{"multipleOf": 2}],
+    });
+    assert_eq!(
+        vec!["dependencies", "multipleOf"],
+        unknown_keywords(&schema).into_iter().collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn lint_default() -> Result<(), Box<dyn Error>> {
+    let spec = v3::from_yaml_reader(File::open("tests/lint.yaml")?)?;
+
+    let diags = v3::lint(&spec, &v3::Config::default());
+    let rules = |rule: &str| diags.iter().filter(|v| v.issue.rule == rule).count();
+    assert_eq!(1, rules("operation-id-case"));
+    // Suppressed by `x-qi-lint-ignore: true`.
+    assert_eq!(0, rules("schema-name-case"));
+    // Suppressed for getPet by `x-qi-lint-ignore` with rule names.
+    assert_eq!(1, rules("operation-description"));
+    assert_eq!(1, rules("operation-tags"));
+    assert_eq!(1, rules("no-inline-schema"));
+    assert_eq!(2, rules("error-responses"));
+    assert_eq!(1, rules("pagination"));
+
+    let pagination = diags.iter().find(|v| v.issue.rule == "pagination").unwrap();
+    assert_eq!(v3::Severity::Info, pagination.severity);
+    assert_eq!("/paths/~1pets/get/parameters", pagination.issue.pointer);
+
+    Ok(())
+}
+
+#[test]
+fn lint_config() -> Result<(), Box<dyn Error>> {
+    let spec = v3::from_yaml_reader(File::open("tests/lint.yaml")?)?;
+
+    let config =
+        v3::Config::from_reader("rules:\n  operation-tags: off\n  pagination: error\n".as_bytes())?;
+    let diags = v3::lint(&spec, &config);
+    assert!(diags.iter().all(|v| v.issue.rule != "operation-tags"));
+    let pagination = diags.iter().find(|v| v.issue.rule == "pagination").unwrap();
+    assert_eq!(v3::Severity::Error, pagination.severity);
+
+    assert!(v3::Config::from_reader("rules:\n  no-such-rule: off\n".as_bytes()).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn source_map() -> Result<(), Box<dyn Error>> {
+    let source = std::fs::read_to_string("tests/lint.yaml")?;
+    let spans = SourceMap::parse("tests/lint.yaml", &source)?;
+
+    let op_id = "/paths/~1pets/get/operationId";
+    assert_eq!(Some(Span { line: 8, column: 7 }), spans.key(op_id));
+    assert_eq!(
+        Some(Span {
+            line: 8,
+            column: 20
+        }),
+        spans.value(op_id)
+    );
+    assert_eq!(
+        Some(Span {
+            line: 38,
+            column: 3
+        }),
+        spans.key("/paths/~1pets~1{petId}")
+    );
+    // Pointers not in source are located by their closest ancestors.
+    assert_eq!(
+        spans.locate(op_id),
+        spans.locate(&format!("{}/missing", op_id))
+    );
+
+    let frame = spans.code_frame(spans.locate(op_id).unwrap());
+    assert!(frame.contains(">  8 |       operationId: list_pets\n     |       ^\n"));
+
+    let source = std::fs::read_to_string("tests/petstore.json")?;
+    let spans = SourceMap::parse("tests/petstore.json", &source)?;
+    assert_eq!(
+        Some(Span {
+            line: 92,
+            column: 9
+        }),
+        spans.key("/paths/~1pets~1{petId}/get/operationId")
+    );
 
     Ok(())
 }
@@ -0,0 +1,589 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::schema::*;
+use crate::v3;
+
+// ref: https://swagger.io/docs/specification/2-0/basic-structure/
+const DEFAULT_MEDIA_TYPE: &str = "application/json";
+const FORM_URLENCODED: &str = "application/x-www-form-urlencoded";
+const FORM_MULTIPART: &str = "multipart/form-data";
+
+/// Convert a Swagger 2.0 spec into OpenAPI 3.0, so that generators only
+/// need to handle one version.
+///
+/// - `definitions` are moved into `components.schemas`
+/// - `body` and `formData` parameters are merged into `requestBody`
+/// - `produces` and `consumes` are expanded into `content`
+/// - `host`, `basePath` and `schemes` are combined into `servers`
+impl From<Spec> for v3::Spec {
+    fn from(spec: Spec) -> Self {
+        let c = Converter { spec: &spec };
+
+        let mut paths = v3::Paths {
+            extensions: spec.paths.extensions.clone(),
+            ..Default::default()
+        };
+        for (path, item) in spec.paths.items.iter() {
+            paths.items.insert(path.clone(), c.path_item(path, item));
+        }
+
+        v3::Spec {
+            openapi: "3.0.3".to_string(),
+            info: spec.info.clone(),
+            servers: c.servers(),
+            paths,
+            components: Some(c.components()),
+            security: spec.security.clone(),
+            tags: spec.tags.clone(),
+            external_docs: spec.external_docs.clone(),
+            extensions: spec.extensions.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+struct Converter<'a> {
+    spec: &'a Spec,
+}
+
+impl<'a> Converter<'a> {
+    fn servers(&self) -> Option<Vec<v3::Server>> {
+        let spec = self.spec;
+        if spec.host.is_none() && spec.base_path.is_none() {
+            return None;
+        }
+
+        let base_path = spec.base_path.clone().unwrap_or_default();
+        let host = match spec.host.as_ref() {
+            // Relative to the location of the spec.
+            None => return Some(vec![server(base_path)]),
+            Some(v) => v,
+        };
+
+        let schemes = spec
+            .schemes
+            .clone()
+            .unwrap_or_else(|| vec!["https".to_string()]);
+
+        Some(
+            schemes
+                .iter()
+                .map(|scheme| server(format!("{}://{}{}", scheme, host, base_path)))
+                .collect(),
+        )
+    }
+
+    fn components(&self) -> v3::Components {
+        let spec = self.spec;
+
+        // Body parameters are request bodies in OpenAPI 3.0, while form
+        // parameters are inlined into operations' request bodies.
+        let mut parameters = BTreeMap::new();
+        let mut request_bodies = BTreeMap::new();
+        for (name, param) in spec.parameters.iter().flatten() {
+            match param.in_.as_deref() {
+                Some("body") => {
+                    let body = self.request_body(param, spec.consumes.as_ref());
+                    request_bodies.insert(name.clone(), body);
+                }
+                Some("formData") => {}
+                _ => {
+                    parameters.insert(name.clone(), parameter(param));
+                }
+            }
+        }
+
+        v3::Components {
+            schemas: spec.definitions.as_ref().map(|m| map_values(m, schema)),
+            responses: spec
+                .responses
+                .as_ref()
+                .map(|m| map_values(m, |v| self.response(v, spec.produces.as_ref()))),
+            parameters: if parameters.is_empty() {
+                None
+            } else {
+                Some(parameters)
+            },
+            request_bodies: if request_bodies.is_empty() {
+                None
+            } else {
+                Some(request_bodies)
+            },
+            security_schemes: spec
+                .security_definitions
+                .as_ref()
+                .map(|m| map_values(m, security_scheme)),
+            ..Default::default()
+        }
+    }
+
+    fn path_item(&self, path: &str, item: &PathItem) -> v3::PathItem {
+        let mut operations = BTreeMap::new();
+        for (method, op) in item.operations() {
+            operations.insert(method, self.operation(path, method, item, op));
+        }
+
+        // Body and form parameters of path item are merged into operations.
+        let parameters: Vec<_> = item
+            .parameters
+            .iter()
+            .flatten()
+            .filter(|v| !self.is_body(v))
+            .map(parameter)
+            .collect();
+
+        v3::PathItem {
+            ref_: item.ref_.clone(),
+            parameters: if parameters.is_empty() {
+                None
+            } else {
+                Some(parameters)
+            },
+            operations,
+            extensions: item.extensions.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn operation(
+        &self,
+        path: &str,
+        method: v3::Method,
+        item: &PathItem,
+        op: &Operation,
+    ) -> v3::Operation {
+        let consumes = op.consumes.as_ref().or(self.spec.consumes.as_ref());
+        let produces = op.produces.as_ref().or(self.spec.produces.as_ref());
+
+        let mut parameters = Vec::new();
+        let mut body = None;
+        let mut form = Vec::new();
+        let params = item.parameters.iter().flatten().map(|v| (true, v));
+        let params = params.chain(op.parameters.iter().flatten().map(|v| (false, v)));
+        for (shared, param) in params {
+            let resolved = self.resolve(param);
+            match resolved.in_.as_deref() {
+                // Keep the reference to body parameter, it will point to
+                // the request body in components.
+                Some("body") => body = Some(param),
+                Some("formData") => {
+                    form.retain(|v: &&Parameter| v.name != resolved.name);
+                    form.push(resolved)
+                }
+                _ if !shared => parameters.push(parameter(param)),
+                _ => {}
+            }
+        }
+
+        let request_body = match body {
+            // Referenced body parameters are moved into request bodies.
+            Some(param) if param.ref_.is_some() => Some(v3::RequestBody {
+                ref_: param.ref_.as_deref().map(reference),
+                ..Default::default()
+            }),
+            Some(param) => Some(self.request_body(param, consumes)),
+            None if !form.is_empty() => Some(self.form_body(&form, consumes)),
+            None => None,
+        };
+
+        v3::Operation {
+            tags: op.tags.clone(),
+            summary: op.summary.clone(),
+            description: op.description.clone(),
+            external_docs: op.external_docs.clone(),
+            operation_id: Some(
+                op.operation_id
+                    .clone()
+                    .unwrap_or_else(|| v3::operation_id(path, method)),
+            ),
+            parameters: if parameters.is_empty() {
+                None
+            } else {
+                Some(parameters)
+            },
+            request_body,
+            responses: Some(v3::Responses {
+                items: map_values(&op.responses.items, |v| self.response(v, produces)),
+                extensions: op.responses.extensions.clone(),
+            }),
+            deprecated: op.deprecated,
+            security: op.security.clone(),
+            extensions: op.extensions.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if the parameter is a body or form parameter, which
+    /// will be converted into request body.
+    fn is_body(&self, param: &Parameter) -> bool {
+        matches!(
+            self.resolve(param).in_.as_deref(),
+            Some("body") | Some("formData")
+        )
+    }
+
+    /// Resolve a reference to a parameter defined in `parameters`.
+    fn resolve<'b>(&'b self, param: &'b Parameter) -> &'b Parameter {
+        let name = match param.ref_.as_deref() {
+            None => return param,
+            Some(v) => v.trim_start_matches("#/parameters/"),
+        };
+
+        self.spec
+            .parameters
+            .as_ref()
+            .and_then(|m| m.get(name))
+            .unwrap_or(param)
+    }
+
+    fn request_body(&self, param: &Parameter, consumes: Option<&Vec<String>>) -> v3::RequestBody {
+        let schema = schema(param.schema.as_ref().unwrap());
+
+        v3::RequestBody {
+            description: param.description.clone(),
+            content: media_types(consumes)
+                .into_iter()
+                .map(|v| {
+                    let media_type = v3::MediaType {
+                        schema: Some(schema.clone()),
+                        ..Default::default()
+                    };
+                    (v, media_type)
+                })
+                .collect(),
+            required: param.required,
+            ..Default::default()
+        }
+    }
+
+    /// Merge form parameters into an object schema, files are only allowed
+    /// in multipart forms.
+    fn form_body(&self, params: &[&Parameter], consumes: Option<&Vec<String>>) -> v3::RequestBody {
+        let has_file = params
+            .iter()
+            .any(|v| v.items.type_.as_deref() == Some("file"));
+        let multipart = consumes
+            .iter()
+            .flat_map(|v| v.iter())
+            .any(|v| v == FORM_MULTIPART);
+        let content_type = if has_file || multipart {
+            FORM_MULTIPART
+        } else {
+            FORM_URLENCODED
+        };
+
+        let mut properties = BTreeMap::new();
+        let mut required = Vec::new();
+        let mut encoding = BTreeMap::new();
+        for param in params {
+            let name = param.name.clone().unwrap();
+            let mut schema = items(&param.items);
+            schema.description = param.description.clone();
+            if param.required.unwrap_or(false) {
+                required.push(name.clone());
+            }
+            if let Some((style, explode)) = collection_format(&param.items, "query") {
+                encoding.insert(
+                    name.clone(),
+                    v3::Encoding {
+                        style: Some(style),
+                        explode: Some(explode),
+                        ..Default::default()
+                    },
+                );
+            }
+            properties.insert(name, schema);
+        }
+
+        let media_type = v3::MediaType {
+            schema: Some(v3::Schema {
+                type_: Some(v3::Type::Single(v3::SchemaType::Object)),
+                properties: Some(properties),
+                required: if required.is_empty() {
+                    None
+                } else {
+                    Some(required)
+                },
+                ..Default::default()
+            }),
+            encoding: if encoding.is_empty() {
+                None
+            } else {
+                Some(encoding)
+            },
+            ..Default::default()
+        };
+
+        let mut content = BTreeMap::new();
+        content.insert(content_type.to_string(), media_type);
+
+        v3::RequestBody {
+            content,
+            required: Some(params.iter().any(|v| v.required.unwrap_or(false))),
+            ..Default::default()
+        }
+    }
+
+    fn response(&self, response: &Response, produces: Option<&Vec<String>>) -> v3::Response {
+        if let Some(r) = response.ref_.as_deref() {
+            return v3::Response {
+                ref_: Some(reference(r)),
+                ..Default::default()
+            };
+        }
+
+        let content = response.schema.as_ref().map(|v| {
+            let schema = schema(v);
+            media_types(produces)
+                .into_iter()
+                .map(|v| {
+                    let media_type = v3::MediaType {
+                        schema: Some(schema.clone()),
+                        example: response.examples.as_ref().and_then(|m| m.get(&v)).cloned(),
+                        ..Default::default()
+                    };
+                    (v, media_type)
+                })
+                .collect()
+        });
+
+        v3::Response {
+            description: response.description.clone(),
+            headers: response.headers.as_ref().map(|m| {
+                map_values(m, |v| v3::Header {
+                    description: v.description.clone(),
+                    schema: Some(items(&v.items)),
+                    ..Default::default()
+                })
+            }),
+            content,
+            extensions: response.extensions.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+fn server(url: String) -> v3::Server {
+    v3::Server {
+        url,
+        ..Default::default()
+    }
+}
+
+fn map_values<T, U, F>(m: &BTreeMap<String, T>, f: F) -> BTreeMap<String, U>
+where
+    F: Fn(&T) -> U,
+{
+    m.iter().map(|(k, v)| (k.clone(), f(v))).collect()
+}
+
+fn media_types(v: Option<&Vec<String>>) -> Vec<String> {
+    match v {
+        Some(v) if !v.is_empty() => v.clone(),
+        _ => vec![DEFAULT_MEDIA_TYPE.to_string()],
+    }
+}
+
+/// Rewrite references into components, like `#/definitions/Pet` into
+/// `#/components/schemas/Pet`.
+fn reference(r: &str) -> String {
+    let prefixes = [
+        ("#/definitions/", "#/components/schemas/"),
+        ("#/responses/", "#/components/responses/"),
+        // Only body parameters are referenced as request bodies.
+        ("#/parameters/", "#/components/requestBodies/"),
+    ];
+    for (from, to) in prefixes.iter() {
+        if let Some(name) = r.strip_prefix(from) {
+            return format!("{}{}", to, name);
+        }
+    }
+
+    r.to_string()
+}
+
+fn parameter(param: &Parameter) -> v3::Parameter {
+    if let Some(r) = param.ref_.as_deref() {
+        return v3::Parameter {
+            ref_: Some(r.replace("#/parameters/", "#/components/parameters/")),
+            ..Default::default()
+        };
+    }
+
+    let location = param.in_.clone().unwrap();
+    let (style, explode) = match collection_format(&param.items, &location) {
+        Some((style, explode)) => (Some(style), Some(explode)),
+        None => (None, None),
+    };
+
+    v3::Parameter {
+        name: param.name.clone(),
+        description: param.description.clone(),
+        required: param.required,
+        allow_empty_value: param.allow_empty_value,
+        style,
+        explode,
+        schema: Some(items(&param.items)),
+        in_: Some(location),
+        ..Default::default()
+    }
+}
+
+/// Convert `collectionFormat` of arrays into `style` and `explode`.
+fn collection_format(v: &Items, location: &str) -> Option<(String, bool)> {
+    if v.type_.as_deref() != Some("array") {
+        return None;
+    }
+
+    let style = match v.collection_format.as_deref().unwrap_or("csv") {
+        "multi" => return Some(("form".to_string(), true)),
+        "ssv" => "spaceDelimited",
+        "pipes" => "pipeDelimited",
+        // tsv is not supported in OpenAPI 3.0, csv is used instead.
+        _ if location == "query" || location == "formData" => "form",
+        _ => "simple",
+    };
+
+    Some((style.to_string(), false))
+}
+
+fn schema_type(v: &str) -> Option<v3::Type> {
+    let typ = match v {
+        "boolean" => v3::SchemaType::Boolean,
+        "object" => v3::SchemaType::Object,
+        "array" => v3::SchemaType::Array,
+        "number" => v3::SchemaType::Number,
+        "string" | "file" => v3::SchemaType::String,
+        "integer" => v3::SchemaType::Integer,
+        _ => return None,
+    };
+
+    Some(v3::Type::Single(typ))
+}
+
+/// Convert the type of non-body parameters and headers into schema.
+fn items(v: &Items) -> v3::Schema {
+    let format = match v.type_.as_deref() {
+        Some("file") => Some("binary".to_string()),
+        _ => v.format.clone(),
+    };
+
+    v3::Schema {
+        type_: v.type_.as_deref().and_then(schema_type),
+        format,
+        items: v.items.as_ref().map(|v| Box::new(items(v))),
+        default: v.default.clone(),
+        maximum: v.maximum.clone(),
+        exclusive_maximum: v.exclusive_maximum.map(Value::Bool),
+        minimum: v.minimum.clone(),
+        exclusive_minimum: v.exclusive_minimum.map(Value::Bool),
+        max_length: v.max_length,
+        min_length: v.min_length,
+        pattern: v.pattern.clone(),
+        max_items: v.max_items,
+        min_items: v.min_items,
+        unique_items: v.unique_items,
+        enum_: v.enum_.clone(),
+        multiple_of: v.multiple_of.clone(),
+        extensions: v.extensions.clone(),
+        ..Default::default()
+    }
+}
+
+fn schema(v: &Schema) -> v3::Schema {
+    let format = match v.type_.as_deref() {
+        Some("file") => Some("binary".to_string()),
+        _ => v.format.clone(),
+    };
+
+    let additional_properties = v.additional_properties.as_ref().map(|v| match v {
+        AdditionalProperties::Bool(v) => v3::AdditionalProperties::Bool(*v),
+        AdditionalProperties::Schema(v) => v3::AdditionalProperties::Schema(Box::new(schema(v))),
+    });
+
+    // `x-nullable` is widely used as the nullable of Swagger 2.0.
+    let mut extensions = v.extensions.clone();
+    let nullable = extensions.remove("x-nullable").and_then(|v| v.as_bool());
+
+    v3::Schema {
+        ref_: v.ref_.as_deref().map(reference),
+        title: v.title.clone(),
+        multiple_of: v.multiple_of.clone(),
+        maximum: v.maximum.clone(),
+        exclusive_maximum: v.exclusive_maximum.map(Value::Bool),
+        minimum: v.minimum.clone(),
+        exclusive_minimum: v.exclusive_minimum.map(Value::Bool),
+        max_length: v.max_length,
+        min_length: v.min_length,
+        pattern: v.pattern.clone(),
+        max_items: v.max_items,
+        min_items: v.min_items,
+        unique_items: v.unique_items,
+        max_properties: v.max_properties,
+        min_properties: v.min_properties,
+        required: v.required.clone(),
+        enum_: v.enum_.clone(),
+        type_: v.type_.as_deref().and_then(schema_type),
+        all_of: v.all_of.as_ref().map(|v| v.iter().map(schema).collect()),
+        items: v.items.as_ref().map(|v| Box::new(schema(v))),
+        properties: v.properties.as_ref().map(|m| map_values(m, schema)),
+        additional_properties,
+        description: v.description.clone(),
+        format,
+        default: v.default.clone(),
+        nullable,
+        discriminator: v.discriminator.clone().map(|v| v3::Discriminator {
+            property_name: v,
+            ..Default::default()
+        }),
+        read_only: v.read_only,
+        xml: v.xml.clone(),
+        external_docs: v.external_docs.clone(),
+        example: v.example.clone(),
+        extensions,
+        ..Default::default()
+    }
+}
+
+fn security_scheme(v: &SecurityScheme) -> v3::SecurityScheme {
+    let mut scheme = v3::SecurityScheme {
+        description: v.description.clone(),
+        extensions: v.extensions.clone(),
+        ..Default::default()
+    };
+
+    match v.type_.as_str() {
+        "basic" => {
+            scheme.type_ = Some("http".to_string());
+            scheme.scheme = Some("basic".to_string());
+        }
+        "apiKey" => {
+            scheme.type_ = Some("apiKey".to_string());
+            scheme.name = v.name.clone();
+            scheme.in_ = v.in_.clone();
+        }
+        "oauth2" => {
+            let flow = v3::OAuthFlow {
+                authorization_url: v.authorization_url.clone(),
+                token_url: v.token_url.clone(),
+                scopes: v.scopes.clone().unwrap_or_default(),
+                ..Default::default()
+            };
+            let mut flows = v3::OAuthFlows::default();
+            match v.flow.as_deref() {
+                Some("implicit") => flows.implicit = Some(flow),
+                Some("password") => flows.password = Some(flow),
+                Some("application") => flows.client_credentials = Some(flow),
+                Some("accessCode") => flows.authorization_code = Some(flow),
+                _ => {}
+            }
+
+            scheme.type_ = Some("oauth2".to_string());
+            scheme.flows = Some(flows);
+        }
+        _ => scheme.type_ = Some(v.type_.clone()),
+    }
+
+    scheme
+}
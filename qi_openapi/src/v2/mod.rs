@@ -0,0 +1,6 @@
+mod convert;
+mod helper;
+mod schema;
+
+pub use helper::*;
+pub use schema::*;
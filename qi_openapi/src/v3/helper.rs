@@ -17,3 +17,21 @@ where
 
     Ok(spec)
 }
+
+pub fn to_json_writer<W>(w: W, spec: &super::schema::Spec) -> Result<(), Box<dyn Error>>
+where
+    W: std::io::Write,
+{
+    serde_json::to_writer_pretty(w, spec)?;
+
+    Ok(())
+}
+
+pub fn to_yaml_writer<W>(w: W, spec: &super::schema::Spec) -> Result<(), Box<dyn Error>>
+where
+    W: std::io::Write,
+{
+    serde_yaml::to_writer(w, spec)?;
+
+    Ok(())
+}
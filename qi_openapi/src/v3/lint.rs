@@ -0,0 +1,412 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io::Read;
+
+use super::schema::*;
+use super::validate::{resolve_parameter, Issue};
+use crate::jsonschema::escape;
+
+/// Extension to suppress lint rules for a location and everything under it.
+///
+/// The value could be a rule name, a list of rule names, or `true` for all
+/// rules.
+pub const X_QI_LINT_IGNORE: &str = "x-qi-lint-ignore";
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Off,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Off => "off",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Rule is a named check with its default severity.
+pub struct Rule {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub severity: Severity,
+    check: fn(&Spec, &mut Vec<Issue>),
+}
+
+/// All lint rules in the order they are run.
+pub const RULES: &[Rule] = &[
+    Rule {
+        name: "operation-id-case",
+        description: "operationId should be camelCase",
+        severity: Severity::Warning,
+        check: check_operation_id_case,
+    },
+    Rule {
+        name: "schema-name-case",
+        description: "Component schema names should be PascalCase",
+        severity: Severity::Warning,
+        check: check_schema_name_case,
+    },
+    Rule {
+        name: "operation-description",
+        description: "Operations should have a summary or description",
+        severity: Severity::Warning,
+        check: check_operation_description,
+    },
+    Rule {
+        name: "operation-tags",
+        description: "Operations should have at least one tag",
+        severity: Severity::Warning,
+        check: check_operation_tags,
+    },
+    Rule {
+        name: "no-inline-schema",
+        description: "Object schemas of request and response bodies should be components",
+        severity: Severity::Warning,
+        check: check_inline_schemas,
+    },
+    Rule {
+        name: "error-responses",
+        description: "Operations should declare 4XX, 5XX or default responses",
+        severity: Severity::Warning,
+        check: check_error_responses,
+    },
+    Rule {
+        name: "pagination",
+        description: "List operations should accept limit and offset, cursor or page",
+        severity: Severity::Info,
+        check: check_pagination,
+    },
+];
+
+/// Config overrides severities of rules, which is loaded from a file like:
+///
+/// ```yaml
+/// rules:
+///   operation-tags: off
+///   pagination: error
+/// ```
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Config {
+    #[serde(default)]
+    pub rules: BTreeMap<String, Severity>,
+}
+
+impl Config {
+    /// Load config from YAML or JSON, unknown rules are rejected.
+    pub fn from_reader<R: Read>(r: R) -> Result<Config, Box<dyn Error>> {
+        let cfg: Config = serde_yaml::from_reader(r)?;
+        for name in cfg.rules.keys() {
+            if !RULES.iter().any(|v| v.name == name) {
+                return Err(format!("unknown lint rule {}", name).into());
+            }
+        }
+
+        Ok(cfg)
+    }
+
+    pub fn severity(&self, rule: &Rule) -> Severity {
+        self.rules.get(rule.name).copied().unwrap_or(rule.severity)
+    }
+}
+
+/// Diagnostic is an issue reported by lint with its severity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub issue: Issue,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.issue)
+    }
+}
+
+/// Lint a spec with all enabled rules, issues under locations with
+/// `x-qi-lint-ignore` are suppressed.
+pub fn lint(spec: &Spec, config: &Config) -> Vec<Diagnostic> {
+    let doc = serde_json::to_value(spec).unwrap();
+    let mut ignores = Vec::new();
+    collect_ignores(&doc, "", &mut ignores);
+
+    let mut diags = Vec::new();
+    for rule in RULES {
+        let severity = config.severity(rule);
+        if severity == Severity::Off {
+            continue;
+        }
+
+        let mut issues = Vec::new();
+        (rule.check)(spec, &mut issues);
+        diags.extend(
+            issues
+                .into_iter()
+                .filter(|v| !is_ignored(&ignores, v))
+                .map(|issue| Diagnostic { severity, issue }),
+        );
+    }
+
+    diags
+}
+
+/// Collect pointers with `x-qi-lint-ignore` and the rules they ignore, an
+/// empty list means all rules.
+fn collect_ignores(value: &Value, ptr: &str, ignores: &mut Vec<(String, Vec<String>)>) {
+    match value {
+        Value::Object(m) => {
+            match m.get(X_QI_LINT_IGNORE) {
+                Some(Value::Bool(true)) => ignores.push((ptr.to_string(), Vec::new())),
+                Some(Value::String(v)) => ignores.push((ptr.to_string(), vec![v.clone()])),
+                Some(Value::Array(v)) => ignores.push((
+                    ptr.to_string(),
+                    v.iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect(),
+                )),
+                _ => {}
+            }
+            for (k, v) in m {
+                collect_ignores(v, &format!("{}/{}", ptr, escape(k)), ignores);
+            }
+        }
+        Value::Array(v) => {
+            for (idx, v) in v.iter().enumerate() {
+                collect_ignores(v, &format!("{}/{}", ptr, idx), ignores);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_ignored(ignores: &[(String, Vec<String>)], issue: &Issue) -> bool {
+    ignores.iter().any(|(ptr, rules)| {
+        let under = issue.pointer == *ptr || issue.pointer.starts_with(&format!("{}/", ptr));
+        under && (rules.is_empty() || rules.iter().any(|v| v == issue.rule))
+    })
+}
+
+fn issue(rule: &'static str, pointer: String, message: String) -> Issue {
+    Issue {
+        rule,
+        pointer,
+        message,
+    }
+}
+
+fn pointer(segments: &[&str]) -> String {
+    segments.iter().map(|v| format!("/{}", escape(v))).collect()
+}
+
+fn operations(spec: &Spec) -> impl Iterator<Item = (&String, Method, &Operation)> {
+    spec.paths.items.iter().flat_map(|(path, item)| {
+        item.operations
+            .iter()
+            .map(move |(method, op)| (path, *method, op))
+    })
+}
+
+fn is_camel_case(v: &str) -> bool {
+    v.starts_with(|c: char| c.is_ascii_lowercase()) && v.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn is_pascal_case(v: &str) -> bool {
+    v.starts_with(|c: char| c.is_ascii_uppercase()) && v.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn check_operation_id_case(spec: &Spec, issues: &mut Vec<Issue>) {
+    for (path, method, op) in operations(spec) {
+        // Generated ids of operations without one are not checked.
+        let id = match &op.operation_id {
+            Some(v) => v,
+            None => continue,
+        };
+        if !is_camel_case(id) {
+            issues.push(issue(
+                "operation-id-case",
+                pointer(&["paths", path, method.as_str(), "operationId"]),
+                format!("operationId {} is not camelCase", id),
+            ));
+        }
+    }
+}
+
+fn check_schema_name_case(spec: &Spec, issues: &mut Vec<Issue>) {
+    let schemas = spec.components.as_ref().and_then(|v| v.schemas.as_ref());
+    for name in schemas.into_iter().flat_map(|v| v.keys()) {
+        if !is_pascal_case(name) {
+            issues.push(issue(
+                "schema-name-case",
+                pointer(&["components", "schemas", name]),
+                format!("schema {} is not PascalCase", name),
+            ));
+        }
+    }
+}
+
+fn check_operation_description(spec: &Spec, issues: &mut Vec<Issue>) {
+    let is_empty = |v: &Option<String>| v.as_deref().is_none_or(|v| v.trim().is_empty());
+
+    for (path, method, op) in operations(spec) {
+        if is_empty(&op.summary) && is_empty(&op.description) {
+            issues.push(issue(
+                "operation-description",
+                pointer(&["paths", path, method.as_str()]),
+                format!(
+                    "operation {} has no summary or description",
+                    op.id(path, method)
+                ),
+            ));
+        }
+    }
+}
+
+fn check_operation_tags(spec: &Spec, issues: &mut Vec<Issue>) {
+    for (path, method, op) in operations(spec) {
+        if op.tags.as_ref().is_none_or(Vec::is_empty) {
+            issues.push(issue(
+                "operation-tags",
+                pointer(&["paths", path, method.as_str()]),
+                format!("operation {} has no tags", op.id(path, method)),
+            ));
+        }
+    }
+}
+
+fn check_inline_schemas(spec: &Spec, issues: &mut Vec<Issue>) {
+    let is_inline_object = |s: &Option<Schema>| {
+        s.as_ref()
+            .is_some_and(|s| s.reference().is_none() && s.properties.is_some())
+    };
+
+    for (path, method, op) in operations(spec) {
+        let op_ptr = ["paths", path, method.as_str()];
+
+        if let Some(body) = &op.request_body {
+            for (mime, media) in body.content.iter() {
+                if is_inline_object(&media.schema) {
+                    issues.push(issue(
+                        "no-inline-schema",
+                        pointer(
+                            &[&op_ptr[..], &["requestBody", "content", mime, "schema"]].concat(),
+                        ),
+                        format!(
+                            "request body of {} is an inline object",
+                            op.id(path, method)
+                        ),
+                    ));
+                }
+            }
+        }
+        for (code, resp) in op.responses.iter().flat_map(|v| v.items.iter()) {
+            for (mime, media) in resp.content.iter().flatten() {
+                if is_inline_object(&media.schema) {
+                    issues.push(issue(
+                        "no-inline-schema",
+                        pointer(
+                            &[&op_ptr[..], &["responses", code, "content", mime, "schema"]]
+                                .concat(),
+                        ),
+                        format!(
+                            "response {} of {} is an inline object",
+                            code,
+                            op.id(path, method)
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn check_error_responses(spec: &Spec, issues: &mut Vec<Issue>) {
+    for (path, method, op) in operations(spec) {
+        let has_error = op
+            .responses
+            .iter()
+            .flat_map(|v| v.items.keys())
+            .any(|v| v == "default" || v.starts_with('4') || v.starts_with('5'));
+        if !has_error {
+            issues.push(issue(
+                "error-responses",
+                pointer(&["paths", path, method.as_str(), "responses"]),
+                format!(
+                    "operation {} declares no error responses",
+                    op.id(path, method)
+                ),
+            ));
+        }
+    }
+}
+
+/// A list operation is a `GET` whose successful response is an array.
+fn check_pagination(spec: &Spec, issues: &mut Vec<Issue>) {
+    for (path, method, op) in operations(spec) {
+        if method != Method::Get || !returns_array(spec, op) {
+            continue;
+        }
+
+        let item = &spec.paths.items[path];
+        let queries: Vec<&str> = item
+            .parameters
+            .iter()
+            .flatten()
+            .chain(op.parameters.iter().flatten())
+            .filter_map(|v| resolve_parameter(spec, v))
+            .filter(|v| v.in_.as_deref() == Some("query"))
+            .filter_map(|v| v.name.as_deref())
+            .collect();
+
+        let has_limit = queries.contains(&"limit");
+        let has_position = queries
+            .iter()
+            .any(|v| ["offset", "cursor", "page"].contains(v));
+        if !has_limit || !has_position {
+            issues.push(issue(
+                "pagination",
+                pointer(&["paths", path, method.as_str(), "parameters"]),
+                format!(
+                    "list operation {} should accept limit and one of offset, cursor or page",
+                    op.id(path, method)
+                ),
+            ));
+        }
+    }
+}
+
+fn returns_array(spec: &Spec, op: &Operation) -> bool {
+    op.responses
+        .iter()
+        .flat_map(|v| v.items.iter())
+        .filter(|(code, _)| code.starts_with('2'))
+        .flat_map(|(_, resp)| resp.content.iter().flatten())
+        .filter_map(|(_, media)| media.schema.as_ref())
+        .any(|media_schema| {
+            let schema = match media_schema.reference() {
+                None => Some(media_schema),
+                Some(r) => spec
+                    .components
+                    .as_ref()
+                    .and_then(|c| c.schemas.as_ref())
+                    .and_then(|m| m.get(r.trim_start_matches("#/components/schemas/"))),
+            };
+            schema.is_some_and(|s| s.types().contains(&&SchemaType::Array))
+        })
+}
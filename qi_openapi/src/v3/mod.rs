@@ -1,5 +1,9 @@
 mod helper;
+mod lint;
 mod schema;
+mod validate;
 
 pub use helper::*;
+pub use lint::*;
 pub use schema::*;
+pub use validate::*;
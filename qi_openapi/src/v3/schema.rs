@@ -1,11 +1,206 @@
-use serde::{Deserialize, Serialize};
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Number, Value};
 use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+use crate::Version;
+
+/// Extensions keeps all fields which are not defined in the specification,
+/// including vendor extensions like `x-amz-*`, so that load -> save is
+/// lossless.
+pub type Extensions = BTreeMap<String, Value>;
+
+/// Patterned holds objects keyed by patterned fields, like path items keyed
+/// by paths, and extensions keyed by `x-` which are not objects.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Patterned<T> {
+    pub items: BTreeMap<String, T>,
+    pub extensions: Extensions,
+}
+
+/// Paths maps paths like `/pets/{petId}` to their path items.
+pub type Paths = Patterned<PathItem>;
+
+/// Responses maps status codes like `200`, `2XX` or `default` to their
+/// responses.
+pub type Responses = Patterned<Response>;
+
+impl<T> Patterned<T> {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.extensions.is_empty()
+    }
+}
+
+impl<T> Default for Patterned<T> {
+    fn default() -> Self {
+        Patterned {
+            items: BTreeMap::new(),
+            extensions: Extensions::new(),
+        }
+    }
+}
+
+impl<T> From<BTreeMap<String, T>> for Patterned<T> {
+    fn from(items: BTreeMap<String, T>) -> Self {
+        Patterned {
+            items,
+            extensions: Extensions::new(),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Patterned<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PatternedVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for PatternedVisitor<T> {
+            type Value = Patterned<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut v = Patterned::default();
+                while let Some(key) = map.next_key::<String>()? {
+                    if key.starts_with("x-") {
+                        v.extensions.insert(key, map.next_value()?);
+                    } else {
+                        v.items.insert(key, map.next_value()?);
+                    }
+                }
+
+                Ok(v)
+            }
+        }
+
+        deserializer.deserialize_map(PatternedVisitor(PhantomData))
+    }
+}
+
+impl<T: Serialize> Serialize for Patterned<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.items.len() + self.extensions.len()))?;
+        for (k, v) in self.items.iter() {
+            map.serialize_entry(k, v)?;
+        }
+        for (k, v) in self.extensions.iter() {
+            map.serialize_entry(k, v)?;
+        }
+
+        map.end()
+    }
+}
+
+/// SecurityRequirement maps security scheme names to the scopes required.
+pub type SecurityRequirement = BTreeMap<String, Vec<String>>;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
 pub struct Spec {
     pub openapi: String,
-    pub paths: BTreeMap<String, PathItem>,
+    pub info: Info,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "jsonSchemaDialect")]
+    pub json_schema_dialect: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub servers: Option<Vec<Server>>,
+    // paths is optional since 3.1.
+    #[serde(default, skip_serializing_if = "Paths::is_empty")]
+    pub paths: Paths,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhooks: Option<BTreeMap<String, PathItem>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub components: Option<Components>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security: Option<Vec<SecurityRequirement>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<Tag>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "externalDocs")]
+    pub external_docs: Option<ExternalDocs>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+impl Spec {
+    /// Returns the version declared by `openapi`, or `None` if unsupported.
+    pub fn version(&self) -> Option<Version> {
+        Version::parse(&self.openapi)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Info {
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "termsOfService")]
+    pub terms_of_service: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact: Option<Contact>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<License>,
+    pub version: String,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Contact {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct License {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Server {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables: Option<BTreeMap<String, ServerVariable>>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct ServerVariable {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "enum")]
+    pub enum_: Option<Vec<String>>,
+    pub default: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 #[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Hash, Copy, Clone, Ord, PartialOrd)]
@@ -21,29 +216,156 @@ pub enum Method {
     Trace,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "get",
+            Method::Put => "put",
+            Method::Post => "post",
+            Method::Delete => "delete",
+            Method::Head => "head",
+            Method::Patch => "patch",
+            Method::Options => "options",
+            Method::Trace => "trace",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(from = "RawPathItem", into = "RawPathItem")]
 pub struct PathItem {
+    pub ref_: Option<String>,
     pub summary: Option<String>,
     pub description: Option<String>,
-
+    pub servers: Option<Vec<Server>>,
     pub parameters: Option<Vec<Parameter>>,
 
-    #[serde(flatten)]
     pub operations: BTreeMap<Method, Operation>,
+
+    pub extensions: Extensions,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+/// RawPathItem is the path item as it is written in the spec, operations
+/// are collected into a map in `PathItem` for convenience.
+#[derive(Deserialize, Serialize)]
+struct RawPathItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$ref")]
+    ref_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    get: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    put: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    post: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delete: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    head: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    patch: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    servers: Option<Vec<Server>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parameters: Option<Vec<Parameter>>,
+
+    #[serde(flatten)]
+    extensions: Extensions,
+}
+
+impl From<RawPathItem> for PathItem {
+    fn from(v: RawPathItem) -> Self {
+        let mut operations = BTreeMap::new();
+        let ops = vec![
+            (Method::Get, v.get),
+            (Method::Put, v.put),
+            (Method::Post, v.post),
+            (Method::Delete, v.delete),
+            (Method::Options, v.options),
+            (Method::Head, v.head),
+            (Method::Patch, v.patch),
+            (Method::Trace, v.trace),
+        ];
+        for (method, op) in ops {
+            if let Some(op) = op {
+                operations.insert(method, op);
+            }
+        }
+
+        PathItem {
+            ref_: v.ref_,
+            summary: v.summary,
+            description: v.description,
+            servers: v.servers,
+            parameters: v.parameters,
+            operations,
+            extensions: v.extensions,
+        }
+    }
+}
+
+impl From<PathItem> for RawPathItem {
+    fn from(mut v: PathItem) -> Self {
+        RawPathItem {
+            ref_: v.ref_,
+            summary: v.summary,
+            description: v.description,
+            get: v.operations.remove(&Method::Get),
+            put: v.operations.remove(&Method::Put),
+            post: v.operations.remove(&Method::Post),
+            delete: v.operations.remove(&Method::Delete),
+            options: v.operations.remove(&Method::Options),
+            head: v.operations.remove(&Method::Head),
+            patch: v.operations.remove(&Method::Patch),
+            trace: v.operations.remove(&Method::Trace),
+            servers: v.servers,
+            parameters: v.parameters,
+            extensions: v.extensions,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
 pub struct Components {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub schemas: Option<BTreeMap<String, Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub responses: Option<BTreeMap<String, Response>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<BTreeMap<String, Parameter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub examples: Option<BTreeMap<String, Example>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "requestBodies")]
     pub request_bodies: Option<BTreeMap<String, RequestBody>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<BTreeMap<String, Header>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "securitySchemes")]
+    pub security_schemes: Option<BTreeMap<String, SecurityScheme>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<BTreeMap<String, Link>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callbacks: Option<BTreeMap<String, Callback>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "pathItems")]
+    pub path_items: Option<BTreeMap<String, PathItem>>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 // ref: https://swagger.io/specification/
 //   - integer as a type is also supported and is defined as a JSON number without a fraction or exponent part.
-//   - null is not supported as a type (see nullable for an alternative solution).
+//   - null is not supported as a type in 3.0 (see nullable for an alternative solution), but is allowed in 3.1.
 #[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum SchemaType {
@@ -53,62 +375,587 @@ pub enum SchemaType {
     Number,
     String,
     Integer,
+    Null,
 }
 
+/// Type is a single type, or a list of types since 3.1 like
+/// `[string, "null"]`.
 #[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum Type {
+    Single(SchemaType),
+    Multiple(Vec<SchemaType>),
+}
+
+/// Reference is implemented by objects which could be replaced by a
+/// reference object like `{"$ref": "#/components/schemas/Pet"}`.
+pub trait Reference {
+    fn reference(&self) -> Option<&str>;
+}
+
+macro_rules! impl_reference {
+    ($($t:ty),*) => {
+        $(
+            impl Reference for $t {
+                fn reference(&self) -> Option<&str> {
+                    self.ref_.as_deref()
+                }
+            }
+        )*
+    };
+}
+
+impl_reference!(
+    Schema,
+    Response,
+    Parameter,
+    RequestBody,
+    Header,
+    Example,
+    Link,
+    SecurityScheme,
+    Callback,
+    PathItem
+);
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
 pub struct Schema {
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "$ref")]
     pub ref_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "multipleOf")]
+    pub multiple_of: Option<Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    // A boolean in 3.0, and a number in 3.1.
+    #[serde(rename = "exclusiveMaximum")]
+    pub exclusive_maximum: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "exclusiveMinimum")]
+    pub exclusive_minimum: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "maxLength")]
+    pub max_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "minLength")]
+    pub min_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "maxItems")]
+    pub max_items: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "minItems")]
+    pub min_items: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "uniqueItems")]
+    pub unique_items: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "maxProperties")]
+    pub max_properties: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "minProperties")]
+    pub min_properties: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "enum")]
+    pub enum_: Option<Vec<Value>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "type")]
-    pub type_: Option<SchemaType>,
+    pub type_: Option<Type>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "allOf")]
+    pub all_of: Option<Vec<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "oneOf")]
+    pub one_of: Option<Vec<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "anyOf")]
+    pub any_of: Option<Vec<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not: Option<Box<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub items: Option<Box<Schema>>,
-    pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub properties: Option<BTreeMap<String, Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "additionalProperties")]
+    pub additional_properties: Option<AdditionalProperties>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discriminator: Option<Discriminator>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "readOnly")]
+    pub read_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "writeOnly")]
+    pub write_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub xml: Option<Xml>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "externalDocs")]
+    pub external_docs: Option<ExternalDocs>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<bool>,
+
+    // Fields added in 3.1 by JSON Schema 2020-12.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$id")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$schema")]
+    pub schema: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$anchor")]
+    pub anchor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$defs")]
+    pub defs: Option<BTreeMap<String, Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "const")]
+    pub const_: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "prefixItems")]
+    pub prefix_items: Option<Vec<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "unevaluatedProperties")]
+    pub unevaluated_properties: Option<AdditionalProperties>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub examples: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "contentMediaType")]
+    pub content_media_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "contentEncoding")]
+    pub content_encoding: Option<String>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+impl Schema {
+    /// Returns the types of this schema except `null`.
+    pub fn types(&self) -> Vec<&SchemaType> {
+        let types = match self.type_.as_ref() {
+            None => vec![],
+            Some(Type::Single(v)) => vec![v],
+            Some(Type::Multiple(v)) => v.iter().collect(),
+        };
+
+        types
+            .into_iter()
+            .filter(|v| **v != SchemaType::Null)
+            .collect()
+    }
+
+    /// Returns true if `null` is allowed, by `nullable: true` in 3.0 or a
+    /// `null` type in 3.1.
+    pub fn is_nullable(&self) -> bool {
+        let null = match self.type_.as_ref() {
+            Some(Type::Single(v)) => *v == SchemaType::Null,
+            Some(Type::Multiple(v)) => v.contains(&SchemaType::Null),
+            None => false,
+        };
+
+        null || self.nullable == Some(true)
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum AdditionalProperties {
+    Bool(bool),
+    Schema(Box<Schema>),
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Discriminator {
+    #[serde(rename = "propertyName")]
+    pub property_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mapping: Option<BTreeMap<String, String>>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Xml {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribute: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrapped: Option<bool>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct ExternalDocs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub url: String,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Tag {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "externalDocs")]
+    pub external_docs: Option<ExternalDocs>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
 pub struct Response {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$ref")]
+    pub ref_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<BTreeMap<String, Header>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<BTreeMap<String, MediaType>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<BTreeMap<String, Link>>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
 pub struct Parameter {
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "$ref")]
     pub ref_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "in")]
     pub in_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub required: Option<bool>,
-    pub schema: Option<Schema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "allowEmptyValue")]
+    pub allow_empty_value: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub style: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explode: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "allowReserved")]
+    pub allow_reserved: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Schema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub examples: Option<BTreeMap<String, Example>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<BTreeMap<String, MediaType>>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
 pub struct RequestBody {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$ref")]
+    pub ref_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub content: BTreeMap<String, MediaType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
 pub struct Header {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$ref")]
+    pub ref_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
-    pub schema: Schema,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "allowEmptyValue")]
+    pub allow_empty_value: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explode: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "allowReserved")]
+    pub allow_reserved: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Schema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub examples: Option<BTreeMap<String, Example>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<BTreeMap<String, MediaType>>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
 pub struct Operation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "externalDocs")]
+    pub external_docs: Option<ExternalDocs>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "operationId")]
-    pub operation_id: String,
+    pub operation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<Vec<Parameter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "requestBody")]
     pub request_body: Option<RequestBody>,
-    pub responses: Option<BTreeMap<String, Response>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub responses: Option<Responses>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callbacks: Option<BTreeMap<String, Callback>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security: Option<Vec<SecurityRequirement>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub servers: Option<Vec<Server>>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+impl Operation {
+    /// Returns `operationId`, or the one generated by [`operation_id`] if
+    /// it is absent.
+    pub fn id(&self, path: &str, method: Method) -> String {
+        match &self.operation_id {
+            Some(v) => v.clone(),
+            None => operation_id(path, method),
+        }
+    }
+}
+
+/// Generate an operation id from method and path for operations without
+/// one, like `get_pets_petId` for `GET /pets/{petId}`.
+pub fn operation_id(path: &str, method: Method) -> String {
+    let mut id = method.as_str().to_string();
+    for part in path.split(|c: char| !c.is_ascii_alphanumeric()) {
+        if !part.is_empty() {
+            id.push('_');
+            id.push_str(part);
+        }
+    }
+
+    id
+}
+
+/// Callback maps runtime expressions to the path items describing the
+/// requests that may be initiated by the API provider.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Callback {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$ref")]
+    pub ref_: Option<String>,
+
+    #[serde(flatten)]
+    pub paths: Paths,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
 pub struct MediaType {
-    pub schema: Schema,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Schema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub examples: Option<BTreeMap<String, Example>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<BTreeMap<String, Encoding>>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+// ref: https://swagger.io/specification/#encoding-object
+//   - only applies to requestBody objects whose media type is multipart or
+//     application/x-www-form-urlencoded.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Encoding {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "contentType")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<BTreeMap<String, Header>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explode: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "allowReserved")]
+    pub allow_reserved: Option<bool>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Example {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$ref")]
+    pub ref_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "externalValue")]
+    pub external_value: Option<String>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct Link {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$ref")]
+    pub ref_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "operationRef")]
+    pub operation_ref: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "operationId")]
+    pub operation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<BTreeMap<String, Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "requestBody")]
+    pub request_body: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<Server>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct SecurityScheme {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$ref")]
+    pub ref_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "type")]
+    pub type_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "in")]
+    pub in_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "bearerFormat")]
+    pub bearer_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flows: Option<OAuthFlows>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "openIdConnectUrl")]
+    pub open_id_connect_url: Option<String>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct OAuthFlows {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub implicit: Option<OAuthFlow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<OAuthFlow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "clientCredentials")]
+    pub client_credentials: Option<OAuthFlow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "authorizationCode")]
+    pub authorization_code: Option<OAuthFlow>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct OAuthFlow {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "authorizationUrl")]
+    pub authorization_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "tokenUrl")]
+    pub token_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "refreshUrl")]
+    pub refresh_url: Option<String>,
+    pub scopes: BTreeMap<String, String>,
+
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
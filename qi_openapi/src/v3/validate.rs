@@ -0,0 +1,255 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::schema::*;
+use crate::jsonschema::{escape, Validator};
+use crate::Version;
+
+// ref: https://spec.openapis.org/oas/3.0/schema/2019-04-02
+const META_SCHEMA: &str = include_str!("openapi-3.0.json");
+
+/// Issue is a problem found in a spec.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    /// Name of the rule which finds this issue, like `unique-operation-id`.
+    pub rule: &'static str,
+    /// JSON pointer to the value which has this issue.
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}: {} [{}]", self.pointer, self.message, self.rule)
+    }
+}
+
+/// Validate a spec against the OpenAPI 3.0 meta-schema and semantic rules
+/// which could not be expressed by the meta-schema:
+///
+/// - `schema`: structure of the document conforms to the meta-schema
+/// - `version`: version of the document is supported
+/// - `unique-operation-id`: operation ids are unique
+/// - `path-params`: path template params are declared as required path
+///   parameters, and all path parameters are used in the template
+/// - `ref`: local references point to existing values
+/// - `status-code`: response keys are valid status codes or ranges
+///
+/// 3.1 documents are only checked by semantic rules.
+pub fn validate(spec: &Spec) -> Vec<Issue> {
+    validate_value(&serde_json::to_value(spec).unwrap())
+}
+
+/// Validate a document as written like `validate`, before it is parsed
+/// into `Spec`, so that values which could not be parsed, or would be
+/// dropped by parsing, are reported with their locations. Documents which
+/// conform to the meta-schema but still could not be parsed are reported
+/// by the `parse` rule, and semantic rules are skipped for them.
+pub fn validate_value(doc: &Value) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    let version = doc.get("openapi").and_then(Value::as_str);
+    match version.and_then(Version::parse) {
+        Some(Version::V3_0) => {
+            let schema: Value = serde_json::from_str(META_SCHEMA).unwrap();
+            for e in Validator::new(&schema).validate(doc) {
+                issues.push(issue("schema", e.pointer, e.message));
+            }
+        }
+        Some(_) => {}
+        None => issues.push(issue(
+            "version",
+            "/openapi".to_string(),
+            format!("unsupported version {}", version.unwrap_or_default()),
+        )),
+    }
+
+    match serde_json::from_value::<Spec>(doc.clone()) {
+        Ok(spec) => {
+            check_operation_ids(&spec, &mut issues);
+            check_path_params(&spec, &mut issues);
+            check_status_codes(&spec, &mut issues);
+        }
+        // Errors of parsing are found by the meta-schema with locations
+        // already.
+        Err(_) if !issues.is_empty() => {}
+        Err(e) => issues.push(issue("parse", String::new(), e.to_string())),
+    }
+    check_references(doc, doc, "", &mut issues);
+
+    issues
+}
+
+fn issue(rule: &'static str, pointer: String, message: String) -> Issue {
+    Issue {
+        rule,
+        pointer,
+        message,
+    }
+}
+
+fn pointer(segments: &[&str]) -> String {
+    segments.iter().map(|v| format!("/{}", escape(v))).collect()
+}
+
+fn operations(spec: &Spec) -> impl Iterator<Item = (&String, &PathItem, Method, &Operation)> {
+    spec.paths.items.iter().flat_map(|(path, item)| {
+        item.operations
+            .iter()
+            .map(move |(method, op)| (path, item, *method, op))
+    })
+}
+
+fn check_operation_ids(spec: &Spec, issues: &mut Vec<Issue>) {
+    let mut ids: BTreeMap<&str, String> = BTreeMap::new();
+
+    for (path, _, method, op) in operations(spec) {
+        let id = match &op.operation_id {
+            Some(v) => v,
+            None => continue,
+        };
+        let ptr = pointer(&["paths", path, method.as_str(), "operationId"]);
+        match ids.get(id.as_str()) {
+            Some(v) => issues.push(issue(
+                "unique-operation-id",
+                ptr,
+                format!("operationId {} is already used by #{}", id, v),
+            )),
+            None => {
+                ids.insert(id, ptr);
+            }
+        }
+    }
+}
+
+fn check_path_params(spec: &Spec, issues: &mut Vec<Issue>) {
+    for (path, item, method, op) in operations(spec) {
+        let ptr = pointer(&["paths", path, method.as_str()]);
+        let templates = path_templates(path);
+
+        // Operation's parameters override path item's with the same name.
+        let mut declared: BTreeMap<&str, &Parameter> = BTreeMap::new();
+        for param in item
+            .parameters
+            .iter()
+            .flatten()
+            .chain(op.parameters.iter().flatten())
+        {
+            let param = match resolve_parameter(spec, param) {
+                None => continue,
+                Some(v) => v,
+            };
+            if param.in_.as_deref() == Some("path") {
+                if let Some(name) = param.name.as_deref() {
+                    declared.insert(name, param);
+                }
+            }
+        }
+
+        for name in templates.iter() {
+            match declared.get(name) {
+                None => issues.push(issue(
+                    "path-params",
+                    ptr.clone(),
+                    format!("path parameter {} is not declared", name),
+                )),
+                Some(v) if v.required != Some(true) => issues.push(issue(
+                    "path-params",
+                    ptr.clone(),
+                    format!("path parameter {} must be required", name),
+                )),
+                _ => {}
+            }
+        }
+        for name in declared.keys() {
+            if !templates.contains(name) {
+                issues.push(issue(
+                    "path-params",
+                    ptr.clone(),
+                    format!("path parameter {} is not in path {}", name, path),
+                ));
+            }
+        }
+    }
+}
+
+/// Returns the names of templates in path like `petId` in `/pets/{petId}`.
+fn path_templates(path: &str) -> Vec<&str> {
+    path.split('{')
+        .skip(1)
+        .filter_map(|v| v.split('}').next())
+        .collect()
+}
+
+pub(super) fn resolve_parameter<'a>(spec: &'a Spec, param: &'a Parameter) -> Option<&'a Parameter> {
+    let mut param = param;
+    // Limit the depth so that circular references will not hang.
+    for _ in 0..16 {
+        let r = match param.reference() {
+            None => return Some(param),
+            Some(v) => v,
+        };
+        param = spec
+            .components
+            .as_ref()
+            .and_then(|c| c.parameters.as_ref())
+            .and_then(|m| m.get(r.trim_start_matches("#/components/parameters/")))?;
+    }
+
+    None
+}
+
+/// Check all local references like `#/components/schemas/Pet`, external
+/// references are not checked.
+fn check_references(doc: &Value, value: &Value, ptr: &str, issues: &mut Vec<Issue>) {
+    match value {
+        Value::Object(m) => {
+            if let Some(Value::String(r)) = m.get("$ref") {
+                if r.starts_with('#') && doc.pointer(&r[1..]).is_none() {
+                    issues.push(issue(
+                        "ref",
+                        format!("{}/$ref", ptr),
+                        format!("reference {} is not found", r),
+                    ));
+                }
+            }
+            for (k, v) in m {
+                check_references(doc, v, &format!("{}/{}", ptr, escape(k)), issues);
+            }
+        }
+        Value::Array(v) => {
+            for (idx, v) in v.iter().enumerate() {
+                check_references(doc, v, &format!("{}/{}", ptr, idx), issues);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_status_codes(spec: &Spec, issues: &mut Vec<Issue>) {
+    for (path, _, method, op) in operations(spec) {
+        for code in op.responses.iter().flat_map(|v| v.items.keys()) {
+            if !is_status_code(code) {
+                issues.push(issue(
+                    "status-code",
+                    pointer(&["paths", path, method.as_str(), "responses", code]),
+                    format!("{} is not a valid status code", code),
+                ));
+            }
+        }
+    }
+}
+
+/// Valid keys are `default`, status codes in 100..=599, and ranges like
+/// `2XX`.
+fn is_status_code(v: &str) -> bool {
+    if v == "default" {
+        return true;
+    }
+
+    let b = v.as_bytes();
+    b.len() == 3
+        && (b'1'..=b'5').contains(&b[0])
+        && ((b[1].is_ascii_digit() && b[2].is_ascii_digit()) || &b[1..] == b"XX")
+}
@@ -0,0 +1,41 @@
+use serde_json::Value;
+use std::error::Error;
+
+/// Version of a spec document.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Version {
+    /// Swagger 2.0, which could be converted into `v3::Spec`.
+    V2,
+    /// OpenAPI 3.0.x
+    V3_0,
+    /// OpenAPI 3.1.x, whose schemas are JSON Schema 2020-12.
+    V3_1,
+}
+
+impl Version {
+    /// Detect the version of a document by its `swagger` or `openapi` field.
+    pub fn detect(doc: &Value) -> Result<Version, Box<dyn Error>> {
+        if let Some(v) = doc.get("swagger") {
+            return match v.as_str() {
+                Some("2.0") => Ok(Version::V2),
+                _ => Err(format!("unsupported swagger version: {}", v).into()),
+            };
+        }
+
+        match doc.get("openapi").and_then(Value::as_str) {
+            Some(v) => Version::parse(v)
+                .ok_or_else(|| format!("unsupported openapi version: {}", v).into()),
+            None => Err("field openapi or swagger is missing".into()),
+        }
+    }
+
+    /// Parse the `openapi` field like `3.0.3` or `3.1.0`.
+    pub fn parse(v: &str) -> Option<Version> {
+        let mut parts = v.split('.');
+        match (parts.next(), parts.next()) {
+            (Some("3"), Some("0")) => Some(Version::V3_0),
+            (Some("3"), Some("1")) => Some(Version::V3_1),
+            _ => None,
+        }
+    }
+}
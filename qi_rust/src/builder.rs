@@ -0,0 +1,430 @@
+use serde::Deserialize;
+use serde_json::Value;
+use similar::TextDiff;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use qi_ir::{Filter, Model, ModelKind, Service};
+
+use crate::bundle::bundle;
+use crate::load::{into_spec, parse_value};
+use crate::rust::{ActixWebGenerator, Generator};
+
+/// Server framework of the generated code.
+#[derive(Debug, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Server {
+    /// No server is generated.
+    None,
+    Actix,
+}
+
+/// Error returned by `Builder::generate`, with the path of the spec which
+/// causes it.
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to read a spec or write the generated code.
+    Io { path: PathBuf, source: io::Error },
+    /// The spec is not a valid OpenAPI or Swagger document.
+    Parse { path: PathBuf, message: String },
+    /// Code could not be generated from the spec, like invalid names set by
+    /// `x-qi-name`.
+    Generate { path: PathBuf, message: String },
+    /// The config file is invalid, like unknown keys.
+    Config { path: PathBuf, message: String },
+    /// Neither `out_dir` nor `OUT_DIR` is set.
+    OutDir,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Parse { path, message }
+            | Error::Generate { path, message }
+            | Error::Config { path, message } => write!(f, "{}: {}", path.display(), message),
+            Error::OutDir => write!(f, "out_dir is not set and OUT_DIR is not present"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Builder generates code for specs, which is designed to be used in
+/// `build.rs`. The code of every spec is written to `<out_dir>/<stem>.rs`,
+/// like `api.rs` for `api.yaml`, so stems of specs must be unique.
+///
+/// Files referenced by relative `$ref`s are bundled into specs like `load`.
+/// `cargo:rerun-if-changed` is printed for every spec and the files they
+/// reference, so that the code is regenerated on changes.
+#[derive(Debug, Clone)]
+pub struct Builder {
+    specs: Vec<PathBuf>,
+    server: Server,
+    client: bool,
+    out_dir: Option<PathBuf>,
+    rerun_if_changed: bool,
+    derives: Vec<String>,
+    types: BTreeMap<String, String>,
+    names: BTreeMap<String, String>,
+    filter: Filter,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Builder {
+            specs: Vec::new(),
+            server: Server::Actix,
+            client: true,
+            out_dir: None,
+            rerun_if_changed: true,
+            derives: Vec::new(),
+            types: BTreeMap::new(),
+            names: BTreeMap::new(),
+            filter: Filter::default(),
+        }
+    }
+}
+
+impl Builder {
+    pub fn new() -> Builder {
+        Builder::default()
+    }
+
+    /// Add a spec to generate, JSON and YAML are both supported.
+    pub fn spec(mut self, path: impl AsRef<Path>) -> Self {
+        self.specs.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the server framework, `Server::Actix` by default.
+    pub fn server(mut self, server: Server) -> Self {
+        self.server = server;
+        self
+    }
+
+    /// Whether to generate the client, `true` by default.
+    pub fn client(mut self, enabled: bool) -> Self {
+        self.client = enabled;
+        self
+    }
+
+    /// Set the directory to write code to, `OUT_DIR` by default.
+    pub fn out_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Whether to print `cargo:rerun-if-changed`, `true` by default.
+    pub fn rerun_if_changed(mut self, enabled: bool) -> Self {
+        self.rerun_if_changed = enabled;
+        self
+    }
+
+    /// Add a trait derived by all models, like `PartialEq`.
+    pub fn derive(mut self, path: impl Into<String>) -> Self {
+        self.derives.push(path.into());
+        self
+    }
+
+    /// Use an existing type like `uuid::Uuid` for a component schema instead
+    /// of generating one, which is the same as `x-qi-type`.
+    pub fn type_mapping(mut self, schema: impl Into<String>, path: impl Into<String>) -> Self {
+        self.types.insert(schema.into(), path.into());
+        self
+    }
+
+    /// Rename an operation by its id or a component schema in generated
+    /// code, which is the same as `x-qi-name`.
+    pub fn rename(mut self, name: impl Into<String>, to: impl Into<String>) -> Self {
+        self.names.insert(name.into(), to.into());
+        self
+    }
+
+    /// Generate only operations matched by the filter, and models used by
+    /// them.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Generate code for all specs, and returns the paths of output files.
+    ///
+    /// Files are not rewritten if their content is not changed, so that
+    /// tools watching them will not be triggered.
+    pub fn generate(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut outputs = Vec::new();
+        for (output, code) in self.render(&mut io::stdout())? {
+            if fs::read_to_string(&output).is_ok_and(|v| v == code) {
+                outputs.push(output);
+                continue;
+            }
+            fs::write(&output, code).map_err(|e| Error::Io {
+                path: output.clone(),
+                source: e,
+            })?;
+            outputs.push(output);
+        }
+
+        Ok(outputs)
+    }
+
+    /// Generate code for all specs in memory and compare it with the files
+    /// in the output directory, nothing is written.
+    ///
+    /// Returns unified diffs of files which are out of date, missing files
+    /// are compared as empty ones.
+    pub fn check(&self) -> Result<Vec<String>, Error> {
+        let mut diffs = Vec::new();
+        for (output, code) in self.render(&mut io::stdout())? {
+            let current = match fs::read_to_string(&output) {
+                Ok(v) => v,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+                Err(e) => {
+                    return Err(Error::Io {
+                        path: output,
+                        source: e,
+                    })
+                }
+            };
+            if current == code {
+                continue;
+            }
+
+            let name = output.display().to_string();
+            diffs.push(
+                TextDiff::from_lines(&current, &code)
+                    .unified_diff()
+                    .header(&format!("a/{}", name), &format!("b/{}", name))
+                    .to_string(),
+            );
+        }
+
+        Ok(diffs)
+    }
+
+    /// Returns the output paths and generated code of all specs, and writes
+    /// `cargo:rerun-if-changed` lines to `rerun`.
+    pub(crate) fn render(&self, rerun: &mut impl Write) -> Result<Vec<(PathBuf, String)>, Error> {
+        let out_dir = match &self.out_dir {
+            Some(v) => v.clone(),
+            None => env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or(Error::OutDir)?,
+        };
+
+        // Specs with the same stem would overwrite the code of each other,
+        // they are rejected before anything is generated.
+        let mut paths: BTreeMap<PathBuf, &PathBuf> = BTreeMap::new();
+        let mut targets = Vec::new();
+        for spec in self.specs.iter() {
+            let stem = spec.file_stem().unwrap_or_default();
+            let output = out_dir.join(stem).with_extension("rs");
+            if let Some(v) = paths.get(&output) {
+                return Err(Error::Generate {
+                    path: spec.clone(),
+                    message: format!(
+                        "output {} is also generated from {}",
+                        output.display(),
+                        v.display()
+                    ),
+                });
+            }
+            paths.insert(output.clone(), spec);
+            targets.push((output, spec));
+        }
+
+        let mut outputs = Vec::new();
+        for (output, spec) in targets {
+            outputs.push((output, self.generate_spec(spec, rerun)?));
+        }
+
+        Ok(outputs)
+    }
+
+    fn generate_spec(&self, path: &Path, rerun: &mut impl Write) -> Result<String, Error> {
+        let mut sources = BTreeSet::new();
+        let value = read_value(path)?;
+        collect_sources(path, &value, &mut sources)?;
+        if self.rerun_if_changed {
+            for v in sources.iter() {
+                writeln!(rerun, "cargo:rerun-if-changed={}", v.display()).map_err(|e| {
+                    Error::Io {
+                        path: path.to_path_buf(),
+                        source: e,
+                    }
+                })?;
+            }
+        }
+
+        let spec = bundle(path, value)
+            .and_then(into_spec)
+            .map_err(|e| Error::Parse {
+                path: path.to_path_buf(),
+                message: e.to_string(),
+            })?;
+
+        let mut srv = Service::new(spec).map_err(|e| Error::Parse {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        self.customize(&mut srv)
+            .map_err(|message| Error::Generate {
+                path: path.to_path_buf(),
+                message,
+            })?;
+        // Filter after customizing, so that settings of schemas unused by
+        // the kept operations are still checked against the spec.
+        srv.filter(&self.filter);
+
+        let g = ActixWebGenerator::new(Generator::new(srv))
+            .server(self.server != Server::None)
+            .client(self.client);
+
+        g.generate().map_err(|e| Error::Generate {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Apply type mappings, renames and derives to the service, unknown
+    /// schemas and operations are rejected since they are likely typos.
+    fn customize(&self, srv: &mut Service) -> Result<(), String> {
+        for (name, path) in self.types.iter() {
+            let m = srv
+                .models
+                .get_mut(name)
+                .ok_or_else(|| format!("type mapping of unknown schema {}", name))?;
+            m.annotation.get_or_insert_with(Default::default).type_path = Some(path.clone());
+        }
+
+        for (name, to) in self.names.iter() {
+            if let Some(op) = srv.operations.iter_mut().find(|v| v.id == *name) {
+                op.annotation.get_or_insert_with(Default::default).display = Some(to.clone());
+                continue;
+            }
+
+            let m = srv
+                .models
+                .remove(name)
+                .ok_or_else(|| format!("rename of unknown operation or schema {}", name))?;
+            srv.models.insert(to.clone(), m);
+            for m in srv.all_models_mut() {
+                rename_reference(m, name, to);
+            }
+        }
+
+        for m in srv.models.values_mut() {
+            if m.kind != ModelKind::Struct {
+                continue;
+            }
+            let annotation = m.annotation.get_or_insert_with(Default::default);
+            for v in self.derives.iter() {
+                if !annotation.derives.contains(v) {
+                    annotation.derives.push(v.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn rename_reference(m: &mut Model, name: &str, to: &str) {
+    if m.kind == ModelKind::Reference && m.name.as_deref() == Some(name) {
+        m.name = Some(to.to_string());
+    }
+
+    for v in m
+        .properties
+        .iter_mut()
+        .flat_map(|v| v.values_mut())
+        .chain(m.element.as_deref_mut())
+    {
+        rename_reference(v, name, to);
+    }
+}
+
+/// Returns the spec and all files referenced by its external `$ref`s, the
+/// code generated from it should be refreshed if any of them is changed.
+pub fn sources(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut sources = BTreeSet::new();
+    collect_sources(path, &read_value(path)?, &mut sources)?;
+
+    Ok(sources.into_iter().collect())
+}
+
+fn read_value(path: &Path) -> Result<Value, Error> {
+    let content = fs::read_to_string(path).map_err(|e| Error::Io {
+        path: path.to_path_buf(),
+        source: e,
+    })?;
+
+    parse_value(path, &content, None).map_err(|e| Error::Parse {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })
+}
+
+/// Collect the spec and all files referenced by it recursively, like
+/// `common.yaml` in `$ref: common.yaml#/components/schemas/Error`.
+pub(crate) fn collect_sources(
+    path: &Path,
+    value: &Value,
+    sources: &mut BTreeSet<PathBuf>,
+) -> Result<(), Error> {
+    if !sources.insert(path.to_path_buf()) {
+        return Ok(());
+    }
+
+    let mut refs = Vec::new();
+    collect_refs(value, &mut refs);
+
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    for r in refs {
+        let file = r.split('#').next().unwrap_or_default();
+        // Remote references are not tracked.
+        if file.is_empty() || file.contains("://") {
+            continue;
+        }
+
+        let file = dir.join(file);
+        if sources.contains(&file) {
+            continue;
+        }
+        let value = read_value(&file)?;
+        collect_sources(&file, &value, sources)?;
+    }
+
+    Ok(())
+}
+
+fn collect_refs<'a>(value: &'a Value, refs: &mut Vec<&'a str>) {
+    match value {
+        Value::Object(m) => {
+            if let Some(Value::String(r)) = m.get("$ref") {
+                refs.push(r);
+            }
+            for v in m.values() {
+                collect_refs(v, refs);
+            }
+        }
+        Value::Array(v) => {
+            for v in v {
+                collect_refs(v, refs);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,275 @@
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::load::parse_value;
+
+/// Bundle files referenced by relative `$ref`s into the spec, like
+/// `schemas/pet.yaml#/Pet`, so that it could be loaded as one document.
+///
+/// Referenced schemas are moved into `components.schemas`, or `definitions`
+/// of Swagger 2.0, since they may be recursive and models are named after
+/// them. They are named after the last segment of the pointer, or the stem
+/// of the file if there is no pointer, with a number appended if the name
+/// is taken. Other objects like responses are inlined where they are
+/// referenced. Remote references like `https://...` are kept as is.
+pub(crate) fn bundle(path: &Path, mut value: Value) -> Result<Value, Box<dyn Error>> {
+    let swagger = value.get("swagger").is_some();
+    let (prefix, pointer) = if swagger {
+        ("#/definitions/", "/definitions")
+    } else {
+        ("#/components/schemas/", "/components/schemas")
+    };
+
+    let mut b = Bundler {
+        root: canonical(path),
+        prefix,
+        docs: BTreeMap::new(),
+        names: BTreeMap::new(),
+        taken: value
+            .pointer(pointer)
+            .and_then(Value::as_object)
+            .map(|v| v.keys().cloned().collect())
+            .unwrap_or_default(),
+        schemas: Map::new(),
+        inlining: Vec::new(),
+    };
+    b.walk(&mut value, path, Context::Other)?;
+    if b.schemas.is_empty() {
+        return Ok(value);
+    }
+
+    let schemas = if swagger {
+        value
+            .as_object_mut()
+            .and_then(|v| object_entry(v, "definitions"))
+    } else {
+        value
+            .as_object_mut()
+            .and_then(|v| object_entry(v, "components"))
+            .and_then(|v| object_entry(v, "schemas"))
+    };
+    schemas
+        .ok_or_else(|| format!("{} is not an object", pointer))?
+        .extend(b.schemas);
+
+    Ok(value)
+}
+
+fn object_entry<'a>(
+    m: &'a mut Map<String, Value>,
+    key: &str,
+) -> Option<&'a mut Map<String, Value>> {
+    m.entry(key)
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+}
+
+/// What a value is, which decides whether a reference in it is a schema.
+#[derive(Debug, Clone, Copy)]
+enum Context {
+    /// Objects other than schemas, like paths and responses.
+    Other,
+    Schema,
+    /// Maps of schemas, like `properties`.
+    SchemaMap,
+    /// Lists of schemas, like `allOf`.
+    SchemaList,
+}
+
+impl Context {
+    /// Context of the value of `key`, `None` if it could not contain
+    /// references, like `enum` of schemas.
+    fn child(self, key: &str) -> Option<Context> {
+        match self {
+            Context::Other => match key {
+                "schema" => Some(Context::Schema),
+                "schemas" | "definitions" => Some(Context::SchemaMap),
+                "example" => None,
+                _ => Some(Context::Other),
+            },
+            Context::Schema => match key {
+                "properties" | "patternProperties" | "definitions" | "$defs"
+                | "dependentSchemas" => Some(Context::SchemaMap),
+                "items"
+                | "additionalItems"
+                | "additionalProperties"
+                | "not"
+                | "if"
+                | "then"
+                | "else"
+                | "contains"
+                | "propertyNames"
+                | "unevaluatedItems"
+                | "unevaluatedProperties"
+                | "contentSchema" => Some(Context::Schema),
+                "allOf" | "anyOf" | "oneOf" | "prefixItems" => Some(Context::SchemaList),
+                _ => None,
+            },
+            Context::SchemaMap => Some(Context::Schema),
+            Context::SchemaList => None,
+        }
+    }
+
+    /// Context of items of an array, `items` may be a list of schemas in
+    /// older drafts.
+    fn item(self) -> Context {
+        match self {
+            Context::Schema | Context::SchemaList => Context::Schema,
+            v => v,
+        }
+    }
+}
+
+/// How a reference is bundled.
+enum Resolved {
+    /// References to the spec itself, or remote ones.
+    Keep,
+    /// Pointing to a schema moved into the spec.
+    Rewrite(String),
+    Inline(Value),
+}
+
+struct Bundler {
+    root: PathBuf,
+    prefix: &'static str,
+    /// Referenced files, keyed by their canonical paths.
+    docs: BTreeMap<PathBuf, Value>,
+    /// Names of moved schemas, keyed by their files and pointers.
+    names: BTreeMap<(PathBuf, String), String>,
+    taken: BTreeSet<String>,
+    schemas: Map<String, Value>,
+    /// Objects being inlined, to reject circular references.
+    inlining: Vec<(PathBuf, String)>,
+}
+
+impl Bundler {
+    /// Bundle references in `value` of `file`.
+    fn walk(&mut self, value: &mut Value, file: &Path, ctx: Context) -> Result<(), Box<dyn Error>> {
+        match value {
+            Value::Object(m) => {
+                if let Some(Value::String(r)) = m.get("$ref") {
+                    let r = r.clone();
+                    match self.resolve(&r, file, ctx)? {
+                        Resolved::Keep => {}
+                        Resolved::Rewrite(r) => {
+                            m.insert("$ref".to_string(), Value::String(r));
+                        }
+                        Resolved::Inline(v) => *value = v,
+                    }
+                    return Ok(());
+                }
+
+                for (k, v) in m.iter_mut() {
+                    if let Some(ctx) = ctx.child(k) {
+                        self.walk(v, file, ctx)?;
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for v in items.iter_mut() {
+                    self.walk(v, file, ctx.item())?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn resolve(&mut self, r: &str, file: &Path, ctx: Context) -> Result<Resolved, Box<dyn Error>> {
+        let (target, pointer) = r.split_once('#').unwrap_or((r, ""));
+        if target.contains("://") || (target.is_empty() && canonical(file) == self.root) {
+            return Ok(Resolved::Keep);
+        }
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            return Err(format!(
+                "{}: fragment of reference {} is not a JSON pointer",
+                file.display(),
+                r
+            )
+            .into());
+        }
+
+        let target = if target.is_empty() {
+            file.to_path_buf()
+        } else {
+            file.parent().unwrap_or_else(|| Path::new("")).join(target)
+        };
+        // References back to the spec become local ones.
+        if canonical(&target) == self.root {
+            return Ok(Resolved::Rewrite(format!("#{}", pointer)));
+        }
+
+        if let Context::Schema = ctx {
+            let name = self.move_schema(&target, pointer)?;
+            return Ok(Resolved::Rewrite(format!("{}{}", self.prefix, name)));
+        }
+
+        let key = (canonical(&target), pointer.to_string());
+        if self.inlining.contains(&key) {
+            return Err(format!("{}: circular reference {}", file.display(), r).into());
+        }
+        let mut value = self.get(&target, pointer)?.clone();
+        self.inlining.push(key);
+        self.walk(&mut value, &target, ctx)?;
+        self.inlining.pop();
+
+        Ok(Resolved::Inline(value))
+    }
+
+    /// Move the schema into the spec, and returns its name.
+    fn move_schema(&mut self, file: &Path, pointer: &str) -> Result<String, Box<dyn Error>> {
+        let key = (canonical(file), pointer.to_string());
+        if let Some(v) = self.names.get(&key) {
+            return Ok(v.clone());
+        }
+
+        let base = match pointer.rsplit('/').next().filter(|v| !v.is_empty()) {
+            Some(v) => v.replace("~1", "/").replace("~0", "~"),
+            None => file
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        };
+        let mut name = base.clone();
+        for i in 2.. {
+            if !self.taken.contains(&name) {
+                break;
+            }
+            name = format!("{}{}", base, i);
+        }
+        // Named before walking, so that recursive schemas refer to it.
+        self.taken.insert(name.clone());
+        self.names.insert(key, name.clone());
+
+        let mut value = self.get(file, pointer)?.clone();
+        self.walk(&mut value, file, Context::Schema)?;
+        self.schemas.insert(name.clone(), value);
+
+        Ok(name)
+    }
+
+    /// Returns the value pointed by `pointer` in `file`.
+    fn get(&mut self, file: &Path, pointer: &str) -> Result<&Value, Box<dyn Error>> {
+        let key = canonical(file);
+        if !self.docs.contains_key(&key) {
+            let content =
+                fs::read_to_string(file).map_err(|e| format!("{}: {}", file.display(), e))?;
+            let value = parse_value(file, &content, None)
+                .map_err(|e| format!("{}: {}", file.display(), e))?;
+            self.docs.insert(key.clone(), value);
+        }
+
+        self.docs[&key]
+            .pointer(pointer)
+            .ok_or_else(|| format!("{}: {} is not found", file.display(), pointer).into())
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,148 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use qi_ir::Filter;
+
+use crate::builder::{Builder, Error, Server};
+
+/// Name of the config file, which is discovered from the current directory
+/// upward.
+pub const CONFIG_FILE: &str = "qi.toml";
+
+/// Config holds generation settings of targets, which is loaded from
+/// `qi.toml` like:
+///
+/// ```toml
+/// [target.petstore]
+/// spec = "specs/petstore.yaml"
+/// out = "src/generated"
+/// server = "none"
+/// derives = ["PartialEq"]
+///
+/// [target.petstore.types]
+/// Timestamp = "chrono::DateTime<chrono::Utc>"
+///
+/// [target.petstore.names]
+/// listPets = "list"
+///
+/// [target.petstore.filter]
+/// include = [{ tags = ["pets"] }]
+/// ```
+///
+/// Paths are relative to the directory of the config file, and unknown keys
+/// are rejected.
+#[derive(Debug, Deserialize, PartialEq, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default, rename = "target")]
+    pub targets: BTreeMap<String, Target>,
+}
+
+/// Target is a spec with the settings to generate code for it.
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Target {
+    pub spec: PathBuf,
+    /// Directory to write code to, the code is named after the spec.
+    pub out: PathBuf,
+    #[serde(default = "default_server")]
+    pub server: Server,
+    #[serde(default = "default_client")]
+    pub client: bool,
+    /// Extra traits derived by all models.
+    #[serde(default)]
+    pub derives: Vec<String>,
+    /// Existing types used instead of generating component schemas, keyed
+    /// by schema names.
+    #[serde(default)]
+    pub types: BTreeMap<String, String>,
+    /// Names used in generated code, keyed by operation ids or schema names.
+    #[serde(default)]
+    pub names: BTreeMap<String, String>,
+    /// Operations to generate, all by default.
+    #[serde(default)]
+    pub filter: Filter,
+}
+
+fn default_server() -> Server {
+    Server::Actix
+}
+
+fn default_client() -> bool {
+    true
+}
+
+impl Config {
+    /// Find the config file in `dir` and its ancestors.
+    pub fn discover(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|v| v.join(CONFIG_FILE))
+            .find(|v| v.is_file())
+    }
+
+    /// Load config from file, paths of targets are resolved against the
+    /// directory of the file.
+    pub fn load(path: &Path) -> Result<Config, Error> {
+        let content = fs::read_to_string(path).map_err(|e| Error::Io {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+        let mut cfg = Config::parse(&content).map_err(|message| Error::Config {
+            path: path.to_path_buf(),
+            message,
+        })?;
+
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for target in cfg.targets.values_mut() {
+            target.spec = dir.join(&target.spec);
+            target.out = dir.join(&target.out);
+        }
+
+        Ok(cfg)
+    }
+
+    /// Parse config from TOML, errors tell the location and the expected
+    /// keys of unknown ones.
+    pub fn parse(content: &str) -> Result<Config, String> {
+        toml::from_str(content).map_err(|e| e.to_string().trim_end().to_string())
+    }
+}
+
+impl Target {
+    /// Returns the target of a spec with default settings.
+    pub fn new(spec: impl AsRef<Path>, out: impl AsRef<Path>) -> Target {
+        Target {
+            spec: spec.as_ref().to_path_buf(),
+            out: out.as_ref().to_path_buf(),
+            server: default_server(),
+            client: default_client(),
+            derives: Vec::new(),
+            types: BTreeMap::new(),
+            names: BTreeMap::new(),
+            filter: Filter::default(),
+        }
+    }
+
+    /// Returns the builder which generates code of this target.
+    pub fn builder(&self) -> Builder {
+        let mut builder = Builder::new()
+            .spec(&self.spec)
+            .out_dir(&self.out)
+            .server(self.server)
+            .client(self.client)
+            .filter(self.filter.clone());
+        for v in self.derives.iter() {
+            builder = builder.derive(v);
+        }
+        for (schema, path) in self.types.iter() {
+            builder = builder.type_mapping(schema, path);
+        }
+        for (name, to) in self.names.iter() {
+            builder = builder.rename(name, to);
+        }
+
+        builder
+    }
+}
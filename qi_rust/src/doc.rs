@@ -0,0 +1,522 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Build `#[doc]` attributes from a description, which are rendered as
+/// `///` comments.
+pub fn description(text: &str) -> TokenStream {
+    attrs(&rustdoc(text))
+}
+
+/// Build `#[doc]` attributes from rustdoc as is, nothing is returned for
+/// empty docs.
+pub fn attrs(doc: &str) -> TokenStream {
+    let lines = doc.lines().map(|v| {
+        if v.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", v)
+        }
+    });
+
+    quote!(#(#[doc = #lines])*)
+}
+
+/// Convert a description into rustdoc. Descriptions are CommonMark by the
+/// spec, but some like those of AWS are HTML fragments, which are
+/// converted into markdown first.
+pub fn rustdoc(text: &str) -> String {
+    let text = if is_html(text) {
+        html_to_markdown(text)
+    } else {
+        text.to_string()
+    };
+
+    fix_markdown(&text).trim().to_string()
+}
+
+/// Tags which mark descriptions as HTML, autolinks like
+/// `<https://example.com>` and generics like `Vec<T>` are not tags.
+const HTML_TAGS: &[&str] = &[
+    "p", "a", "b", "i", "br", "code", "em", "strong", "ul", "ol", "li", "pre", "note", "div",
+];
+
+/// Whether the description is an HTML fragment, like those of AWS.
+fn is_html(text: &str) -> bool {
+    text.match_indices('<').any(|(i, _)| {
+        let rest = text[i + 1..].trim_start_matches('/');
+        let len = rest
+            .find(|v: char| !v.is_ascii_alphanumeric())
+            .unwrap_or(rest.len());
+        let name = rest[..len].to_ascii_lowercase();
+
+        HTML_TAGS.contains(&name.as_str())
+            && rest[len..].starts_with(|v: char| v == '>' || v == '/' || v.is_whitespace())
+    })
+}
+
+/// Convert HTML into markdown, unknown tags are dropped with their text
+/// kept.
+fn html_to_markdown(html: &str) -> String {
+    let mut out = String::new();
+    // Lists being written, with the number of the next item in ordered
+    // ones.
+    let mut lists: Vec<Option<usize>> = Vec::new();
+    let mut link: Option<String> = None;
+    let mut pre = false;
+
+    let mut rest = html;
+    while !rest.is_empty() {
+        let tag = rest
+            .strip_prefix('<')
+            .and_then(|v| v.find('>').map(|end| &v[..end]));
+        let tag = match tag {
+            Some(v) if v.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') => v,
+            _ => {
+                // A `<` which is not a tag is a part of the text.
+                let skip = if rest.starts_with('<') { 1 } else { 0 };
+                let end = rest[skip..].find('<').map_or(rest.len(), |v| v + skip);
+                let text = decode_entities(&rest[..end]);
+                if pre {
+                    out.push_str(&text);
+                } else if in_code(&out) {
+                    push_text(&mut out, &text);
+                } else {
+                    push_text(&mut out, &escape_markdown(&text));
+                }
+                rest = &rest[end..];
+                continue;
+            }
+        };
+        rest = &rest[tag.len() + 2..];
+
+        let closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/').trim_end_matches('/');
+        let (name, attrs) = tag
+            .split_once(|c: char| c.is_whitespace())
+            .unwrap_or((tag, ""));
+        let name = name.to_ascii_lowercase();
+
+        if pre {
+            if closing && name == "pre" {
+                end_line(&mut out);
+                out.push_str("```");
+                pre = false;
+                start_block(&mut out);
+            }
+            continue;
+        }
+
+        match (name.as_str(), closing) {
+            // Paragraphs in list items are kept in the items.
+            ("p" | "div" | "note" | "important" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6", _)
+                if lists.is_empty() =>
+            {
+                start_block(&mut out)
+            }
+            ("br", _) => {
+                if lists.is_empty() {
+                    start_block(&mut out);
+                } else {
+                    end_line(&mut out);
+                    out.push_str(&"  ".repeat(lists.len()));
+                }
+            }
+            ("ul", false) => {
+                start_list(&mut out, lists.is_empty());
+                lists.push(None);
+            }
+            ("ol", false) => {
+                start_list(&mut out, lists.is_empty());
+                lists.push(Some(1));
+            }
+            ("ul" | "ol", true) => {
+                lists.pop();
+                if lists.is_empty() {
+                    start_block(&mut out);
+                }
+            }
+            ("li", false) => {
+                end_line(&mut out);
+                out.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(n)) => {
+                        out.push_str(&format!("{}. ", n));
+                        *n += 1;
+                    }
+                    _ => out.push_str("- "),
+                }
+            }
+            ("pre", false) => {
+                start_block(&mut out);
+                out.push_str("```text\n");
+                pre = true;
+            }
+            ("code" | "tt", _) => out.push('`'),
+            ("b" | "strong", _) => out.push_str("**"),
+            ("i" | "em", _) => out.push('*'),
+            ("a", false) => {
+                link = attr(attrs, "href");
+                if link.is_some() {
+                    out.push('[');
+                }
+            }
+            ("a", true) => {
+                if let Some(href) = link.take() {
+                    out.push_str(&format!("]({})", href));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out
+}
+
+fn start_block(out: &mut String) {
+    trim_end_spaces(out);
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+    }
+}
+
+fn start_list(out: &mut String, top: bool) {
+    if top {
+        start_block(out);
+    } else {
+        end_line(out);
+    }
+}
+
+fn end_line(out: &mut String) {
+    trim_end_spaces(out);
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+fn trim_end_spaces(out: &mut String) {
+    let len = out.trim_end_matches(' ').len();
+    out.truncate(len);
+}
+
+/// Append text with whitespace collapsed, spaces at the start of lines are
+/// dropped.
+fn push_text(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_whitespace() {
+            let start = out.is_empty()
+                || out.ends_with('\n')
+                || out.ends_with(' ')
+                || out.trim_end_matches(' ').ends_with("- ");
+            if !start {
+                out.push(' ');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+/// Whether the text ends inside an inline code span.
+fn in_code(out: &str) -> bool {
+    out.matches('`').count() % 2 == 1
+}
+
+/// Returns the value of an attribute like `href="https://example.com"`.
+fn attr(attrs: &str, name: &str) -> Option<String> {
+    let start = attrs.find(&format!("{}=", name))? + name.len() + 1;
+    let value = &attrs[start..];
+    let value = match value.chars().next()? {
+        q @ ('"' | '\'') => value[1..].split(q).next()?,
+        _ => value.split_whitespace().next()?,
+    };
+
+    Some(decode_entities(value))
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => ' ',
+                v => {
+                    let code = match v.strip_prefix("#x").or_else(|| v.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => v.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out
+}
+
+/// Escape characters of text which have special meanings in markdown,
+/// URLs are kept as is to be turned into autolinks.
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::new();
+    for word in text.split_inclusive(char::is_whitespace) {
+        if word.starts_with("http://") || word.starts_with("https://") {
+            out.push_str(word);
+            continue;
+        }
+        for c in word.chars() {
+            if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+/// Fix markdown which is valid CommonMark but not good rustdoc:
+///
+/// - Code blocks are doctests by default, which are marked as `text` or
+///   `ignore`, and indented code blocks are fenced.
+/// - Bare URLs are turned into autolinks.
+/// - Brackets which are not links are escaped, or they are taken as
+///   broken intra-doc links.
+fn fix_markdown(text: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut fence: Option<String> = None;
+    let mut indented = false;
+    // Whether the lines are in a list, where indented lines are
+    // continuations of items instead of code.
+    let mut in_list = false;
+
+    for line in text.lines() {
+        let line = line.trim_end();
+        let trimmed = line.trim_start();
+
+        if let Some(marker) = fence.as_ref() {
+            let c = marker.as_bytes()[0] as char;
+            if trimmed.len() >= marker.len() && trimmed.chars().all(|v| v == c) {
+                fence = None;
+            }
+            out.push(line.to_string());
+            continue;
+        }
+
+        if indented {
+            if line.is_empty() || line.starts_with("    ") {
+                out.push(line.strip_prefix("    ").unwrap_or(line).to_string());
+                continue;
+            }
+            // Blank lines before the end belong to the text after it.
+            let blanks = out.iter().rev().take_while(|v| v.is_empty()).count();
+            out.truncate(out.len() - blanks);
+            out.push("```".to_string());
+            out.extend(std::iter::repeat_n(String::new(), blanks.max(1)));
+            indented = false;
+        }
+
+        let marker: String = trimmed
+            .chars()
+            .take_while(|v| *v == '`' || *v == '~')
+            .collect();
+        if marker.len() >= 3 && marker.chars().all(|v| v == marker.as_bytes()[0] as char) {
+            let indent = &line[..line.len() - trimmed.len()];
+            out.push(format!(
+                "{}{}{}",
+                indent,
+                marker,
+                fence_info(trimmed[marker.len()..].trim())
+            ));
+            fence = Some(marker);
+            continue;
+        }
+
+        let blank_before = out.last().is_none_or(|v| v.is_empty());
+        if line.starts_with("    ") && blank_before && !in_list {
+            out.push("```text".to_string());
+            out.push(line[4..].to_string());
+            indented = true;
+            continue;
+        }
+
+        if is_list_item(trimmed) {
+            in_list = true;
+        } else if !trimmed.is_empty() && !line.starts_with(' ') {
+            in_list = false;
+        }
+        out.push(fix_inline(line));
+    }
+
+    if indented {
+        while out.last().is_some_and(|v| v.is_empty()) {
+            out.pop();
+        }
+        out.push("```".to_string());
+    }
+    if let Some(marker) = fence {
+        out.push(marker);
+    }
+
+    out.join("\n")
+}
+
+/// Returns the info string of a fenced code block, which is never tested
+/// as a doctest.
+fn fence_info(info: &str) -> String {
+    let lang = info.split(|c: char| c == ',' || c.is_whitespace()).next();
+    match lang {
+        None | Some("") => "text".to_string(),
+        Some("rust") if !info.contains("ignore") => format!("{},ignore", info),
+        _ => info.to_string(),
+    }
+}
+
+fn is_list_item(line: &str) -> bool {
+    let digits = line.chars().take_while(|v| v.is_ascii_digit()).count();
+    let rest = &line[digits..];
+
+    if digits > 0 {
+        rest.starts_with(". ") || rest.starts_with(") ")
+    } else {
+        ["- ", "* ", "+ "].iter().any(|v| rest.starts_with(v))
+    }
+}
+
+/// Fix URLs and brackets in a line, code spans and link reference
+/// definitions like `[ref]: url` are kept as is.
+fn fix_inline(line: &str) -> String {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('[')
+        && trimmed
+            .find("]:")
+            .is_some_and(|v| !trimmed[..v].contains(']'))
+    {
+        return line.to_string();
+    }
+
+    let mut out = String::new();
+    for (i, part) in line.split('`').enumerate() {
+        if i > 0 {
+            out.push('`');
+        }
+        if i % 2 == 1 {
+            out.push_str(part);
+        } else {
+            out.push_str(&escape_angles(&escape_brackets(&autolink(part))));
+        }
+    }
+
+    out
+}
+
+/// Wrap bare URLs in `<>`, those in links or autolinks are not bare.
+fn autolink(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = ["http://", "https://"]
+        .iter()
+        .filter_map(|v| rest.find(v))
+        .min()
+    {
+        let bare = rest[..start]
+            .chars()
+            .next_back()
+            .is_none_or(|v| v.is_whitespace());
+        let len = rest[start..]
+            .find(|v: char| v.is_whitespace() || v == '<' || v == '>')
+            .unwrap_or(rest.len() - start);
+        let url = rest[start..start + len].trim_end_matches(['.', ',', ';', ':', ')', '!', '?']);
+
+        out.push_str(&rest[..start]);
+        if bare {
+            out.push_str(&format!("<{}>", url));
+        } else {
+            out.push_str(url);
+        }
+        rest = &rest[start + url.len()..];
+    }
+    out.push_str(rest);
+
+    out
+}
+
+/// Escape `<` which does not start an autolink, or it is taken as an
+/// HTML tag like `<T>` in `Vec<T>`.
+fn escape_angles(text: &str) -> String {
+    let mut out = String::new();
+    let mut prev = None;
+    for (i, c) in text.char_indices() {
+        let autolink = |rest: &str| rest.starts_with("http://") || rest.starts_with("https://");
+        if c == '<' && prev != Some('\\') && !autolink(&text[i + 1..]) {
+            out.push('\\');
+        }
+        out.push(c);
+        prev = Some(c);
+    }
+
+    out
+}
+
+/// Escape brackets which are not links like `[text](url)` or
+/// `[text][ref]`.
+fn escape_brackets(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut escaped = vec![false; chars.len()];
+
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' => match closing_bracket(&chars, i) {
+                Some(end) if chars.get(end + 1) == Some(&'(') => i = end,
+                Some(end) if chars.get(end + 1) == Some(&'[') => {
+                    i = closing_bracket(&chars, end + 1).unwrap_or(end);
+                }
+                Some(end) => {
+                    escaped[i] = true;
+                    escaped[end] = true;
+                    i = end;
+                }
+                None => escaped[i] = true,
+            },
+            ']' => escaped[i] = true,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let mut out = String::new();
+    for (c, escaped) in chars.into_iter().zip(escaped) {
+        if escaped {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+fn closing_bracket(chars: &[char], start: usize) -> Option<usize> {
+    (start + 1..chars.len()).find(|v| chars[*v] == ']' && chars[v - 1] != '\\')
+}
@@ -0,0 +1,55 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::ToTokens;
+
+/// Format tokens into a file in rustfmt style.
+///
+/// Tokens are parsed as a file first, so that generated code with syntax
+/// errors is rejected at generation time instead of by its consumers.
+pub fn format(tokens: TokenStream) -> Result<String, syn::Error> {
+    let file: syn::File = syn::parse2(tokens)?;
+
+    Ok(prettyplease::unparse(&file))
+}
+
+/// Build an identifier from a name which is lexically valid, raw
+/// identifiers like `r#type` are supported.
+pub fn ident(name: &str) -> Ident {
+    match name.strip_prefix("r#") {
+        Some(v) => Ident::new_raw(v, Span::call_site()),
+        None => Ident::new(name, Span::call_site()),
+    }
+}
+
+/// Build a type from its path like `std::path::PathBuf`, which must have
+/// been checked by `check_type`.
+pub fn ty(path: &str) -> TokenStream {
+    syn::parse_str::<syn::Type>(path)
+        .expect("type must be checked")
+        .into_token_stream()
+}
+
+/// Build a path like `serde::Serialize`, which must have been checked by
+/// `check_path`.
+pub fn path(path: &str) -> TokenStream {
+    syn::parse_str::<syn::Path>(path)
+        .expect("path must be checked")
+        .into_token_stream()
+}
+
+pub fn check_ident(name: &str) -> Result<(), String> {
+    syn::parse_str::<Ident>(name)
+        .map(|_| ())
+        .map_err(|e| format!("invalid identifier {}: {}", name, e))
+}
+
+pub fn check_type(path: &str) -> Result<(), String> {
+    syn::parse_str::<syn::Type>(path)
+        .map(|_| ())
+        .map_err(|e| format!("invalid type {}: {}", path, e))
+}
+
+pub fn check_path(path: &str) -> Result<(), String> {
+    syn::parse_str::<syn::Path>(path)
+        .map(|_| ())
+        .map_err(|e| format!("invalid path {}: {}", path, e))
+}
@@ -0,0 +1,69 @@
+//! qi_rust generates actix-web servers and reqwest clients from OpenAPI
+//! specs, either by the `qi` command or from `build.rs`:
+//!
+//! ```no_run
+//! use qi_rust::{Builder, Server};
+//!
+//! Builder::new()
+//!     .spec("api.yaml")
+//!     .server(Server::Actix)
+//!     .client(true)
+//!     .generate()
+//!     .unwrap();
+//! ```
+//!
+//! And include the generated code which is named after the spec:
+//!
+//! ```ignore
+//! include!(concat!(env!("OUT_DIR"), "/api.rs"));
+//! ```
+//!
+//! # Vendor extensions
+//!
+//! Generated code could be customized by these extensions in the spec,
+//! invalid values are reported with their location:
+//!
+//! | Extension     | Applies to                                   | Value                       | Effect                                              |
+//! |---------------|----------------------------------------------|-----------------------------|-----------------------------------------------------|
+//! | `x-qi-name`   | schemas, properties, parameters, operations  | string                      | Name of the generated type, field or function.      |
+//! | `x-qi-type`   | schemas                                      | string                      | Path of an existing type used instead of generating one. |
+//! | `x-qi-skip`   | operations                                   | boolean                     | Omits the operation if `true`.                      |
+//! | `x-qi-derive` | schemas                                      | string or array of strings  | Extra traits derived by the generated type, a string is split by `,`. |
+//!
+//! ```yaml
+//! components:
+//!   schemas:
+//!     Pet:
+//!       type: object
+//!       x-qi-name: Animal
+//!       x-qi-derive: [PartialEq, Eq]
+//!       properties:
+//!         type:
+//!           type: string
+//!           x-qi-name: kind
+//!     Addr:
+//!       type: string
+//!       x-qi-type: std::path::PathBuf
+//! ```
+//!
+//! Names and types are used in generated code as is, so they must be valid
+//! Rust identifiers and paths. [`Builder::rename`] and
+//! [`Builder::type_mapping`] set them without touching the spec.
+
+mod builder;
+mod bundle;
+mod config;
+mod doc;
+mod emit;
+mod load;
+mod rust;
+mod watch;
+
+pub use builder::{sources, Builder, Error, Server};
+pub use config::{Config, Target, CONFIG_FILE};
+pub use load::{load, load_with_spans, validate_file};
+pub use rust::{ActixWebGenerator, Generator};
+pub use watch::{debounce, WatchSet};
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,145 @@
+use serde_json::Value;
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use qi_ir::Service;
+use qi_openapi::v3::{self, Issue, Spec};
+use qi_openapi::{v2, SourceMap, Version};
+
+use crate::bundle::bundle;
+
+/// Load a spec from file, Swagger 2.0 specs will be converted into
+/// OpenAPI 3.0. Files referenced by relative `$ref`s are bundled into the
+/// spec, which are resolved against the directory of the spec.
+pub fn load(input: &Path, format: Option<&str>) -> Result<Spec, Box<dyn Error>> {
+    let content = read(input)?;
+
+    into_spec(bundle(input, parse_value(input, &content, format)?)?)
+}
+
+/// Load a spec like `load` and track spans of its source, spans are not
+/// tracked for Swagger 2.0 specs since pointers of the converted spec do
+/// not match the source.
+pub fn load_with_spans(
+    input: &Path,
+    format: Option<&str>,
+) -> Result<(Spec, Option<SourceMap>), Box<dyn Error>> {
+    let content = read(input)?;
+
+    let value = bundle(input, parse_value(input, &content, format)?)?;
+    let spans = match Version::detect(&value)? {
+        Version::V2 => None,
+        _ => Some(SourceMap::parse(&input.display().to_string(), &content)?),
+    };
+
+    Ok((into_spec(value)?, spans))
+}
+
+/// Validate a spec file with spans of its source like `load_with_spans`,
+/// 3.x specs are validated as written so that values which could not be
+/// loaded are reported with their locations. Specs without issues are also
+/// built into the IR, whose errors are reported by the `ir` rule, so that
+/// valid specs could be generated.
+pub fn validate_file(
+    input: &Path,
+    format: Option<&str>,
+) -> Result<(Vec<Issue>, Option<SourceMap>), Box<dyn Error>> {
+    let content = read(input)?;
+
+    let value = bundle(input, parse_value(input, &content, format)?)?;
+    let (mut issues, spans) = match Version::detect(&value)? {
+        Version::V2 => (Vec::new(), None),
+        _ => (
+            v3::validate_value(&value),
+            Some(SourceMap::parse(&input.display().to_string(), &content)?),
+        ),
+    };
+    if !issues.is_empty() {
+        return Ok((issues, spans));
+    }
+
+    let spec = into_spec(value)?;
+    if spans.is_none() {
+        issues = v3::validate(&spec);
+        if !issues.is_empty() {
+            return Ok((issues, spans));
+        }
+    }
+    if let Err(e) = Service::new(spec) {
+        issues.push(Issue {
+            rule: "ir",
+            pointer: e.pointer,
+            message: e.message,
+        });
+    }
+
+    Ok((issues, spans))
+}
+
+/// Read the content of input, `-` for stdin.
+pub(crate) fn read(input: &Path) -> Result<String, Box<dyn Error>> {
+    if input == Path::new("-") {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        return Ok(content);
+    }
+
+    Ok(fs::read_to_string(input)?)
+}
+
+/// Parse content in the given format, or the one implied by extension, or
+/// the one sniffed from content if the extension is missing or unknown.
+pub(crate) fn parse_value(
+    input: &Path,
+    content: &str,
+    format: Option<&str>,
+) -> Result<Value, Box<dyn Error>> {
+    let format = format
+        .or_else(|| {
+            match input
+                .extension()
+                .and_then(OsStr::to_str)
+                .unwrap_or_default()
+            {
+                "json" => Some("json"),
+                "yaml" | "yml" => Some("yaml"),
+                _ => None,
+            }
+        })
+        .unwrap_or_else(|| sniff_format(content));
+
+    match format {
+        "json" => Ok(serde_json::from_str(content)?),
+        "yaml" => Ok(serde_yaml::from_str(content)?),
+        format => Err(format!("not supported format: {}", format).into()),
+    }
+}
+
+/// JSON documents are objects which start with `{`, and everything else is
+/// treated as YAML.
+pub(crate) fn sniff_format(content: &str) -> &'static str {
+    if content
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .starts_with('{')
+    {
+        "json"
+    } else {
+        "yaml"
+    }
+}
+
+pub(crate) fn into_spec(value: Value) -> Result<Spec, Box<dyn Error>> {
+    match Version::detect(&value)? {
+        Version::V2 => {
+            let spec: v2::Spec = serde_json::from_value(value)?;
+            Ok(spec.into())
+        }
+        // 3.1 is loaded into the same model, and normalized while parsing
+        // schemas.
+        Version::V3_0 | Version::V3_1 => Ok(serde_json::from_value(value)?),
+    }
+}
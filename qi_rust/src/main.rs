@@ -1,486 +1,638 @@
-mod rust;
-
-use std::collections::{BTreeMap, HashMap};
-use std::ffi::OsStr;
+use std::collections::BTreeSet;
+use std::env;
+use std::error::Error;
+use std::fmt::Display;
 use std::fs;
-use std::fs::File;
-use std::ops::Deref;
-use std::path::Path;
-use std::str::FromStr;
-
-use clap::{crate_authors, crate_description, crate_version, App, Arg};
-use serde::{Deserialize, Serialize};
-
-use qi_openapi::v3;
-pub use qi_openapi::v3::Method;
-use qi_openapi::v3::{from_json_reader, from_yaml_reader, Schema, SchemaType, Spec};
-
-use crate::rust::ActixWebGenerator;
-use rust::Generator;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use clap::{crate_authors, crate_description, crate_version, App, AppSettings, Arg, SubCommand};
+use notify::{RecursiveMode, Watcher};
+use serde_json::Value;
+
+use qi_ir::{diff, Ir, Service};
+use qi_openapi::{v3, SourceMap};
+use qi_rust::{
+    debounce, load, load_with_spans, validate_file, ActixWebGenerator, Builder, Config, Generator,
+    Server, Target, WatchSet, CONFIG_FILE,
+};
 
 fn main() {
-    let matches = App::new("qi Rust")
+    let input = Arg::with_name("input")
+        .required(true)
+        .index(1)
+        .help("Set input, `-` for stdin");
+    // Input format is detected from extension or content by default.
+    let input_format = Arg::with_name("input-format")
+        .long("input-format")
+        .takes_value(true)
+        .possible_values(&["json", "yaml"])
+        .help("Set input format");
+    let format = input_format.clone().long("format").alias("input-format");
+
+    let matches = App::new("qi")
         .version(crate_version!())
         .author(crate_authors!())
         .about(crate_description!())
-        .arg(
-            Arg::with_name("input")
-                .required(true)
-                .index(1)
-                .help("Set input"),
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("generate")
+                .about("Generate actix-web server and reqwest client")
+                .long_about(
+                    "Generate actix-web server and reqwest client\n\n\
+                     Targets in qi.toml, which is discovered from the current directory \
+                     upward, are generated if input is not set. Flags override settings \
+                     of targets.",
+                )
+                .arg(input.clone().required(false))
+                // Specs are always detected by extension or content when
+                // generating into a directory.
+                .arg(format.clone().conflicts_with("out"))
+                .arg(
+                    Arg::with_name("config")
+                        .long("config")
+                        .takes_value(true)
+                        .conflicts_with("input")
+                        .help("Set config file instead of discovering qi.toml"),
+                )
+                .arg(
+                    Arg::with_name("target")
+                        .long("target")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .conflicts_with("input")
+                        .help("Generate only the given targets in config"),
+                )
+                .arg(
+                    Arg::with_name("out")
+                        .long("out")
+                        .takes_value(true)
+                        .help("Write code into directory as <name>.rs instead of stdout"),
+                )
+                .arg(
+                    Arg::with_name("server")
+                        .long("server")
+                        .takes_value(true)
+                        .possible_values(&["actix", "none"])
+                        .help("Set server framework"),
+                )
+                .arg(
+                    Arg::with_name("client")
+                        .long("client")
+                        .takes_value(true)
+                        .possible_values(&["true", "false"])
+                        .help("Set whether to generate client"),
+                )
+                .arg(
+                    Arg::with_name("check")
+                        .long("check")
+                        .help("Check whether code in --out is up to date without writing"),
+                )
+                .arg(
+                    Arg::with_name("watch")
+                        .long("watch")
+                        .conflicts_with("check")
+                        .help(
+                            "Regenerate code in --out on changes of spec and files it references",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("validate")
+                .about("Validate spec against the OpenAPI meta-schema, semantic rules and IR")
+                .arg(input.clone())
+                .arg(format),
+        )
+        .subcommand(
+            SubCommand::with_name("ir")
+                .about("Dump the normalized intermediate representation")
+                .arg(input.clone())
+                .arg(input_format.clone())
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["json", "yaml"])
+                        .default_value("json")
+                        .help("Set output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Compare two specs and report breaking changes")
+                .arg(
+                    Arg::with_name("old")
+                        .required(true)
+                        .index(1)
+                        .help("Set old input"),
+                )
+                .arg(
+                    Arg::with_name("new")
+                        .required(true)
+                        .index(2)
+                        .help("Set new input"),
+                )
+                .arg(input_format.clone())
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["text", "json"])
+                        .default_value("text")
+                        .help("Set output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("lint")
+                .about("Lint spec with configurable rules")
+                .arg(input)
+                .arg(input_format)
+                .arg(
+                    Arg::with_name("config")
+                        .long("config")
+                        .takes_value(true)
+                        .help("Set lint config which overrides severities of rules"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["text", "json", "sarif"])
+                        .default_value("text")
+                        .help("Set output format"),
+                ),
         )
         .get_matches();
 
-    let input = Path::new(matches.value_of("input").unwrap());
-    let file = fs::File::open(input).unwrap();
+    match matches.subcommand() {
+        ("generate", Some(m)) => {
+            let overrides = Overrides {
+                out: m.value_of("out").map(PathBuf::from),
+                server: m.value_of("server").map(|v| match v {
+                    "none" => Server::None,
+                    _ => Server::Actix,
+                }),
+                client: m.value_of("client").map(|v| v == "true"),
+            };
+            let targets = match m.value_of("input") {
+                None => load_targets(
+                    m.value_of("config").map(Path::new),
+                    m.values_of("target").map(|v| v.collect()),
+                    &overrides,
+                ),
+                Some(input) if overrides.out.is_none() => {
+                    if m.is_present("check") || m.is_present("watch") {
+                        eprintln!("--check and --watch require --out");
+                        process::exit(1);
+                    }
+                    return generate(Path::new(input), m.value_of("input-format"), &overrides);
+                }
+                Some(input) => vec![input_target(Path::new(input), &overrides)],
+            };
 
-    let specs;
-    match input.extension().and_then(OsStr::to_str).unwrap() {
-        "json" => specs = from_json_reader(file).unwrap(),
-        "yaml" | "yml" => specs = from_yaml_reader(file).unwrap(),
-        extension @ _ => panic!("not supported file extension: {}", extension),
+            if m.is_present("watch") {
+                watch(&targets)
+            } else {
+                generate_into(&targets, m.is_present("check"))
+            }
+        }
+        ("validate", Some(m)) => validate(
+            Path::new(m.value_of("input").unwrap()),
+            m.value_of("input-format"),
+        ),
+        ("ir", Some(m)) => ir(
+            Path::new(m.value_of("input").unwrap()),
+            m.value_of("input-format"),
+            m.value_of("format").unwrap(),
+        ),
+        ("diff", Some(m)) => diff(
+            Path::new(m.value_of("old").unwrap()),
+            Path::new(m.value_of("new").unwrap()),
+            m.value_of("input-format"),
+            m.value_of("format").unwrap(),
+        ),
+        ("lint", Some(m)) => lint(
+            Path::new(m.value_of("input").unwrap()),
+            m.value_of("input-format"),
+            m.value_of("config").map(Path::new),
+            m.value_of("format").unwrap(),
+        ),
+        _ => unreachable!(),
     }
+}
 
-    let srv = Service::new(specs);
-
-    let g = Generator::new(srv.clone());
-    let actix_g = ActixWebGenerator::new(g);
+/// Settings set by flags, which override those of targets in config.
+struct Overrides {
+    out: Option<PathBuf>,
+    server: Option<Server>,
+    client: Option<bool>,
+}
 
-    for op in srv.operations {
-        println!("{}\n", actix_g.generate_output(op))
+impl Overrides {
+    fn apply(&self, target: &mut Target) {
+        if let Some(v) = self.out.as_ref() {
+            target.out = v.clone();
+        }
+        if let Some(v) = self.server {
+            target.server = v;
+        }
+        if let Some(v) = self.client {
+            target.client = v;
+        }
     }
-    // println!("{}", actix_g.generate_dispatch());
-
-    ()
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
-pub struct Service {
-    models: BTreeMap<String, Model>,
-    operations: Vec<Operation>,
+fn generate(input: &Path, input_format: Option<&str>, overrides: &Overrides) {
+    let spec = exit_on_error(input, load(input, input_format));
 
-    parameters: BTreeMap<String, Parameter>,
-    spec: Spec,
-}
+    let srv = exit_on_error(input, Service::new(spec).map_err(Box::from));
 
-impl Service {
-    pub fn new(spec: Spec) -> Service {
-        let mut srv = Service {
-            models: Default::default(),
-            operations: vec![],
-            parameters: Default::default(),
-            spec,
-        };
+    let g = Generator::new(srv);
+    let actix_g = ActixWebGenerator::new(g)
+        .server(overrides.server != Some(Server::None))
+        .client(overrides.client != Some(false));
 
-        srv.format_parameters();
-        srv.format_models();
-        srv.format_operations();
+    println!("{}", exit_on_error(input, actix_g.generate()));
+}
 
-        srv
+/// Returns the target of the input spec, which has no settings other than
+/// flags.
+fn input_target(input: &Path, overrides: &Overrides) -> Target {
+    if input == Path::new("-") {
+        eprintln!("--out requires a spec file instead of stdin");
+        process::exit(1);
     }
 
-    fn format_parameters(&mut self) {
-        let mut gp = BTreeMap::new();
-
-        let components = self.spec.components.as_ref().unwrap();
-
-        if let Some(params) = &components.parameters {
-            for (name, param) in params {
-                // Parameters in components could not be references.
-                gp.insert(
-                    name.to_string(),
-                    Parameter {
-                        name: param.name.clone().unwrap(),
-                        model: parse_schema_type(param.schema.as_ref().unwrap()),
-                        mandatory: param.required.unwrap_or(false),
-                    },
+    let mut target = Target::new(input, "");
+    overrides.apply(&mut target);
+    target
+}
+
+/// Load targets from the config file, which is discovered from the current
+/// directory upward if not set. All targets are selected if `names` is not
+/// set.
+fn load_targets(
+    config: Option<&Path>,
+    names: Option<Vec<&str>>,
+    overrides: &Overrides,
+) -> Vec<Target> {
+    let path = match config {
+        Some(v) => v.to_path_buf(),
+        None => {
+            let cwd = env::current_dir().unwrap();
+            Config::discover(&cwd).unwrap_or_else(|| {
+                eprintln!("input is not set and no {} found", CONFIG_FILE);
+                process::exit(1);
+            })
+        }
+    };
+    let mut cfg = Config::load(&path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+
+    if let Some(names) = names {
+        for name in names.iter() {
+            if !cfg.targets.contains_key(*name) {
+                let known: Vec<&str> = cfg.targets.keys().map(String::as_str).collect();
+                eprintln!(
+                    "{}: unknown target {}, expected one of {}",
+                    path.display(),
+                    name,
+                    known.join(", ")
                 );
+                process::exit(1);
             }
         }
+        cfg.targets.retain(|k, _| names.contains(&k.as_str()));
+    }
 
-        self.parameters = gp;
+    let mut targets: Vec<Target> = cfg.targets.into_values().collect();
+    for target in targets.iter_mut() {
+        overrides.apply(target);
     }
+    targets
+}
 
-    fn parse_parameter(&self, param: &v3::Parameter) -> Parameter {
-        if let Some(r) = param.ref_.as_ref() {
-            self.parameters.get(parse_ref(r).as_str()).unwrap().clone()
-        } else {
-            let m = parse_schema_type(param.schema.as_ref().unwrap());
+fn generate_into(targets: &[Target], check: bool) {
+    // Errors of builder are reported with their paths already.
+    let fail = |e: qi_rust::Error| -> ! {
+        eprintln!("{}", e);
+        process::exit(1);
+    };
 
-            Parameter {
-                name: param.name.clone().unwrap(),
-                model: self.deref_model(&m),
-                mandatory: param.required.unwrap_or(false),
-            }
+    let mut diffs = Vec::new();
+    for target in targets.iter() {
+        let builder = target.builder().rerun_if_changed(false);
+        if check {
+            diffs.extend(builder.check().unwrap_or_else(|e| fail(e)));
+            continue;
         }
+
+        let r = fs::create_dir_all(&target.out).map_err(Into::into);
+        exit_on_error(&target.out, r);
+        builder.generate().unwrap_or_else(|e| fail(e));
     }
 
-    fn format_models(&mut self) {
-        let mut shapes: BTreeMap<String, Model> = BTreeMap::new();
+    for diff in diffs.iter() {
+        print!("{}", diff);
+    }
+    if !diffs.is_empty() {
+        eprintln!("{} files are out of date", diffs.len());
+        process::exit(1);
+    }
+}
 
-        let components = self.spec.components.as_ref().unwrap();
+/// Watch specs of targets and files referenced by them, and regenerate code
+/// once they are changed. Errors are reported without exiting.
+fn watch(targets: &[Target]) {
+    // Changes in this period are handled together.
+    const DEBOUNCE: Duration = Duration::from_millis(200);
 
-        if let Some(schemas) = &components.schemas {
-            for (name, schema) in schemas {
-                // println!("parsing schema {}", name);
-                let model = parse_schema_type(schema);
-                if model.kind == ModelKind::Any {
-                    continue;
-                }
-                shapes.insert(name.to_string(), model);
+    for target in targets.iter() {
+        let r = fs::create_dir_all(&target.out).map_err(Into::into);
+        exit_on_error(&target.out, r);
+    }
+
+    let builders: Vec<(&Path, Builder)> = targets
+        .iter()
+        .map(|v| (v.spec.as_path(), v.builder().rerun_if_changed(false)))
+        .collect();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    let mut set = WatchSet::new();
+    let mut dirs: BTreeSet<PathBuf> = BTreeSet::new();
+    // All targets are generated at first, and then only those whose specs
+    // are changed.
+    let mut changed: Option<BTreeSet<PathBuf>> = None;
+
+    loop {
+        for (input, builder) in builders.iter() {
+            if changed.as_ref().is_some_and(|v| !v.contains(*input)) {
+                continue;
             }
+            regenerate(input, builder);
+            set.update(input);
         }
+        let next = set.dirs();
+        for dir in dirs.difference(&next) {
+            let _ = watcher.unwatch(dir);
+        }
+        for dir in next.difference(&dirs) {
+            if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                eprintln!("{}: {}", dir.display(), e);
+            }
+        }
+        dirs = next;
 
-        self.models = shapes;
+        let specs = |event: &notify::Result<notify::Event>| match event {
+            Ok(v) => set.changed_specs(v),
+            Err(_) => BTreeSet::new(),
+        };
+        let events = match debounce(&rx, DEBOUNCE, |v| !specs(v).is_empty()) {
+            Some(v) => v,
+            None => return,
+        };
+        changed = Some(
+            events
+                .iter()
+                .flat_map(specs)
+                .map(Path::to_path_buf)
+                .collect(),
+        );
     }
+}
 
-    fn deref_model(&self, m: &Model) -> Model {
-        match m.kind {
-            ModelKind::Reference => self
-                .models
-                .get(m.name.as_ref().unwrap().as_str())
-                .unwrap()
-                .clone(),
-            _ => m.clone(),
+/// Validate the spec and regenerate code, diagnostics are printed instead
+/// of exiting.
+fn regenerate(input: &Path, builder: &Builder) {
+    let (issues, spans) = match validate_file(input, None) {
+        Ok(v) => v,
+        Err(e) => return eprintln!("{}: {}", input.display(), e),
+    };
+    if !issues.is_empty() {
+        for issue in issues.iter() {
+            report(input, spans.as_ref(), &issue.pointer, issue);
         }
+        return eprintln!("{} issues found", issues.len());
     }
 
-    fn format_operations(&mut self) {
-        let mut ops: Vec<Operation> = Vec::new();
-
-        for (path, item) in self.spec.paths.iter() {
-            for (method, o) in item.operations.iter() {
-                let mut op = Operation {
-                    id: o.operation_id.clone(),
-                    method: (*method).clone(),
-                    uri: path.clone(),
-                    expect: Vec::new(),
-                    description: None,
-                    tags: None,
-                    input: Input {
-                        description: None,
-                        path: vec![],
-                        query: vec![],
-                        header: vec![],
-                        body: None,
-                    },
-                    output: Output {
-                        description: None,
-                        status_code: 0,
-                        header: vec![],
-                        body: None,
-                    },
-                };
-
-                println!("format operation {}", &op.id);
-
-                if let Some(params) = o.parameters.as_ref() {
-                    for param in params.iter() {
-                        let p = self.parse_parameter(param);
-
-                        match param.in_.as_ref().unwrap().as_str() {
-                            "path" => op.input.path.push(p),
-                            "query" => op.input.query.push(p),
-                            "header" => op.input.header.push(p),
-                            location @ _ => panic!("invalid parameter location {}", location),
-                        }
-                    }
-                }
-
-                if let Some(body) = o.request_body.as_ref() {
-                    let (_, media_type) = body.content.iter().next().unwrap();
-                    let m = self.deref_model(&parse_schema_type(&media_type.schema));
-
-                    // TODO: we need to convert String body to Iterator<Byte>
-                    // if m.kind == ModelKind::String {
-                    //     op.input.body = Some(Model {
-                    //         kind: ModelKind::Iterator,
-                    //         annotation: None,
-                    //         name: None,
-                    //         properties: None,
-                    //         element: Some(Box::new(Model {
-                    //             kind: ModelKind::Byte,
-                    //             annotation: None,
-                    //             name: None,
-                    //             properties: None,
-                    //             element: None,
-                    //         })),
-                    //     })
-                    // } else {
-                    //     op.input.body = Some(m);
-                    // }
-
-                    op.input.body = Some(m);
-                }
-
-                o.responses.as_ref().and_then(|responses| {
-                    for (name, response) in responses.iter() {
-                        if name == "default" {
-                            println!("ignore operation {}'s default response", &op.id);
-                            continue;
-                        }
-
-                        let status_code = usize::from_str(name.as_str()).unwrap();
-                        if status_code < 100 || status_code > 300 {
-                            println!(
-                                "ignore operation {}'s {} error response",
-                                &op.id, &status_code
-                            );
-                            continue;
-                        }
-
-                        op.expect.push(status_code);
-
-                        let mut output = Output {
-                            description: None,
-                            status_code,
-                            header: Vec::new(),
-                            body: None,
-                        };
-
-                        if let Some(headers) = response.headers.as_ref() {
-                            for (name, header) in headers.iter() {
-                                let m = parse_schema_type(&header.schema);
-
-                                output.header.push(Parameter {
-                                    name: name.clone(),
-                                    model: self.deref_model(&m),
-                                    mandatory: false,
-                                });
-                            }
-                        }
-
-                        if let Some(content) = response.content.as_ref() {
-                            let (_, media_type) = content.iter().next().unwrap();
-                            // TODO: We should deref here.
-                            let m = parse_schema_type(&media_type.schema);
-                            output.body = Some(self.deref_model(&m))
-                        }
-
-                        op.output = output;
-                    }
-
-                    Some(responses)
-                });
-
-                ops.push(op);
+    match builder.generate() {
+        Ok(outputs) => {
+            for v in outputs {
+                eprintln!("generated {}", v.display());
             }
         }
-
-        self.operations = ops;
+        Err(e) => eprintln!("{}", e),
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
-struct Operation {
-    id: String,
+fn ir(input: &Path, input_format: Option<&str>, format: &str) {
+    let spec = exit_on_error(input, load(input, input_format));
+    let srv = exit_on_error(input, Service::new(spec).map_err(Box::from));
 
-    method: Method,
-    uri: String,
-    expect: Vec<usize>,
-
-    input: Input,
-    output: Output,
-
-    #[serde(skip_serializing_if = "Option::is_none")]
-    description: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    tags: Option<Vec<String>>,
+    println!("{}", dump_ir(&srv, format));
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
-struct Input {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    description: Option<String>,
-    path: Vec<Parameter>,
-    query: Vec<Parameter>,
-    header: Vec<Parameter>,
-    body: Option<Model>,
-}
+/// Dump the service as IR in JSON or YAML.
+fn dump_ir(srv: &Service, format: &str) -> String {
+    let ir = Ir::new(srv);
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
-struct Output {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    description: Option<String>,
-    status_code: usize,
-    header: Vec<Parameter>,
-    body: Option<Model>,
+    match format {
+        "yaml" => serde_yaml::to_string(&ir).unwrap(),
+        _ => serde_json::to_string_pretty(&ir).unwrap(),
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
-pub struct Parameter {
-    name: String,
-    model: Model,
-    mandatory: bool,
-}
+/// Print all issues of the spec, and exit with non-zero code if any.
+fn validate(input: &Path, input_format: Option<&str>) {
+    let (issues, spans) = exit_on_error(input, validate_file(input, input_format));
+
+    for issue in issues.iter() {
+        report(input, spans.as_ref(), &issue.pointer, issue);
+    }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Copy, Clone)]
-#[serde(rename_all = "lowercase")]
-enum ModelKind {
-    // Any used for dynamic type
-    Any,
-    Boolean,
-    // UTF-8 string
-    String,
-    Byte,
-
-    Date,
-    Time,
-    Datetime,
-
-    Int,
-    Int8,
-    Int16,
-    Int32,
-    Int64,
-    Uint,
-    Uint8,
-    Uint16,
-    Uint32,
-    Uint64,
-
-    Float32,
-    Float64,
-
-    Array,
-    Map,
-    Enum,
-    Struct,
-    Iterator,
-
-    Reference,
+    if !issues.is_empty() {
+        eprintln!("{} issues found", issues.len());
+        process::exit(1);
+    }
 }
 
-impl ModelKind {
-    fn is_simple(&self) -> bool {
-        match self {
-            ModelKind::Any
-            | ModelKind::Array
-            | ModelKind::Map
-            | ModelKind::Enum
-            | ModelKind::Struct
-            | ModelKind::Iterator
-            | ModelKind::Reference => false,
-            _ => true,
+/// Print changes between two specs, and exit with non-zero code if any
+/// breaking change found.
+fn diff(old: &Path, new: &Path, input_format: Option<&str>, format: &str) {
+    let old_spec = exit_on_error(old, load(old, input_format));
+    let old_srv = exit_on_error(old, Service::new(old_spec).map_err(Box::from));
+    let new_spec = exit_on_error(new, load(new, input_format));
+    let new_srv = exit_on_error(new, Service::new(new_spec).map_err(Box::from));
+
+    let changes = diff::diff(&old_srv, &new_srv);
+    let breaking = changes.iter().filter(|v| v.breaking).count();
+    match format {
+        "json" => {
+            let v = serde_json::json!({
+                "breaking": breaking > 0,
+                "changes": changes,
+            });
+            println!("{}", serde_json::to_string_pretty(&v).unwrap());
+        }
+        _ => {
+            for change in changes.iter() {
+                println!("{}", change);
+            }
+            eprintln!(
+                "{} breaking changes, {} non-breaking changes",
+                breaking,
+                changes.len() - breaking
+            );
         }
     }
-}
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
-pub struct Model {
-    #[serde(rename = "type")]
-    kind: ModelKind,
+    if breaking > 0 {
+        process::exit(1);
+    }
+}
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    annotation: Option<Annotation>,
+/// Print diagnostics of the spec in given format, and exit with non-zero
+/// code if any error found.
+fn lint(input: &Path, input_format: Option<&str>, config: Option<&Path>, format: &str) {
+    let (spec, spans) = exit_on_error(input, load_with_spans(input, input_format));
+    let config = match config {
+        None => v3::Config::default(),
+        Some(path) => exit_on_error(
+            path,
+            fs::File::open(path)
+                .map_err(|e| e.into())
+                .and_then(v3::Config::from_reader),
+        ),
+    };
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    properties: Option<BTreeMap<String, Model>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    element: Option<Box<Model>>,
-}
+    let diags = v3::lint(&spec, &config);
+    match format {
+        "json" => {
+            let diags: Vec<Value> = diags
+                .iter()
+                .map(|v| {
+                    let span = spans.as_ref().and_then(|m| m.locate(&v.issue.pointer));
+                    serde_json::json!({
+                        "rule": v.issue.rule,
+                        "severity": v.severity,
+                        "pointer": v.issue.pointer,
+                        "line": span.map(|v| v.line),
+                        "column": span.map(|v| v.column),
+                        "message": v.issue.message,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&diags).unwrap());
+        }
+        "sarif" => println!(
+            "{}",
+            serde_json::to_string_pretty(&sarif(input, spans.as_ref(), &diags)).unwrap()
+        ),
+        _ => {
+            for diag in diags.iter() {
+                report(input, spans.as_ref(), &diag.issue.pointer, diag);
+            }
+        }
+    }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
-struct Annotation {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    display: Option<String>,
+    if diags.iter().any(|v| v.severity == v3::Severity::Error) {
+        process::exit(1);
+    }
 }
 
-fn parse_ref(s: &String) -> String {
-    s.split("/").last().unwrap().to_string()
+/// Build a SARIF 2.1.0 log, locations are logical locations named by JSON
+/// pointers, with regions if spans are tracked.
+fn sarif(input: &Path, spans: Option<&SourceMap>, diags: &[v3::Diagnostic]) -> Value {
+    let rules: Vec<Value> = v3::RULES
+        .iter()
+        .map(|v| {
+            serde_json::json!({
+                "id": v.name,
+                "shortDescription": { "text": v.description },
+                "defaultConfiguration": { "level": sarif_level(v.severity) },
+            })
+        })
+        .collect();
+    let results: Vec<Value> = diags
+        .iter()
+        .map(|v| {
+            let mut physical = serde_json::json!({
+                "artifactLocation": { "uri": input.to_string_lossy() },
+            });
+            if let Some(span) = spans.and_then(|m| m.locate(&v.issue.pointer)) {
+                physical["region"] = serde_json::json!({
+                    "startLine": span.line,
+                    "startColumn": span.column,
+                });
+            }
+            serde_json::json!({
+                "ruleId": v.issue.rule,
+                "level": sarif_level(v.severity),
+                "message": { "text": v.issue.message },
+                "locations": [{
+                    "physicalLocation": physical,
+                    "logicalLocations": [{
+                        "fullyQualifiedName": format!("#{}", v.issue.pointer),
+                    }],
+                }],
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "qi",
+                    "version": crate_version!(),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
 }
 
-fn parse_schema_type(schema: &Schema) -> Model {
-    if let Some(r) = schema.ref_.as_ref() {
-        return Model {
-            kind: ModelKind::Reference,
-            name: Some(parse_ref(r)),
-            annotation: None,
-            properties: None,
-            element: None,
-        };
+fn sarif_level(severity: v3::Severity) -> &'static str {
+    match severity {
+        v3::Severity::Off => "none",
+        v3::Severity::Info => "note",
+        v3::Severity::Warning => "warning",
+        v3::Severity::Error => "error",
     }
+}
 
-    let mut model = Model {
-        kind: ModelKind::Any,
-        annotation: None,
-        name: None,
-        properties: None,
-        element: None,
-    };
-
-    if schema.type_.as_ref().is_none() {
-        return model;
+/// Print a diagnostic with its location, and the code frame around it if
+/// spans are tracked.
+fn report(input: &Path, spans: Option<&SourceMap>, pointer: &str, diag: &dyn Display) {
+    match spans.and_then(|m| m.locate(pointer).map(|span| (m, span))) {
+        None => println!("{}: {}", input.display(), diag),
+        Some((m, span)) => println!(
+            "{}:{}:{}: {}\n{}",
+            input.display(),
+            span.line,
+            span.column,
+            diag,
+            m.code_frame(span)
+        ),
     }
+}
 
-    let schema_type = schema.type_.as_ref().unwrap();
-
-    match schema_type {
-        SchemaType::Boolean => model.kind = ModelKind::Boolean,
-        SchemaType::Object => {
-            model.kind = ModelKind::Struct;
-
-            if let Some(props) = schema.properties.as_ref() {
-                let mut m = BTreeMap::new();
-
-                for (name, property) in props {
-                    m.insert(name.to_string(), parse_schema_type(&property));
-                }
-
-                model.properties = Some(m);
-            }
-        }
-        SchemaType::Array => {
-            model.kind = ModelKind::Array;
-
-            match schema.items.as_ref() {
-                None => println!("schema has kind array, but items is none"),
-                Some(items) => model.element = Some(Box::new(parse_schema_type(items.as_ref()))),
-            }
-        }
-        SchemaType::Number => match schema.format.as_ref() {
-            None => model.kind = ModelKind::Float32,
-            Some(v) => match v.as_str() {
-                "double" => model.kind = ModelKind::Float64,
-                "float" | _ => model.kind = ModelKind::Float32,
-            },
-        },
-        SchemaType::String => {
-            model.kind = ModelKind::String;
-
-            if let Some(v) = schema.format.as_ref() {
-                match v.as_str() {
-                    // TODO: base64 encoded characters
-                    "byte" => {}
-                    "binary" => {
-                        model.kind = ModelKind::Array;
-                        model.element = Some(Box::new(Model {
-                            kind: ModelKind::Byte,
-                            annotation: None,
-                            name: None,
-                            properties: None,
-                            element: None,
-                        }))
-                    }
-                    _ => {}
-                }
-            }
-        }
-        SchemaType::Integer => {
-            match schema.format.as_ref() {
-                None => model.kind = ModelKind::Int,
-                Some(v) => {
-                    match v.as_str() {
-                        "int32" => model.kind = ModelKind::Int32,
-                        "int64" => model.kind = ModelKind::Int64,
-                        _ => model.kind = ModelKind::Int,
-                    };
-                }
-            };
-        }
-    };
-
-    model
+/// Print the error with its source path and exit.
+fn exit_on_error<T>(path: &Path, r: Result<T, Box<dyn Error>>) -> T {
+    r.unwrap_or_else(|e| {
+        eprintln!("{}: {}", path.display(), e);
+        process::exit(1);
+    })
 }
@@ -1,5 +1,16 @@
-use crate::{Model, ModelKind, Operation, Parameter, Service};
-use std::collections::{BTreeMap, HashMap};
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use qi_ir::{
+    Encoding, KeyLocation, Method, Model, ModelKind, Operation, Output, Parameter, SecurityKind,
+    SecurityScheme, Service,
+};
+
+use crate::doc;
+use crate::emit::{self, ident};
 
 pub struct Generator {
     srv: Service,
@@ -10,95 +21,372 @@ impl Generator {
         Generator { srv }
     }
 
-    fn generate_type(&self, m: &Model) -> String {
-        let s = match m.kind {
+    /// Check names, types and derives set by vendor extensions, which are
+    /// used in generated code as is.
+    fn check(&self) -> Result<(), Box<dyn Error>> {
+        let params = self.srv.parameters.values().map(|v| &v.model);
+        let ops = self.srv.operations.iter().flat_map(Operation::models);
+        for m in self.srv.models.values().chain(params).chain(ops) {
+            check_model(m, &self.srv.models)?;
+        }
+
+        for (name, scheme) in self.srv.security_schemes.iter() {
+            if scheme.kind == SecurityKind::ApiKey
+                && (scheme.name.is_none() || scheme.location.is_none())
+            {
+                return Err(format!("security scheme {} requires name and in", name).into());
+            }
+        }
+        for op in self.srv.operations.iter() {
+            for name in op.security.iter().flat_map(|v| v.keys()) {
+                if !self.srv.security_schemes.contains_key(name) {
+                    return Err(format!(
+                        "operation {} requires unknown security scheme {}",
+                        op.id, name
+                    )
+                    .into());
+                }
+            }
+        }
+
+        // Operations are generated in modules named after their tags.
+        for op in self.srv.operations.iter() {
+            if group_name(op).as_deref() == Some("models") {
+                return Err(
+                    format!("tag of operation {} conflicts with module models", op.id).into(),
+                );
+            }
+        }
+
+        for op in self.srv.operations.iter() {
+            self.check_params(op)?;
+            self.check_body(op)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check styles of parameters: path and header parameters must be in
+    /// `simple` style, query parameters could not be in `deepObject` style,
+    /// and arrays in cookies could not be exploded.
+    fn check_params(&self, op: &Operation) -> Result<(), Box<dyn Error>> {
+        for f in input_fields(op).iter().chain(output_fields(op).iter()) {
+            let p = f.param;
+            let supported = match f.location {
+                Location::Query => {
+                    ["form", "spaceDelimited", "pipeDelimited"].contains(&p.style.as_str())
+                }
+                Location::Cookie => p.style == "form" && (!p.explode || p.model.kind.is_simple()),
+                _ => p.style == "simple",
+            };
+            if !supported {
+                let explode = if p.explode { "exploded " } else { "" };
+                return Err(format!(
+                    "{}style {} of parameter {} in operation {} is not supported",
+                    explode, p.style, p.name, op.id
+                )
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check properties of form and multipart bodies: forms could not carry
+    /// binary properties or objects in `deepObject` style, and multipart
+    /// bodies could only stream one binary property.
+    fn check_body(&self, op: &Operation) -> Result<(), Box<dyn Error>> {
+        let format = input_format(op);
+        if format != Some(BodyFormat::Form) && format != Some(BodyFormat::Multipart) {
+            return Ok(());
+        }
+
+        let body = self.deref(op.input.body.as_ref().unwrap());
+        let mut streams = Vec::new();
+        for (name, prop) in body.properties.iter().flatten() {
+            if self.deref(prop).kind == ModelKind::Iterator {
+                streams.push(name.as_str());
+            }
+            if format == Some(BodyFormat::Multipart) {
+                continue;
+            }
+
+            let style = op.input.encoding.get(name).map_or("form", |v| &v.style);
+            if !["form", "spaceDelimited", "pipeDelimited"].contains(&style) {
+                return Err(format!(
+                    "style {} of property {} in form of operation {} is not supported",
+                    style, name, op.id
+                )
+                .into());
+            }
+        }
+
+        match (format, streams.as_slice()) {
+            (Some(BodyFormat::Form), [name, ..]) => Err(format!(
+                "binary property {} could not be sent in form of operation {}",
+                name, op.id
+            )
+            .into()),
+            (_, [_, name, ..]) => Err(format!(
+                "binary property {} in multipart body of operation {} could not be streamed, only one binary property is supported",
+                name, op.id
+            )
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn generate_type(&self, m: &Model) -> TokenStream {
+        if let Some(v) = m.type_path() {
+            return emit::ty(v);
+        }
+
+        match m.kind {
             // Simple type
-            ModelKind::Any => "std::any::Any",
-            ModelKind::Boolean => "bool",
-            ModelKind::String => "String",
-            ModelKind::Byte => "u8",
+            ModelKind::Any => quote!(serde_json::Value),
+            ModelKind::Boolean => quote!(bool),
+            ModelKind::String => quote!(String),
+            ModelKind::Byte => quote!(u8),
             // FIXME
-            ModelKind::Date => "",
-            ModelKind::Time => "",
-            ModelKind::Datetime => "",
-            ModelKind::Int => "isize",
-            ModelKind::Int8 => "i8",
-            ModelKind::Int16 => "i16",
-            ModelKind::Int32 => "i32",
-            ModelKind::Int64 => "i64",
-            ModelKind::Uint => "usize",
-            ModelKind::Uint8 => "u8",
-            ModelKind::Uint16 => "u16",
-            ModelKind::Uint32 => "u32",
-            ModelKind::Uint64 => "u64",
-            ModelKind::Float32 => "f32",
-            ModelKind::Float64 => "f64",
+            ModelKind::Date => quote!(),
+            ModelKind::Time => quote!(),
+            ModelKind::Datetime => quote!(),
+            ModelKind::Int => quote!(isize),
+            ModelKind::Int8 => quote!(i8),
+            ModelKind::Int16 => quote!(i16),
+            ModelKind::Int32 => quote!(i32),
+            ModelKind::Int64 => quote!(i64),
+            ModelKind::Uint => quote!(usize),
+            ModelKind::Uint8 => quote!(u8),
+            ModelKind::Uint16 => quote!(u16),
+            ModelKind::Uint32 => quote!(u32),
+            ModelKind::Uint64 => quote!(u64),
+            ModelKind::Float32 => quote!(f32),
+            ModelKind::Float64 => quote!(f64),
             // Compose type
             ModelKind::Array => {
                 assert!(m.element.is_some());
 
                 let element = m.element.as_ref().unwrap();
-                let element_type = self.generate_type(element);
+                let element_type = self.generate_nullable_type(element);
 
-                return format!("Vec<{}>", element_type);
+                quote!(Vec<#element_type>)
             }
             // FIXME
-            ModelKind::Enum => "",
+            ModelKind::Enum => quote!(),
             ModelKind::Map => {
                 assert!(m.element.is_some());
 
                 let element = m.element.as_ref().unwrap();
-                let element_type = self.generate_type(element);
+                let element_type = self.generate_nullable_type(element);
 
-                return format!("HashMap<String, {}>", element_type);
+                quote!(HashMap<String, #element_type>)
             }
-            ModelKind::Struct => {
-                println!("struct {:?} should be extracted as another type", m);
-
-                ""
-            }
-            // FIXME
-            ModelKind::Iterator => "",
+            // Inline struct doesn't have a name, use dynamic value instead.
+            ModelKind::Struct => quote!(serde_json::Value),
+            // Only byte streams are supported for now.
+            ModelKind::Iterator => quote!(Body),
             ModelKind::Reference => {
                 assert!(m.name.is_some());
 
                 let name = m.name.clone().unwrap();
-                let ref_model = self.srv.models.get(&name).unwrap();
+                let ref_model = self.deref(m);
 
-                if ref_model.kind == ModelKind::Struct {
-                    return name;
+                if ref_model.kind == ModelKind::Struct && ref_model.type_path().is_none() {
+                    let name = ident(&to_pascal_case(&name));
+                    quote!(models::#name)
                 } else {
-                    return self.generate_type(ref_model);
+                    self.generate_type(&ref_model)
                 }
             }
-        };
+        }
+    }
 
-        s.to_string()
+    /// Nullable values are wrapped in `Option`, except dynamic values which
+    /// could hold `null` already.
+    fn generate_nullable_type(&self, m: &Model) -> TokenStream {
+        let typ = self.generate_type(m);
+
+        if m.nullable && typ.to_string() != quote!(serde_json::Value).to_string() {
+            quote!(Option<#typ>)
+        } else {
+            typ
+        }
     }
 
     // Language related.
-    pub fn generate_struct(&self, name: &String, m: &Model) -> String {
+    pub fn generate_struct(&self, name: &str, m: &Model) -> TokenStream {
         assert_eq!(ModelKind::Struct, m.kind);
 
-        let mut s = String::new();
+        let name = ident(name);
+
+        // Streams could only be consumed once and are not serializable.
+        let streaming = m
+            .properties
+            .iter()
+            .flatten()
+            .any(|(_, v)| v.kind == ModelKind::Iterator);
+        let mut derives = if streaming {
+            vec!["Debug", "Default"]
+        } else {
+            vec!["Debug", "Clone", "Default", "Serialize", "Deserialize"]
+        };
+        for v in m.annotation.iter().flat_map(|v| v.derives.iter()) {
+            if !derives.contains(&v.as_str()) {
+                derives.push(v);
+            }
+        }
+        let derives = derives.into_iter().map(emit::path);
+
+        let fields = m.properties.iter().flatten().map(|(name, prop)| {
+            let field = field_name(name, prop);
+            let rename = if field != *name {
+                Some(quote!(#[serde(rename = #name)]))
+            } else {
+                None
+            };
+            let doc = prop.description.as_deref().map(doc::description);
+            let deprecated = deprecated(prop.deprecated(), &format!("property {}", name));
+            let field = ident(&field);
+            let typ = self.generate_nullable_type(prop);
+
+            quote! {
+                #doc
+                #rename
+                #deprecated
+                pub #field: #typ,
+            }
+        });
+        let doc = m.description.as_deref().map(doc::description);
+        let deprecated = deprecated(m.deprecated(), &format!("schema {}", name));
+        let allow = if m
+            .properties
+            .iter()
+            .flatten()
+            .any(|(_, v)| self.uses_deprecated(v))
+        {
+            Some(quote!(#[allow(deprecated)]))
+        } else {
+            None
+        };
+
+        quote! {
+            #doc
+            #[derive(#(#derives),*)]
+            #deprecated
+            #allow
+            pub struct #name {
+                #(#fields)*
+            }
+        }
+    }
+
+    /// Returns the model referred by `m`, references to unknown models are
+    /// rejected by `check`.
+    fn deref(&self, m: &Model) -> Model {
+        match m.kind {
+            ModelKind::Reference => {
+                let name = m.name.as_ref().unwrap();
+                self.srv
+                    .models
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_else(|| panic!("reference to unknown model {}", name))
+            }
+            _ => m.clone(),
+        }
+    }
+
+    /// Whether the type of the model is or contains a deprecated model.
+    fn uses_deprecated(&self, m: &Model) -> bool {
+        if m.type_path().is_some() {
+            return false;
+        }
+
+        match m.kind {
+            ModelKind::Reference => {
+                let v = self.deref(m);
+                if v.kind == ModelKind::Struct && v.type_path().is_none() {
+                    v.deprecated()
+                } else {
+                    self.uses_deprecated(&v)
+                }
+            }
+            _ => m
+                .element
+                .as_deref()
+                .is_some_and(|v| self.uses_deprecated(v)),
+        }
+    }
+
+    /// Returns `#[allow(deprecated)]` if the input or output uses deprecated
+    /// fields or models, so that generated code itself never warns.
+    fn allow_deprecated(&self, fields: &[Field], body: Option<&Model>) -> Option<TokenStream> {
+        let uses = fields
+            .iter()
+            .map(|f| &f.param.model)
+            .any(|v| v.deprecated() || self.uses_deprecated(v))
+            || body.is_some_and(|v| self.uses_deprecated(v));
+
+        uses.then(|| quote!(#[allow(deprecated)]))
+    }
+
+    fn generate_field(&self, f: &Field) -> TokenStream {
+        let what = match f.location {
+            Location::Header => format!("header {}", f.param.name),
+            Location::Cookie => format!("cookie {}", f.param.name),
+            Location::Part(prop) => format!("header {} of part {}", f.param.name, prop),
+            _ => format!("parameter {}", f.param.name),
+        };
+        let doc = f.param.description.as_deref().map(doc::description);
+        let deprecated = deprecated(f.param.model.deprecated(), &what);
+        let field = ident(&f.name);
+        let typ = self.generate_type(&f.param.model);
 
-        if m.properties.as_ref().is_none() {
-            s.push_str(format!("struct {} {{}}", name).as_str());
-            return s;
+        if f.param.mandatory {
+            quote!(#doc #deprecated pub #field: #typ,)
+        } else {
+            quote!(#doc #deprecated pub #field: Option<#typ>,)
         }
+    }
+}
 
-        s.push_str(format!("struct {} {{\n", name).as_str());
-        for (name, prop) in m.properties.as_ref().unwrap().iter() {
-            s.push_str(format!("  {}: {},\n", name, self.generate_type(prop)).as_str());
+fn check_model(m: &Model, models: &BTreeMap<String, Model>) -> Result<(), Box<dyn Error>> {
+    if m.kind == ModelKind::Reference {
+        match m.name.as_ref() {
+            Some(name) if models.contains_key(name) => {}
+            Some(name) => return Err(format!("reference to unknown model {}", name).into()),
+            None => return Err("reference without name".into()),
+        }
+    }
+    if let Some(v) = m.annotation.as_ref() {
+        if let Some(name) = v.display.as_ref() {
+            emit::check_ident(name)?;
         }
-        s.push_str("}\n");
+        if let Some(path) = v.type_path.as_ref() {
+            emit::check_type(path)?;
+        }
+        for path in v.derives.iter() {
+            emit::check_path(path)?;
+        }
+    }
 
-        s
+    for v in m
+        .properties
+        .iter()
+        .flat_map(|v| v.values())
+        .chain(m.element.as_deref())
+    {
+        check_model(v, models)?;
     }
+
+    Ok(())
 }
 
 pub struct ActixWebGenerator {
     g: Generator,
+    server: bool,
+    client: bool,
 }
 
 // common
@@ -121,106 +409,453 @@ pub struct ActixWebGenerator {
 // generate_send(operation) -> fn PutObject(Request) -> Response, error
 impl ActixWebGenerator {
     pub fn new(g: Generator) -> ActixWebGenerator {
-        ActixWebGenerator { g }
-    }
-    fn generate_iterator() {}
-    pub fn generate_structs(&self) {
-        for (name, model) in self.g.srv.models.iter() {
-            if model.kind != ModelKind::Struct {
-                continue;
-            }
-            println!("{}\n", self.g.generate_struct(name, model))
+        ActixWebGenerator {
+            g,
+            server: true,
+            client: true,
         }
     }
-    pub fn generate_input(&self, op: Operation) -> String {
-        let name = op.id + "Input";
-        let mut model = Model {
-            kind: ModelKind::Struct,
-            annotation: None,
-            name: None,
-            properties: Some(BTreeMap::new()),
-            element: None,
+
+    /// Whether to generate the actix-web server, enabled by default.
+    pub fn server(mut self, enabled: bool) -> Self {
+        self.server = enabled;
+        self
+    }
+
+    /// Whether to generate the reqwest client, enabled by default.
+    pub fn client(mut self, enabled: bool) -> Self {
+        self.client = enabled;
+        self
+    }
+
+    /// Generate code formatted in rustfmt style, names and types set by
+    /// vendor extensions are checked and the code is parsed before
+    /// formatting, so that invalid code is never returned.
+    pub fn generate(&self) -> Result<String, Box<dyn Error>> {
+        self.g.check()?;
+
+        let code = emit::format(self.generate_tokens())
+            .map_err(|e| format!("generated code is invalid: {}", e))?;
+
+        Ok(format!("// Code generated by qi. DO NOT EDIT.\n\n{}", code))
+    }
+
+    fn generate_tokens(&self) -> TokenStream {
+        let header = header();
+        let uses_query = self
+            .g
+            .srv
+            .operations
+            .iter()
+            .any(|op| !op.input.query.is_empty());
+        let form = if self.uses(BodyFormat::Form) || uses_query {
+            Some(form())
+        } else {
+            None
+        };
+        let multipart = if self.uses(BodyFormat::Multipart) {
+            Some(multipart())
+        } else {
+            None
+        };
+        let body = self.generate_iterator();
+        let security = if self.uses_security() {
+            Some(security())
+        } else {
+            None
+        };
+        let structs = self.generate_structs();
+        let groups = self.groups();
+        let modules = groups
+            .iter()
+            .filter_map(|(name, ops)| name.as_ref().map(|v| self.generate_module(v, ops)));
+        let ops = groups
+            .get(&None)
+            .map(|ops| self.generate_operations(ops, None));
+        let server = if self.server {
+            Some(self.generate_server(&groups))
+        } else {
+            None
+        };
+        let client = if self.client {
+            Some(self.generate_client(&groups))
+        } else {
+            None
         };
 
-        let m = model.properties.as_mut().unwrap();
-
-        for param in op.input.path.iter() {
-            m.insert(param.name.clone(), param.model.clone());
-        }
-        for param in op.input.query.iter() {
-            m.insert(param.name.clone(), param.model.clone());
-        }
-        for param in op.input.header.iter() {
-            m.insert(param.name.clone(), param.model.clone());
-        }
-        if let Some(body) = op.input.body {
-            if body.kind == ModelKind::String {
-                m.insert(
-                    "body".to_string(),
-                    Model {
-                        kind: ModelKind::Iterator,
-                        annotation: None,
-                        name: None,
-                        properties: None,
-                        element: Some(Box::new(Model {
-                            kind: ModelKind::Byte,
-                            annotation: None,
-                            name: None,
-                            properties: None,
-                            element: None,
-                        })),
-                    },
-                );
+        quote! {
+            #header
+            #form
+            #multipart
+            #body
+            #security
+            #structs
+            #(#modules)*
+            #ops
+            #server
+            #client
+        }
+    }
+
+    /// Returns operations grouped by the module of their first tag, untagged
+    /// operations are grouped by `None` and generated at the top level.
+    fn groups(&self) -> BTreeMap<Option<String>, Vec<&Operation>> {
+        let mut groups: BTreeMap<Option<String>, Vec<&Operation>> = BTreeMap::new();
+        for op in self.g.srv.operations.iter() {
+            groups.entry(group_name(op)).or_default().push(op);
+        }
+
+        groups
+    }
+
+    /// Generate the input, output and conversions of operations, `vis` is
+    /// the visibility of handlers which are routed by `configure`.
+    fn generate_operations(&self, ops: &[&Operation], vis: Option<TokenStream>) -> TokenStream {
+        let ops = ops.iter().map(|op| {
+            let input = self.generate_input(op);
+            let output = self.generate_output(op);
+            let error = self.generate_error(op);
+            let server = if self.server {
+                let parse_request = self.generate_parse_request(op);
+                let format_response = self.generate_format_response(op);
+                let format_error = self.generate_format_error(op);
+                let handler = self.generate_handler(op, vis.clone());
+                Some(quote! {
+                    #parse_request
+                    #format_response
+                    #format_error
+                    #handler
+                })
             } else {
-                m.extend(body.properties.clone().unwrap());
+                None
+            };
+            let client = if self.client {
+                let format_request = self.generate_format_request(op);
+                let parse_response = self.generate_parse_response(op);
+                Some(quote! {
+                    #format_request
+                    #parse_response
+                })
+            } else {
+                None
+            };
+
+            quote! {
+                #input
+                #output
+                #error
+                #server
+                #client
             }
-        }
+        });
 
-        self.g.generate_struct(&name, &model)
+        quote!(#(#ops)*)
     }
-    pub fn generate_output(&self, op: Operation) -> String {
-        let name = op.id + "Output";
-        let mut model = Model {
-            kind: ModelKind::Struct,
-            annotation: None,
-            name: None,
-            properties: Some(BTreeMap::new()),
-            element: None,
+
+    /// Generate the module of a tag, with the server trait and the client
+    /// of its operations.
+    fn generate_module(&self, name: &str, ops: &[&Operation]) -> TokenStream {
+        let ops_tokens = self.generate_operations(ops, Some(quote!(pub(super))));
+        let server = if self.server {
+            let handles = ops.iter().map(|op| self.generate_handle(op));
+            Some(quote! {
+                #[allow(async_fn_in_trait)]
+                pub trait Server: 'static {
+                    #(#handles)*
+                }
+            })
+        } else {
+            None
+        };
+        let client = if self.client {
+            let sends = ops
+                .iter()
+                .map(|op| self.generate_send(op, quote!(self.client)));
+            Some(quote! {
+                pub struct Client<'a> {
+                    pub(super) client: &'a super::Client,
+                }
+
+                impl Client<'_> {
+                    #(#sends)*
+                }
+            })
+        } else {
+            None
         };
+        let name = ident(name);
 
-        let m = model.properties.as_mut().unwrap();
-
-        for param in op.output.header.iter() {
-            m.insert(param.name.clone(), param.model.clone());
-        }
-        if let Some(body) = op.output.body {
-            if body.kind == ModelKind::String {
-                m.insert(
-                    "body".to_string(),
-                    Model {
-                        kind: ModelKind::Iterator,
-                        annotation: None,
-                        name: None,
-                        properties: None,
-                        element: Some(Box::new(Model {
-                            kind: ModelKind::Byte,
-                            annotation: None,
-                            name: None,
-                            properties: None,
-                            element: None,
-                        })),
-                    },
-                );
+        quote! {
+            pub mod #name {
+                #[allow(unused_imports)]
+                use super::*;
+
+                #server
+                #client
+                #ops_tokens
+            }
+        }
+    }
+
+    /// Generate the streaming body type shared by server and client.
+    ///
+    /// `Body` wraps a `Stream<Item = Result<Bytes, BoxError>>` together with
+    /// an optional known length, so that large payloads like objects in S3
+    /// could be transferred without buffering them in memory.
+    fn generate_iterator(&self) -> TokenStream {
+        body()
+    }
+
+    /// Models are generated in their own module so that their names will
+    /// not conflict with operations' input and output.
+    pub fn generate_structs(&self) -> TokenStream {
+        let structs = self
+            .g
+            .srv
+            .models
+            .iter()
+            .filter(|(_, model)| model.kind == ModelKind::Struct && model.type_path().is_none())
+            .map(|(name, model)| self.g.generate_struct(&to_pascal_case(name), model));
+
+        quote! {
+            pub mod models {
+                #[allow(unused_imports)]
+                use super::*;
+
+                #(#structs)*
+            }
+        }
+    }
+
+    /// Returns the type of a body, inline structs are named after the
+    /// operation's input or output with a `Body` suffix.
+    fn body_type(&self, name: &str, body: &Model) -> TokenStream {
+        if is_inline_struct(body) {
+            format_ident!("{}Body", name).into_token_stream()
+        } else {
+            self.g.generate_type(body)
+        }
+    }
+
+    pub fn generate_input(&self, op: &Operation) -> TokenStream {
+        // Descriptions of request bodies are about the body instead of the
+        // whole input.
+        let body = op.input.body.as_ref();
+        let body_doc = op.input.description.as_deref().map(doc::description);
+        self.generate_message(&input_name(op), &input_fields(op), body, None, body_doc)
+    }
+
+    pub fn generate_output(&self, op: &Operation) -> TokenStream {
+        let doc = op.output.description.as_deref().map(doc::description);
+        self.generate_message(
+            &output_name(op),
+            &output_fields(op),
+            op.output.body.as_ref(),
+            doc,
+            None,
+        )
+    }
+
+    /// Generate the enum of an operation's error responses, which is
+    /// returned boxed by the client, and sent by the server if handlers
+    /// return it.
+    ///
+    /// Error bodies are small, so raw ones are buffered as `Bytes` instead
+    /// of streamed, which keeps the error `Send + Sync`.
+    fn generate_error(&self, op: &Operation) -> Option<TokenStream> {
+        if op.errors.is_empty() {
+            return None;
+        }
+
+        let name = error_name(op);
+        let errors = sorted_errors(op);
+        let allow = self.generate_error_allow(op);
+
+        let mut inline = Vec::new();
+        let mut variants = Vec::new();
+        let mut statuses = Vec::new();
+        for e in errors.iter() {
+            let variant = ident(&error_variant(e));
+            let doc = e.description.as_deref().map(doc::description);
+            let body = e.body.as_ref().map(|v| {
+                if is_inline_struct(v) {
+                    let body_name = format!("{}{}", name, variant);
+                    inline.push(self.g.generate_struct(&format!("{}Body", body_name), v));
+                }
+                self.error_body_type(&format!("{}{}", name, variant), v)
+            });
+            let status = Literal::usize_unsuffixed(e.status_code);
+
+            if e.range || e.status_code == 0 {
+                let body = body.map(|v| quote!(body: #v,));
+                variants.push(quote!(#doc #variant { status: u16, #body },));
+                statuses.push(quote!(Self::#variant { status, .. } => *status,));
+            } else if let Some(body) = body {
+                variants.push(quote!(#doc #variant(#body),));
+                statuses.push(quote!(Self::#variant(..) => #status,));
             } else {
-                if let Some(props) = body.properties.clone() {
-                    m.extend(props);
-                } else {
-                    m.insert("body".to_string(), body);
+                variants.push(quote!(#doc #variant,));
+                statuses.push(quote!(Self::#variant => #status,));
+            }
+        }
+
+        let doc = doc::attrs(&format!(
+            "Error responses of `{}`.\n\nThey are returned boxed by the client, and sent by the server if handlers return them.",
+            op.id
+        ));
+        let id = &op.id;
+        let name = ident(&name);
+        Some(quote! {
+            #(#inline)*
+
+            #doc
+            #[derive(Debug)]
+            #allow
+            pub enum #name {
+                #(#variants)*
+            }
+
+            #allow
+            impl #name {
+                /// Status code of the response.
+                pub fn status(&self) -> u16 {
+                    match self {
+                        #(#statuses)*
+                    }
+                }
+            }
+
+            impl std::fmt::Display for #name {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "{} responds with status {}", #id, self.status())
                 }
             }
+
+            impl std::error::Error for #name {}
+        })
+    }
+
+    /// Returns the type of an error body, raw bodies are buffered.
+    fn error_body_type(&self, name: &str, body: &Model) -> TokenStream {
+        if body.kind == ModelKind::Iterator {
+            quote!(Bytes)
+        } else {
+            self.body_type(name, body)
+        }
+    }
+
+    /// Generate the struct of an operation's input or output, and the
+    /// struct of its body if it's inline.
+    fn generate_message(
+        &self,
+        name: &str,
+        fields: &[Field],
+        body: Option<&Model>,
+        doc: Option<TokenStream>,
+        body_doc: Option<TokenStream>,
+    ) -> TokenStream {
+        let allow = self.g.allow_deprecated(fields, body);
+        let inline = body
+            .filter(|v| is_inline_struct(v))
+            .map(|v| self.g.generate_struct(&format!("{}Body", name), v));
+        let fields = fields.iter().map(|f| self.g.generate_field(f));
+        let body = body.map(|v| {
+            let typ = self.body_type(name, v);
+            quote!(#body_doc pub body: #typ,)
+        });
+        let name = ident(name);
+
+        quote! {
+            #inline
+
+            #doc
+            #[derive(Debug, Default)]
+            #allow
+            pub struct #name {
+                #(#fields)*
+                #body
+            }
+        }
+    }
+
+    /// Generate the server trait, which requires the traits of all tags
+    /// and has methods of untagged operations.
+    fn generate_server(&self, groups: &BTreeMap<Option<String>, Vec<&Operation>>) -> TokenStream {
+        let traits = groups.keys().flatten().map(|v| {
+            let name = ident(v);
+            quote!(#name::Server +)
+        });
+        let handles = groups
+            .get(&None)
+            .into_iter()
+            .flatten()
+            .map(|op| self.generate_handle(op));
+        let dispatch = self.generate_dispatch();
+        let (authenticate, authorize) = if self.uses_security() {
+            (
+                Some(quote! {
+                    /// Authenticate the credential of a security scheme, which runs
+                    /// before handlers of operations requiring the scheme. `scopes` are
+                    /// required by the operation, requests are rejected with `401` on
+                    /// errors.
+                    async fn authenticate(
+                        &self,
+                        req: &HttpRequest,
+                        scheme: &str,
+                        scopes: &[&str],
+                        credential: Credential,
+                    ) -> Result<(), BoxError>;
+                }),
+                Some(self.generate_authorize()),
+            )
+        } else {
+            (None, None)
+        };
+
+        quote! {
+            #[allow(async_fn_in_trait)]
+            pub trait Server: #(#traits)* 'static {
+                #authenticate
+                #(#handles)*
+            }
+
+            #authorize
+            #dispatch
         }
+    }
+
+    /// Generate the client, which has methods of untagged operations and
+    /// returns clients of tags by their names, like `client.pets()`.
+    fn generate_client(&self, groups: &BTreeMap<Option<String>, Vec<&Operation>>) -> TokenStream {
+        let client = client(self.uses_security());
+        let credentials = if self.uses_security() {
+            Some(self.generate_credentials())
+        } else {
+            None
+        };
+        let accessors = groups.keys().flatten().map(|v| {
+            let name = ident(v);
+            quote! {
+                pub fn #name(&self) -> #name::Client<'_> {
+                    #name::Client { client: self }
+                }
+            }
+        });
+        let sends = groups
+            .get(&None)
+            .into_iter()
+            .flatten()
+            .map(|op| self.generate_send(op, quote!(self)));
+
+        quote! {
+            #client
+            #credentials
 
-        self.g.generate_struct(&name, &model)
+            impl Client {
+                #(#accessors)*
+                #(#sends)*
+            }
+        }
     }
 
     //  App::new().service(web::resource("/index.html").route(
@@ -231,30 +866,1762 @@ impl ActixWebGenerator {
     //                              .contains_key("content-type")))
     //             .to(|| HttpResponse::MethodNotAllowed()))
     //     );
-    pub fn generate_dispatch(&self) -> String {
-        let mut s = String::new();
+    pub fn generate_dispatch(&self) -> TokenStream {
+        // Routes with guards must be registered before the plain ones which
+        // share the same path, otherwise they will never be matched.
+        let mut ops: Vec<&Operation> = self.g.srv.operations.iter().collect();
+        ops.sort_by_key(|op| route_guards(op).is_empty());
 
-        s.push_str("App::new()\n");
-        for op in self.g.srv.operations.iter() {
-            let (id, method, uri) = (&op.id, &op.method, &op.uri);
-
-            s.push_str(
-                format!(
-                    "  .service(
-    web::resource(\"{}\")
-      .route(
-        web::route().guard(guard::{:?}())
-    )))
-  )\n",
-                    uri, method,
-                )
-                .as_str(),
-            );
+        let routes = ops.into_iter().map(|op| {
+            let path = route_path(op);
+            let method = ident(method_name(op.method));
+            let guards = route_guards(op)
+                .into_iter()
+                .map(|key| quote!(.guard(guard::fn_guard(|ctx| has_key(ctx.head(), #key)))));
+            let handler = handler_name(op);
+            let module = group_name(op).map(|v| {
+                let name = ident(&v);
+                quote!(#name::)
+            });
+
+            quote! {
+                cfg.route(#path, web::#method()#(#guards)*.to(#module #handler::<S>));
+            }
+        });
+
+        quote! {
+            pub fn configure<S: Server>(cfg: &mut web::ServiceConfig) {
+                #(#routes)*
+            }
+        }
+    }
+
+    fn generate_parse_request(&self, op: &Operation) -> TokenStream {
+        let mut stmts = Vec::new();
+
+        if op.input.body.is_none() {
+            stmts.push(quote!(let _ = payload;));
+        }
+        let uses_req = matches!(
+            input_format(op),
+            Some(BodyFormat::Stream) | Some(BodyFormat::Multipart)
+        );
+        if input_fields(op).is_empty() && !uses_req {
+            stmts.push(quote!(let _ = req;));
+        }
+        if !op.input.query.is_empty() {
+            stmts.push(quote! {
+                let query: Vec<(String, String)> = serde_urlencoded::from_str(req.query_string())?;
+            });
+        }
+        match input_format(op) {
+            Some(BodyFormat::Form) => stmts.push(self.generate_decode_form(op)),
+            Some(BodyFormat::Multipart) => stmts.push(self.generate_decode_multipart(op)),
+            _ => {}
+        }
+
+        let mut fields: Vec<TokenStream> = input_fields(op)
+            .iter()
+            .map(|f| {
+                let name = &f.param.name;
+                let value = match f.location {
+                    Location::Path => {
+                        quote!(req.match_info().get(#name).map(decode_path).transpose()?.as_deref())
+                    }
+                    Location::Query if exploded(f) => quote!(form_values(&query, #name)),
+                    Location::Query => quote!(form_value(&query, #name)),
+                    Location::Header => {
+                        quote!(req.headers().get(#name).and_then(|v| v.to_str().ok()))
+                    }
+                    Location::Cookie => quote!(req.cookie(#name).as_ref().map(|v| v.value())),
+                    Location::Part(prop) => quote! {
+                        parts.get(#prop).and_then(|v| v.get(#name)).and_then(|v| v.to_str().ok())
+                    },
+                };
+                parse_param(f, value)
+            })
+            .collect();
+        match input_format(op) {
+            None => {}
+            Some(BodyFormat::Stream) => {
+                fields.push(quote!(body: Body::from_payload(req, payload),))
+            }
+            Some(BodyFormat::Form) | Some(BodyFormat::Multipart) => fields.push(quote!(body,)),
+            Some(BodyFormat::Json) => fields.push(quote! {
+                body: serde_json::from_slice(&read_payload(payload).await?)?,
+            }),
+        }
+
+        let name = ident(&input_name(op));
+        let allow = self
+            .g
+            .allow_deprecated(&input_fields(op), op.input.body.as_ref());
+        quote! {
+            #allow
+            impl #name {
+                async fn from_request(req: &HttpRequest, payload: web::Payload) -> Result<Self, BoxError> {
+                    #(#stmts)*
+                    Ok(Self {
+                        #(#fields)*
+                    })
+                }
+            }
+        }
+    }
+
+    fn generate_format_response(&self, op: &Operation) -> TokenStream {
+        let status = Literal::usize_unsuffixed(op.output.status_code);
+        let headers = output_fields(op).into_iter().map(|f| {
+            format_param(
+                &f,
+                |name, value| quote!(resp.insert_header((#name, #value));),
+            )
+        });
+        let body = match op.output.body.as_ref() {
+            None => quote!(Ok(resp.finish())),
+            Some(body) if body.kind == ModelKind::Iterator => quote! {
+                if let Some(size) = self.body.len() {
+                    resp.no_chunking(size);
+                }
+                Ok(resp.streaming(self.body.map_err(|e| e as Box<dyn std::error::Error>)))
+            },
+            Some(_) => quote!(Ok(resp.json(self.body))),
+        };
+
+        let name = ident(&output_name(op));
+        let allow = self
+            .g
+            .allow_deprecated(&output_fields(op), op.output.body.as_ref());
+        quote! {
+            #allow
+            impl #name {
+                fn into_response(self) -> Result<HttpResponse, BoxError> {
+                    let mut resp = HttpResponse::build(StatusCode::from_u16(#status)?);
+                    #(#headers)*
+                    #body
+                }
+            }
+        }
+    }
+
+    fn generate_format_error(&self, op: &Operation) -> Option<TokenStream> {
+        if op.errors.is_empty() {
+            return None;
+        }
+
+        let arms = sorted_errors(op).into_iter().map(|e| {
+            let variant = ident(&error_variant(e));
+            let pattern = match (e.range || e.status_code == 0, e.body.is_some()) {
+                (true, true) => quote!(Self::#variant { body, .. }),
+                (true, false) => quote!(Self::#variant { .. }),
+                (false, true) => quote!(Self::#variant(body)),
+                (false, false) => quote!(Self::#variant),
+            };
+            let resp = match e.body.as_ref() {
+                None => quote!(resp.finish()),
+                Some(v) if v.kind == ModelKind::Iterator => quote!(resp.body(body)),
+                Some(_) => quote!(resp.json(body)),
+            };
+            quote!(#pattern => #resp,)
+        });
+
+        let name = ident(&error_name(op));
+        let allow = self.generate_error_allow(op);
+        Some(quote! {
+            #allow
+            impl #name {
+                fn into_response(self) -> HttpResponse {
+                    let status = StatusCode::from_u16(self.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                    let mut resp = HttpResponse::build(status);
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        })
+    }
+
+    /// Returns `#[allow(deprecated)]` if bodies of error responses use
+    /// deprecated models.
+    fn generate_error_allow(&self, op: &Operation) -> Option<TokenStream> {
+        let uses = op
+            .errors
+            .iter()
+            .filter_map(|e| e.body.as_ref())
+            .any(|v| self.g.uses_deprecated(v));
+
+        uses.then(|| quote!(#[allow(deprecated)]))
+    }
+
+    fn generate_handle(&self, op: &Operation) -> TokenStream {
+        let name = ident(&fn_name(op));
+        let input = ident(&input_name(op));
+        let output = ident(&output_name(op));
+        let doc = doc::attrs(&operation_doc(op));
+
+        quote! {
+            #doc
+            async fn #name(&self, input: #input) -> Result<#output, BoxError>;
+        }
+    }
+
+    fn generate_handler(&self, op: &Operation, vis: Option<TokenStream>) -> TokenStream {
+        let handler = handler_name(op);
+        let name = ident(&fn_name(op));
+        let input = ident(&input_name(op));
+        // Handlers in modules of tags are bound by the top level server,
+        // which authenticates requests.
+        let server = if vis.is_some() {
+            quote!(super::Server)
+        } else {
+            quote!(Server)
+        };
+        // Errors of the operation are sent as their responses.
+        let format_error = if op.errors.is_empty() {
+            quote!(Err(e) => HttpResponse::InternalServerError().body(e.to_string()),)
+        } else {
+            let error = ident(&error_name(op));
+            quote! {
+                Err(e) => match e.downcast::<#error>() {
+                    Ok(e) => e.into_response(),
+                    Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+                },
+            }
+        };
+        let authorize = if op.security.is_empty() {
+            None
+        } else {
+            let security = security_requirement(op);
+            Some(quote! {
+                if let Err(e) = authorize(srv.get_ref(), &req, #security).await {
+                    return HttpResponse::Unauthorized().body(e.to_string());
+                }
+            })
+        };
+
+        quote! {
+            #vis async fn #handler<S: #server>(srv: web::Data<S>, req: HttpRequest, payload: web::Payload) -> HttpResponse {
+                #authorize
+                let input = match #input::from_request(&req, payload).await {
+                    Ok(v) => v,
+                    Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+                };
+                match srv.#name(input).await.and_then(|v| v.into_response()) {
+                    Ok(resp) => resp,
+                    #format_error
+                }
+            }
+        }
+    }
+
+    fn generate_format_request(&self, op: &Operation) -> TokenStream {
+        let fields = input_fields(op);
+        let mut stmts = Vec::new();
+
+        let mut uri = route_path(op);
+        let mut args = Vec::new();
+        for f in fields.iter().filter(|f| f.location == Location::Path) {
+            uri = uri.replace(&format!("{{{}}}", f.param.name), "{}");
+            args.push(ident(&f.name));
+        }
+        let uri = format!("{{}}{}", uri);
+        stmts.push(quote! {
+            let url = format!(#uri, endpoint #(, encode_path(self.#args.to_string()))*);
+        });
+
+        let mutable = !op.input.query.is_empty()
+            || !op.input.header.is_empty()
+            || !op.input.cookie.is_empty()
+            || op.input.body.is_some();
+        let mutability = if mutable { Some(quote!(mut)) } else { None };
+        let method = ident(&method_name(op.method).to_uppercase());
+        stmts.push(quote! {
+            let #mutability req = http.request(reqwest::Method::#method, url);
+        });
+
+        if !op.input.query.is_empty() {
+            let queries = fields
+                .iter()
+                .filter(|f| f.location == Location::Query)
+                .map(|f| format_param(f, |name, value| quote!(query.push((#name, #value));)));
+            stmts.push(quote! {
+                let mut query: Vec<(&str, String)> = Vec::new();
+                #(#queries)*
+                req = req.query(&query);
+            });
+        }
+        for f in fields.iter().filter(|f| f.location == Location::Header) {
+            stmts.push(format_param(
+                f,
+                |name, value| quote!(req = req.header(#name, #value);),
+            ));
+        }
+        // Cookies are sent in one header joined by `; `.
+        if !op.input.cookie.is_empty() {
+            let cookies = fields
+                .iter()
+                .filter(|f| f.location == Location::Cookie)
+                .map(|f| {
+                    format_param(
+                        f,
+                        |name, value| quote!(cookies.push(format!("{}={}", #name, #value));),
+                    )
+                });
+            stmts.push(quote! {
+                let mut cookies: Vec<String> = Vec::new();
+                #(#cookies)*
+                if !cookies.is_empty() {
+                    req = req.header("cookie", cookies.join("; "));
+                }
+            });
         }
+        match input_format(op) {
+            None => {}
+            Some(BodyFormat::Stream) => stmts.push(quote! {
+                if let Some(size) = self.body.len() {
+                    req = req.header("content-length", size);
+                }
+                req = req.body(reqwest::Body::wrap_stream(self.body));
+            }),
+            Some(BodyFormat::Form) => stmts.push(self.generate_encode_form(op)),
+            Some(BodyFormat::Multipart) => stmts.push(self.generate_encode_multipart(op)),
+            Some(BodyFormat::Json) => stmts.push(quote!(req = req.json(&self.body);)),
+        }
+
+        let name = ident(&input_name(op));
+        let allow = self
+            .g
+            .allow_deprecated(&input_fields(op), op.input.body.as_ref());
+        quote! {
+            #allow
+            impl #name {
+                fn into_request(self, http: &reqwest::Client, endpoint: &str) -> Result<reqwest::RequestBuilder, BoxError> {
+                    #(#stmts)*
+                    Ok(req)
+                }
+            }
+        }
+    }
 
-        s
+    fn generate_parse_response(&self, op: &Operation) -> TokenStream {
+        // Ranges like `2XX` accept any status code of the class.
+        let unexpected = if op.output.range {
+            let class = Literal::usize_unsuffixed(op.output.status_code / 100);
+            quote!(resp.status().as_u16() / 100 != #class)
+        } else {
+            let status = Literal::usize_unsuffixed(op.output.status_code);
+            quote!(resp.status().as_u16() != #status)
+        };
+        let parse_error = self.generate_parse_error(op);
+        let mut fields: Vec<TokenStream> = output_fields(op)
+            .iter()
+            .map(|f| {
+                let name = &f.param.name;
+                parse_param(
+                    f,
+                    quote!(resp.headers().get(#name).and_then(|v| v.to_str().ok())),
+                )
+            })
+            .collect();
+        match op.output.body.as_ref() {
+            None => {}
+            Some(body) if body.kind == ModelKind::Iterator => {
+                fields.push(quote!(body: Body::from_response(resp),));
+            }
+            Some(_) => fields.push(quote!(body: resp.json().await?,)),
+        }
+
+        let name = ident(&output_name(op));
+        let allow = self
+            .g
+            .allow_deprecated(&output_fields(op), op.output.body.as_ref());
+        quote! {
+            #allow
+            impl #name {
+                async fn from_response(resp: reqwest::Response) -> Result<Self, BoxError> {
+                    if #unexpected {
+                        let status = resp.status();
+                        #parse_error
+                    }
+                    Ok(Self {
+                        #(#fields)*
+                    })
+                }
+            }
+        }
+    }
+
+    /// Generate the statements which return the error of an unexpected
+    /// `status`, which is the operation's error of the status if declared.
+    fn generate_parse_error(&self, op: &Operation) -> TokenStream {
+        let unexpected = quote! {
+            let text = resp.text().await.unwrap_or_default();
+            return Err(format!("unexpected status {}: {}", status, text).into());
+        };
+        if op.errors.is_empty() {
+            return unexpected;
+        }
+
+        let name = ident(&error_name(op));
+        let mut arms: Vec<TokenStream> = sorted_errors(op)
+            .into_iter()
+            .map(|e| {
+                let variant = ident(&error_variant(e));
+                let body = match e.body.as_ref() {
+                    None => None,
+                    Some(v) if v.kind == ModelKind::Iterator => Some(quote!(resp.bytes().await?)),
+                    Some(_) => Some(quote!(resp.json().await?)),
+                };
+                let value = match (e.range || e.status_code == 0, body) {
+                    (true, Some(body)) => quote!(#name::#variant { status: code, body: #body }),
+                    (true, None) => quote!(#name::#variant { status: code }),
+                    (false, Some(body)) => quote!(#name::#variant(#body)),
+                    (false, None) => quote!(#name::#variant),
+                };
+                let pattern = match e.status_code {
+                    0 => quote!(_),
+                    v if e.range => {
+                        let start = Literal::usize_unsuffixed(v);
+                        let end = Literal::usize_unsuffixed(v + 99);
+                        quote!(#start..=#end)
+                    }
+                    v => Literal::usize_unsuffixed(v).into_token_stream(),
+                };
+                quote!(#pattern => #value,)
+            })
+            .collect();
+        if op.errors.iter().all(|e| e.status_code != 0) {
+            arms.push(quote!(_ => { #unexpected }));
+        }
+
+        quote! {
+            let code = status.as_u16();
+            let e = match code {
+                #(#arms)*
+            };
+            return Err(Box::new(e));
+        }
+    }
+
+    /// Whether any operation requires security schemes.
+    fn uses_security(&self) -> bool {
+        self.g
+            .srv
+            .operations
+            .iter()
+            .any(|op| !op.security.is_empty())
+    }
+
+    /// Generate the authorization of requests on the server, which extracts
+    /// credentials by schemes and authenticates them by the server.
+    fn generate_authorize(&self) -> TokenStream {
+        let arms = self
+            .g
+            .srv
+            .security_schemes
+            .iter()
+            .map(|(name, scheme)| {
+                let value = match scheme.kind {
+                    SecurityKind::ApiKey => {
+                        let key = scheme.name.as_ref().unwrap();
+                        match scheme.location.unwrap() {
+                            KeyLocation::Header => quote! {
+                                req.headers().get(#key).and_then(|v| v.to_str().ok()).map(|v| Credential::ApiKey(v.to_string()))
+                            },
+                            KeyLocation::Query => quote! {
+                                web::Query::<HashMap<String, String>>::from_query(req.query_string())
+                                    .ok()
+                                    .and_then(|q| q.get(#key).cloned())
+                                    .map(Credential::ApiKey)
+                            },
+                            KeyLocation::Cookie => quote! {
+                                req.cookie(#key).map(|v| Credential::ApiKey(v.value().to_string()))
+                            },
+                        }
+                    }
+                    SecurityKind::Http if scheme.scheme.as_deref() == Some("basic") => {
+                        quote!(authorization(req, "basic").and_then(|v| decode_basic(&v)))
+                    }
+                    _ => {
+                        let prefix = http_scheme(scheme);
+                        quote!(authorization(req, #prefix).map(Credential::Bearer))
+                    }
+                };
+
+                quote!(#name => #value,)
+            });
+
+        quote! {
+            /// Check the credentials of a request against the security
+            /// requirement of its operation, errors of rejected credentials are
+            /// returned rather than missing ones.
+            async fn authorize<S: Server>(srv: &S, req: &HttpRequest, security: Security) -> Result<(), BoxError> {
+                let mut rejected = None;
+                'alternatives: for alternative in security.iter() {
+                    for (scheme, scopes) in alternative.iter() {
+                        let credential = match extract_credential(req, scheme) {
+                            Some(v) => v,
+                            None => continue 'alternatives,
+                        };
+                        if let Err(e) = srv.authenticate(req, scheme, scopes, credential).await {
+                            rejected.get_or_insert(e);
+                            continue 'alternatives;
+                        }
+                    }
+                    return Ok(());
+                }
+                Err(rejected.unwrap_or_else(|| "missing credentials".into()))
+            }
+
+            fn extract_credential(req: &HttpRequest, scheme: &str) -> Option<Credential> {
+                match scheme {
+                    #(#arms)*
+                    _ => None,
+                }
+            }
+
+            /// Returns the credentials of the `Authorization` header if it's
+            /// of the scheme, which is case-insensitive.
+            #[allow(dead_code)]
+            fn authorization(req: &HttpRequest, scheme: &str) -> Option<String> {
+                let value = req.headers().get("authorization")?.to_str().ok()?;
+                let (prefix, credentials) = value.split_once(' ')?;
+                prefix.eq_ignore_ascii_case(scheme).then(|| credentials.trim().to_string())
+            }
+
+            /// Decode the base64 credentials of the http `basic` scheme.
+            #[allow(dead_code)]
+            fn decode_basic(credentials: &str) -> Option<Credential> {
+                const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+                let mut buf = Vec::new();
+                let (mut bits, mut len) = (0u32, 0);
+                for c in credentials.trim_end_matches('=').bytes() {
+                    let v = ALPHABET.iter().position(|v| *v == c)? as u32;
+                    bits = (bits << 6) | v;
+                    len += 6;
+                    if len >= 8 {
+                        len -= 8;
+                        buf.push((bits >> len) as u8);
+                        bits &= (1 << len) - 1;
+                    }
+                }
+                let text = String::from_utf8(buf).ok()?;
+                let (username, password) = match text.split_once(':') {
+                    Some((username, password)) => (username.to_string(), Some(password.to_string())),
+                    None => (text, None),
+                };
+                Some(Credential::Basic { username, password })
+            }
+        }
+    }
+
+    /// Generate the credential provider of the client, and the application
+    /// of credentials to requests by schemes.
+    fn generate_credentials(&self) -> TokenStream {
+        let arms = self
+            .g
+            .srv
+            .security_schemes
+            .iter()
+            .map(|(name, scheme)| match scheme.kind {
+                SecurityKind::ApiKey => {
+                    let key = scheme.name.as_ref().unwrap();
+                    let apply = match scheme.location.unwrap() {
+                        KeyLocation::Header => quote!(req.header(#key, v)),
+                        KeyLocation::Query => quote!(req.query(&[(#key, v)])),
+                        KeyLocation::Cookie => {
+                            quote!(req.header("cookie", format!("{}={}", #key, v)))
+                        }
+                    };
+                    quote!((#name, Credential::ApiKey(v)) => Ok(#apply),)
+                }
+                SecurityKind::Http if scheme.scheme.as_deref() == Some("basic") => quote! {
+                    (#name, Credential::Basic { username, password }) => Ok(req.basic_auth(username, password)),
+                },
+                _ => match http_scheme(scheme) {
+                    "bearer" => quote!((#name, Credential::Bearer(v)) => Ok(req.bearer_auth(v)),),
+                    prefix => quote! {
+                        (#name, Credential::Bearer(v)) => Ok(req.header("authorization", format!("{} {}", #prefix, v))),
+                    },
+                },
+            });
+
+        quote! {
+            /// CredentialProvider provides credentials of security schemes to the
+            /// client, like tokens which are refreshed before expiration.
+            pub trait CredentialProvider: Send + Sync {
+                /// Returns the credential of a scheme with the scopes required by
+                /// the operation, or `None` if it's not available.
+                fn credential<'a>(
+                    &'a self,
+                    scheme: &'a str,
+                    scopes: &'a [&'a str],
+                ) -> futures::future::BoxFuture<'a, Result<Option<Credential>, BoxError>>;
+            }
+
+            /// Fixed credentials keyed by scheme names.
+            impl CredentialProvider for HashMap<String, Credential> {
+                fn credential<'a>(
+                    &'a self,
+                    scheme: &'a str,
+                    _: &'a [&'a str],
+                ) -> futures::future::BoxFuture<'a, Result<Option<Credential>, BoxError>> {
+                    Box::pin(async move { Ok(self.get(scheme).cloned()) })
+                }
+            }
+
+            impl Client {
+                /// Set the provider of credentials for operations which require
+                /// security schemes, requests are sent without credentials if it's
+                /// not set.
+                pub fn with_credentials(mut self, provider: impl CredentialProvider + 'static) -> Self {
+                    self.credentials = Some(std::sync::Arc::new(provider));
+                    self
+                }
+
+                /// Apply credentials of the first alternative of the security
+                /// requirement whose credentials are all provided.
+                async fn authorize(&self, mut req: reqwest::RequestBuilder, security: Security) -> Result<reqwest::RequestBuilder, BoxError> {
+                    let provider = match self.credentials.as_ref() {
+                        Some(v) => v,
+                        None => return Ok(req),
+                    };
+                    'alternatives: for alternative in security.iter() {
+                        let mut credentials = Vec::new();
+                        for (scheme, scopes) in alternative.iter() {
+                            match provider.credential(scheme, scopes).await? {
+                                Some(v) => credentials.push((*scheme, v)),
+                                None => continue 'alternatives,
+                            }
+                        }
+                        for (scheme, credential) in credentials {
+                            req = apply_credential(req, scheme, credential)?;
+                        }
+                        return Ok(req);
+                    }
+                    Ok(req)
+                }
+            }
+
+            fn apply_credential(req: reqwest::RequestBuilder, scheme: &str, credential: Credential) -> Result<reqwest::RequestBuilder, BoxError> {
+                match (scheme, credential) {
+                    #(#arms)*
+                    (scheme, _) => Err(format!("invalid credential of security scheme {}", scheme).into()),
+                }
+            }
+        }
+    }
+
+    fn uses(&self, format: BodyFormat) -> bool {
+        self.g
+            .srv
+            .operations
+            .iter()
+            .any(|op| input_format(op) == Some(format))
+    }
+
+    /// Returns the properties of input body with their field names and
+    /// encodings, streaming properties are placed at the end.
+    fn body_props(&self, op: &Operation) -> Vec<BodyProp> {
+        let body = self.g.deref(op.input.body.as_ref().unwrap());
+        let required = body.required;
+
+        let mut props: Vec<_> = body
+            .properties
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, prop)| {
+                let field = field_name(&name, &prop);
+                let model = self.g.deref(&prop);
+                let encoding = op.input.encoding.get(&name).cloned().unwrap_or(Encoding {
+                    content_type: "text/plain".to_string(),
+                    headers: vec![],
+                    style: "form".to_string(),
+                    explode: true,
+                });
+                BodyProp {
+                    required: required.contains(&name),
+                    name,
+                    field,
+                    model,
+                    encoding,
+                }
+            })
+            .collect();
+        props.sort_by_key(|v| v.model.kind == ModelKind::Iterator);
+
+        props
+    }
+
+    /// Generate the form decoder, required properties which are missing
+    /// are rejected, except exploded arrays which are empty then.
+    fn generate_decode_form(&self, op: &Operation) -> TokenStream {
+        let body = self.body_type(&input_name(op), op.input.body.as_ref().unwrap());
+        let fields = self.body_props(op).into_iter().map(|p| {
+            let name = &p.name;
+            let delimiter = delimiter(&p.encoding.style);
+            let field = ident(&p.field);
+            let value = match form_kind(&p.model) {
+                FormKind::Simple => quote!(parse_value(#name, form_value(&form, #name))?),
+                FormKind::Array if p.encoding.explode => {
+                    return quote!(#field: parse_all(#name, form_values(&form, #name))?,);
+                }
+                FormKind::Array => {
+                    quote!(parse_values(#name, form_value(&form, #name), #delimiter)?)
+                }
+                FormKind::Complex => quote! {
+                    form_value(&form, #name).map(serde_json::from_str).transpose()?
+                },
+                FormKind::Stream => unreachable!("binary properties in form must be checked"),
+            };
+            let value = if p.required {
+                quote!(required(#name, #value)?)
+            } else {
+                quote!(#value.unwrap_or_default())
+            };
+
+            quote!(#field: #value,)
+        });
+
+        quote! {
+            let form: Vec<(String, String)> =
+                serde_urlencoded::from_bytes(&read_payload(payload).await?)?;
+            let body = #body {
+                #(#fields)*
+            };
+        }
+    }
+
+    fn generate_encode_form(&self, op: &Operation) -> TokenStream {
+        let stmts = self.body_props(op).into_iter().map(|p| {
+            let name = &p.name;
+            let field = ident(&p.field);
+            let delimiter = delimiter(&p.encoding.style);
+            match form_kind(&p.model) {
+                FormKind::Simple => quote! {
+                    form.push((#name, self.body.#field.to_string()));
+                },
+                FormKind::Array if p.encoding.explode => quote! {
+                    for v in &self.body.#field {
+                        form.push((#name, v.to_string()));
+                    }
+                },
+                FormKind::Array => quote! {
+                    form.push((#name, format_values(&self.body.#field, #delimiter)));
+                },
+                FormKind::Complex => quote! {
+                    form.push((#name, serde_json::to_string(&self.body.#field)?));
+                },
+                FormKind::Stream => unreachable!("binary properties in form must be checked"),
+            }
+        });
+
+        quote! {
+            let mut form: Vec<(&str, String)> = Vec::new();
+            #(#stmts)*
+            req = req.form(&form);
+        }
+    }
+
+    /// Generate the multipart decoder, headers of parts are kept in `parts`
+    /// by their names for the fields of part headers.
+    ///
+    /// Streaming parts are forwarded to the handler without buffering, so
+    /// they must be the last part in the request: parts after the first
+    /// streaming part are reported as errors of the stream.
+    fn generate_decode_multipart(&self, op: &Operation) -> TokenStream {
+        let body = self.body_type(&input_name(op), op.input.body.as_ref().unwrap());
+        let props = self.body_props(op);
+        let arms = props.iter().map(|p| {
+            let name = &p.name;
+            let field = ident(&p.field);
+            let stmt = match form_kind(&p.model) {
+                FormKind::Simple => quote! {
+                    body.#field = parse_value(#name, Some(&read_field(&mut field).await?))?.unwrap_or_default();
+                },
+                FormKind::Array => quote! {
+                    body.#field.push(required(#name, parse_value(#name, Some(&read_field(&mut field).await?))?)?);
+                },
+                FormKind::Complex => quote! {
+                    body.#field = serde_json::from_str(&read_field(&mut field).await?)?;
+                },
+                FormKind::Stream => quote! {
+                    body.#field = Body::from_multipart(field, multipart);
+                    break;
+                },
+            };
+
+            quote! {
+                #name => {
+                    parts.insert(#name, field.headers().clone());
+                    #stmt
+                }
+            }
+        });
+        let required: Vec<_> = props
+            .iter()
+            .filter(|p| p.required)
+            .map(|p| &p.name)
+            .collect();
+        let required = (!required.is_empty()).then(|| {
+            quote! {
+                for name in [#(#required),*] {
+                    if !parts.contains_key(name) {
+                        return Err(format!("missing required part {}", name).into());
+                    }
+                }
+            }
+        });
+
+        quote! {
+            let mut body = #body::default();
+            let mut parts: HashMap<&str, actix_web::http::header::HeaderMap> = HashMap::new();
+            let mut multipart = actix_multipart::Multipart::new(req.headers(), payload);
+            while let Some(field) = multipart.next().await {
+                // Fields are only borrowed mutably when they are read as text.
+                #[allow(unused_mut)]
+                let mut field = field.map_err(|e| e.to_string())?;
+                let name = field.name().unwrap_or_default().to_string();
+                match name.as_str() {
+                    #(#arms)*
+                    _ => {}
+                }
+            }
+            #required
+        }
+    }
+
+    /// Generate the multipart encoder, headers of parts are sent from the
+    /// fields of part headers.
+    fn generate_encode_multipart(&self, op: &Operation) -> TokenStream {
+        let fields = input_fields(op);
+        let stmts = self.body_props(op).into_iter().map(|p| {
+            let name = &p.name;
+            let ct = &p.encoding.content_type;
+            let field = ident(&p.field);
+            let fields: Vec<_> = fields
+                .iter()
+                .filter(|f| f.location == Location::Part(name))
+                .collect();
+            let part = |value: TokenStream| {
+                if fields.is_empty() {
+                    quote!(#value.mime_str(#ct)?)
+                } else {
+                    quote!(#value.mime_str(#ct)?.headers(headers.clone()))
+                }
+            };
+            let stmt = match form_kind(&p.model) {
+                FormKind::Simple => {
+                    let part = part(quote!(Part::text(self.body.#field.to_string())));
+                    quote!(form = form.part(#name, #part);)
+                }
+                FormKind::Array => {
+                    let part = part(quote!(Part::text(v.to_string())));
+                    quote! {
+                        for v in &self.body.#field {
+                            form = form.part(#name, #part);
+                        }
+                    }
+                }
+                FormKind::Complex => {
+                    let part = part(quote!(Part::text(serde_json::to_string(&self.body.#field)?)));
+                    quote!(form = form.part(#name, #part);)
+                }
+                FormKind::Stream => {
+                    let part = part(quote!(stream_part(self.body.#field).file_name(#name)));
+                    quote!(form = form.part(#name, #part);)
+                }
+            };
+            if fields.is_empty() {
+                return stmt;
+            }
+            let headers = fields.iter().map(|f| {
+                format_param(f, |name, value| {
+                    quote! {
+                        headers.insert(reqwest::header::HeaderName::from_bytes(#name.as_bytes())?, #value.parse()?);
+                    }
+                })
+            });
+
+            quote! {
+                let mut headers = reqwest::header::HeaderMap::new();
+                #(#headers)*
+                #stmt
+            }
+        });
+
+        quote! {
+            let mut form = reqwest::multipart::Form::new();
+            #(#stmts)*
+            req = req.multipart(form);
+        }
+    }
+
+    /// Generate the client method of an operation, `client` is the
+    /// expression of the top level client.
+    fn generate_send(&self, op: &Operation, client: TokenStream) -> TokenStream {
+        let name = ident(&fn_name(op));
+        let input = ident(&input_name(op));
+        let output = ident(&output_name(op));
+        let doc = doc::attrs(&operation_doc(op));
+        let deprecated = deprecated(is_deprecated(op), &format!("operation {}", op.id));
+
+        let send = if op.security.is_empty() {
+            quote!(let resp = input.into_request(&#client.http, &#client.endpoint)?.send().await?;)
+        } else {
+            let security = security_requirement(op);
+            quote! {
+                let req = input.into_request(&#client.http, &#client.endpoint)?;
+                let resp = #client.authorize(req, #security).await?.send().await?;
+            }
+        };
+
+        quote! {
+            #doc
+            #deprecated
+            pub async fn #name(&self, input: #input) -> Result<#output, BoxError> {
+                #send
+                #output::from_response(resp).await
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum BodyFormat {
+    Json,
+    Stream,
+    Form,
+    Multipart,
+}
+
+fn input_format(op: &Operation) -> Option<BodyFormat> {
+    let body = op.input.body.as_ref()?;
+    if body.kind == ModelKind::Iterator {
+        return Some(BodyFormat::Stream);
+    }
+
+    match op.input.content_type.as_deref() {
+        Some("application/x-www-form-urlencoded") => Some(BodyFormat::Form),
+        Some(v) if v.starts_with("multipart/") => Some(BodyFormat::Multipart),
+        _ => Some(BodyFormat::Json),
+    }
+}
+
+/// Property of a form or multipart body.
+struct BodyProp {
+    name: String,
+    field: String,
+    model: Model,
+    encoding: Encoding,
+    required: bool,
+}
+
+/// Returns the delimiter of arrays which are not exploded in forms or
+/// parameters, styles are checked by `Generator::check`.
+fn delimiter(style: &str) -> char {
+    match style {
+        "spaceDelimited" => ' ',
+        "pipeDelimited" => '|',
+        _ => ',',
+    }
+}
+
+/// How a property is carried in a form or multipart body.
+enum FormKind {
+    Simple,
+    Array,
+    Complex,
+    Stream,
+}
+
+fn form_kind(prop: &Model) -> FormKind {
+    match prop.kind {
+        ModelKind::Iterator => FormKind::Stream,
+        ModelKind::Array if prop.element.as_ref().unwrap().kind.is_simple() => FormKind::Array,
+        kind if kind.is_simple() => FormKind::Simple,
+        _ => FormKind::Complex,
+    }
+}
+
+/// Returns `#[deprecated]` with a note like `operation listPets is
+/// deprecated` if `enabled`.
+fn deprecated(enabled: bool, what: &str) -> Option<TokenStream> {
+    let note = format!("{} is deprecated", what);
+
+    enabled.then(|| quote!(#[deprecated(note = #note)]))
+}
+
+/// Returns the doc of an operation's methods, with its summary,
+/// description, method and path, and examples of bodies.
+fn operation_doc(op: &Operation) -> String {
+    let mut sections: Vec<String> = op
+        .summary
+        .iter()
+        .chain(op.description.iter())
+        .map(|v| doc::rustdoc(v))
+        .collect();
+    sections.push(format!(
+        "`{} {}`",
+        method_name(op.method).to_uppercase(),
+        route_path(op)
+    ));
+
+    let examples = [
+        ("Request", &op.input.example, &op.input.content_type),
+        ("Response", &op.output.example, &op.output.content_type),
+    ];
+    for (what, example, content_type) in examples.iter() {
+        if let Some(v) = example {
+            sections.push(format!(
+                "{} example:\n\n{}",
+                what,
+                example_block(v, content_type.as_deref())
+            ));
+        }
+    }
+
+    sections.retain(|v| !v.is_empty());
+    sections.join("\n\n")
+}
+
+/// Returns the code block of an example, examples of non JSON bodies like
+/// XML are strings which are kept as is.
+fn example_block(example: &Value, content_type: Option<&str>) -> String {
+    let json = content_type.is_none_or(|v| v.contains("json"));
+    match example {
+        Value::String(v) if !json => format!("```text\n{}\n```", v.trim_end()),
+        v => format!("```json\n{}\n```", serde_json::to_string_pretty(v).unwrap()),
+    }
+}
+
+/// Returns the security requirement of an operation as a `Security`, like
+/// `&[&[("petstore_auth", &["write:pets"])]]`.
+///
+/// Anonymous alternatives are placed last, so that credentials are still
+/// applied and authenticated if they are available.
+fn security_requirement(op: &Operation) -> TokenStream {
+    let mut security: Vec<_> = op.security.iter().collect();
+    security.sort_by_key(|v| v.is_empty());
+    let alternatives = security.into_iter().map(|v| {
+        let schemes = v
+            .iter()
+            .map(|(name, scopes)| quote!((#name, &[#(#scopes),*])));
+        quote!(&[#(#schemes),*])
+    });
+
+    quote!(&[#(#alternatives),*])
+}
+
+/// Returns the lowercase scheme in the `Authorization` header of http,
+/// `oauth2` and `openIdConnect` schemes.
+fn http_scheme(scheme: &SecurityScheme) -> &str {
+    match scheme.kind {
+        SecurityKind::Http => scheme.scheme.as_deref().unwrap_or("bearer"),
+        _ => "bearer",
+    }
+}
+
+fn is_deprecated(op: &Operation) -> bool {
+    op.annotation.as_ref().is_some_and(|v| v.deprecated)
+}
+
+fn is_inline_struct(m: &Model) -> bool {
+    m.kind == ModelKind::Struct && m.properties.is_some() && m.type_path().is_none()
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Location<'a> {
+    Path,
+    Query,
+    Header,
+    Cookie,
+    /// Header of the part of a property in multipart body.
+    Part(&'a str),
+}
+
+/// Field is a parameter with its location and the field name used in the
+/// generated struct.
+struct Field<'a> {
+    name: String,
+    location: Location<'a>,
+    param: &'a Parameter,
+}
+
+fn input_fields(op: &Operation) -> Vec<Field<'_>> {
+    let params = op
+        .input
+        .path
+        .iter()
+        .map(|p| (Location::Path, p))
+        .chain(op.input.query.iter().map(|p| (Location::Query, p)))
+        .chain(op.input.header.iter().map(|p| (Location::Header, p)))
+        .chain(op.input.cookie.iter().map(|p| (Location::Cookie, p)));
+    // Encoding headers are ignored unless the body is multipart.
+    let parts = op
+        .input
+        .encoding
+        .iter()
+        .filter(|_| input_format(op) == Some(BodyFormat::Multipart))
+        .flat_map(|(prop, e)| e.headers.iter().map(move |p| (Location::Part(prop), p)));
+
+    fields(params.chain(parts))
+}
+
+fn output_fields(op: &Operation) -> Vec<Field<'_>> {
+    fields(op.output.header.iter().map(|p| (Location::Header, p)))
+}
+
+/// Build fields with unique names. Different parameters could have the same
+/// field name, like `max-keys` and `MaxKeys` in S3; `body` is reserved.
+fn fields<'a>(params: impl Iterator<Item = (Location<'a>, &'a Parameter)>) -> Vec<Field<'a>> {
+    let mut names = vec!["body".to_string()];
+    let mut fields = Vec::new();
+
+    for (location, param) in params {
+        let base = match location {
+            // Headers of parts are prefixed by their properties, like
+            // `file_content_id`.
+            Location::Part(prop) if param.model.display().is_none() => {
+                to_snake_case(&format!("{} {}", prop, param.name))
+            }
+            _ => field_name(&param.name, &param.model),
+        };
+        let mut name = base.clone();
+        let mut idx = 1;
+        while names.contains(&name) {
+            idx += 1;
+            name = format!("{}_{}", base, idx);
+        }
+        names.push(name.clone());
+
+        fields.push(Field {
+            name,
+            location,
+            param,
+        });
+    }
+
+    fields
+}
+
+/// Returns whether the field is an array in query which is exploded, like
+/// `ids=1&ids=2`.
+fn exploded(f: &Field) -> bool {
+    f.location == Location::Query && f.param.explode && !f.param.model.kind.is_simple()
+}
+
+/// Generate the field initializer which parses a parameter from `value`,
+/// an expression of type `Option<&str>`, or `Vec<&str>` of all values if
+/// the parameter is exploded.
+fn parse_param(f: &Field, value: TokenStream) -> TokenStream {
+    let name = &f.param.name;
+    let delimiter = delimiter(&f.param.style);
+    let parse = if f.param.model.kind.is_simple() {
+        quote!(parse_value(#name, #value)?)
+    } else if exploded(f) {
+        quote!(parse_exploded(#name, #value)?)
+    } else {
+        quote!(parse_values(#name, #value, #delimiter)?)
+    };
+    let field = ident(&f.name);
+
+    if f.param.mandatory {
+        quote!(#field: required(#name, #parse)?,)
+    } else {
+        quote!(#field: #parse,)
+    }
+}
+
+/// Generate the statement which formats a parameter with `stmt`, which
+/// takes the parameter's name and the formatted value.
+fn format_param(f: &Field, stmt: impl Fn(&str, TokenStream) -> TokenStream) -> TokenStream {
+    let delimiter = delimiter(&f.param.style);
+    let stmt = if f.param.model.kind.is_simple() {
+        stmt(&f.param.name, quote!(v.to_string()))
+    } else if exploded(f) {
+        let stmt = stmt(&f.param.name, quote!(v.to_string()));
+        quote! {
+            for v in v.iter() {
+                #stmt
+            }
+        }
+    } else {
+        stmt(&f.param.name, quote!(format_values(&v, #delimiter)))
+    };
+    let field = ident(&f.name);
+
+    if f.param.mandatory {
+        quote! {
+            let v = self.#field;
+            #stmt
+        }
+    } else {
+        quote! {
+            if let Some(v) = self.#field {
+                #stmt
+            }
+        }
+    }
+}
+
+/// Returns the field name of a property or parameter, which could be
+/// overridden by `x-qi-name`.
+fn field_name(name: &str, m: &Model) -> String {
+    match m.display() {
+        Some(v) => v.to_string(),
+        None => to_snake_case(name),
+    }
+}
+
+fn op_name(op: &Operation) -> &str {
+    op.annotation
+        .as_ref()
+        .and_then(|v| v.display.as_deref())
+        .unwrap_or(&op.id)
+}
+
+/// Returns the module name of the operation's first tag, operations with
+/// tags which have the same module name are grouped together.
+fn group_name(op: &Operation) -> Option<String> {
+    op.tags.iter().flatten().next().map(|v| to_snake_case(v))
+}
+
+fn fn_name(op: &Operation) -> String {
+    to_snake_case(op_name(op))
+}
+
+fn handler_name(op: &Operation) -> Ident {
+    format_ident!("handle_{}", ident(&fn_name(op)))
+}
+
+fn input_name(op: &Operation) -> String {
+    to_pascal_case(op_name(op)) + "Input"
+}
+
+fn output_name(op: &Operation) -> String {
+    to_pascal_case(op_name(op)) + "Output"
+}
+
+fn error_name(op: &Operation) -> String {
+    to_pascal_case(op_name(op)) + "Error"
+}
+
+/// Returns the variant of an error response in the operation's error, like
+/// `Status404`, `Status4XX` for ranges and `Default`.
+fn error_variant(e: &Output) -> String {
+    match e.status_code {
+        0 => "Default".to_string(),
+        v if e.range => format!("Status{}XX", v / 100),
+        v => format!("Status{}", v),
+    }
+}
+
+/// Returns error responses in the order of matching, which are exact status
+/// codes, ranges and then `default`.
+fn sorted_errors(op: &Operation) -> Vec<&Output> {
+    let mut errors: Vec<&Output> = op.errors.iter().collect();
+    errors.sort_by_key(|e| (e.status_code == 0, e.range, e.status_code));
+
+    errors
+}
+
+fn method_name(method: Method) -> &'static str {
+    match method {
+        Method::Get => "get",
+        Method::Put => "put",
+        Method::Post => "post",
+        Method::Delete => "delete",
+        Method::Head => "head",
+        Method::Patch => "patch",
+        Method::Options => "options",
+        Method::Trace => "trace",
+    }
+}
+
+/// Specs like S3 use fragments to distinguish operations sharing the same
+/// path and method, e.g. `/{Bucket}#tagging`. The fragment is not a part of
+/// the path to route.
+fn route_path(op: &Operation) -> String {
+    op.uri.split('#').next().unwrap().to_string()
+}
+
+/// Keys in the uri fragment which must exist in query or headers.
+///
+/// Keys end with `!` are markers like `deprecated!` instead of real keys.
+fn route_guards(op: &Operation) -> Vec<String> {
+    match op.uri.split_once('#') {
+        None => vec![],
+        Some((_, fragment)) => fragment
+            .split('&')
+            .filter(|v| !v.is_empty() && !v.ends_with('!'))
+            .map(|v| v.to_string())
+            .collect(),
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "try", "typeof",
+    "unsized", "virtual", "yield",
+];
+
+/// Keywords which could not be raw identifiers, they are escaped with a
+/// trailing underscore like `self_` instead.
+const PATH_KEYWORDS: &[&str] = &["crate", "self", "super", "Self"];
+
+/// Split name into lowercase words by non-alphanumeric characters and case
+/// changes, `ETag` will be split into `e` and `tag`.
+fn split_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let chars: Vec<char> = name.chars().collect();
+
+    for (i, c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !word.is_empty() {
+                words.push(word.clone());
+                word.clear();
+            }
+            continue;
+        }
+
+        if c.is_ascii_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_lower = chars
+                .get(i + 1)
+                .map(|v| v.is_ascii_lowercase())
+                .unwrap_or(false);
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_lower)
+            {
+                words.push(word.clone());
+                word.clear();
+            }
+        }
+        word.push(c.to_ascii_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut s = split_words(name).join("_");
+
+    if s.is_empty() || s.starts_with(|c: char| c.is_ascii_digit()) {
+        s.insert(0, '_');
+    }
+    if KEYWORDS.contains(&s.as_str()) {
+        s.insert_str(0, "r#");
+    }
+    if PATH_KEYWORDS.contains(&s.as_str()) {
+        s.push('_');
+    }
+
+    s
+}
+
+fn to_pascal_case(name: &str) -> String {
+    let mut s: String = split_words(name)
+        .iter()
+        .map(|w| {
+            let mut cs = w.chars();
+            match cs.next() {
+                Some(c) => c.to_ascii_uppercase().to_string() + cs.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect();
+
+    if s.is_empty() || s.starts_with(|c: char| c.is_ascii_digit()) {
+        s.insert(0, '_');
+    }
+    if PATH_KEYWORDS.contains(&s.as_str()) {
+        s.push('_');
+    }
+
+    s
+}
+
+fn header() -> TokenStream {
+    quote! {
+        // Imports are sorted since there are no blank lines to separate
+        // groups in tokens.
+        #[allow(unused_imports)]
+        use actix_web::http::StatusCode;
+        #[allow(unused_imports)]
+        use actix_web::{guard, web, HttpRequest, HttpResponse};
+        use bytes::Bytes;
+        use futures::{SinkExt, Stream, StreamExt, TryStreamExt};
+        #[allow(unused_imports)]
+        use serde::{Deserialize, Serialize};
+        #[allow(unused_imports)]
+        use std::collections::HashMap;
+        use std::pin::Pin;
+        use std::str::FromStr;
+        use std::task::{Context, Poll};
+
+        pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+        #[allow(dead_code)]
+        fn has_key(head: &actix_web::dev::RequestHead, key: &str) -> bool {
+            head.headers().contains_key(key)
+                || head
+                    .uri
+                    .query()
+                    .map(|q| q.split('&').any(|kv| kv.split('=').next() == Some(key)))
+                    .unwrap_or(false)
+        }
+
+        #[allow(dead_code)]
+        fn required<T>(name: &str, value: Option<T>) -> Result<T, BoxError> {
+            value.ok_or_else(|| format!("missing required parameter {}", name).into())
+        }
+
+        #[allow(dead_code)]
+        fn parse_value<T>(name: &str, value: Option<&str>) -> Result<Option<T>, BoxError>
+        where
+            T: FromStr,
+            T::Err: std::fmt::Display,
+        {
+            match value {
+                None => Ok(None),
+                Some(v) => v
+                    .parse()
+                    .map(Some)
+                    .map_err(|e| format!("invalid parameter {}: {}", name, e).into()),
+            }
+        }
+
+        /// Parse values joined by the delimiter, which is `,` except in forms.
+        #[allow(dead_code)]
+        fn parse_values<T>(name: &str, value: Option<&str>, delimiter: char) -> Result<Option<Vec<T>>, BoxError>
+        where
+            T: FromStr,
+            T::Err: std::fmt::Display,
+        {
+            match value {
+                None => Ok(None),
+                Some(v) => v
+                    .split(delimiter)
+                    .map(|v| v.parse().map_err(|e| format!("invalid parameter {}: {}", name, e).into()))
+                    .collect::<Result<Vec<T>, BoxError>>()
+                    .map(Some),
+            }
+        }
+
+        #[allow(dead_code)]
+        fn format_values<T: ToString>(values: &[T], delimiter: char) -> String {
+            values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(&delimiter.to_string())
+        }
+
+        /// Percent-encode a path segment, only unreserved characters are
+        /// kept.
+        #[allow(dead_code)]
+        fn encode_path(value: String) -> String {
+            let mut s = String::with_capacity(value.len());
+            for b in value.bytes() {
+                match b {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                        s.push(b as char)
+                    }
+                    _ => s.push_str(&format!("%{:02X}", b)),
+                }
+            }
+            s
+        }
+
+        /// Decode a path segment, in which actix-web keeps `%`, `/` and `+`
+        /// percent-encoded.
+        #[allow(dead_code)]
+        fn decode_path(value: &str) -> Result<String, BoxError> {
+            let mut buf = Vec::with_capacity(value.len());
+            let mut bytes = value.bytes();
+            while let Some(b) = bytes.next() {
+                if b != b'%' {
+                    buf.push(b);
+                    continue;
+                }
+                let hex: Vec<u8> = bytes.by_ref().take(2).collect();
+                let v = std::str::from_utf8(&hex)
+                    .ok()
+                    .filter(|v| v.len() == 2)
+                    .and_then(|v| u8::from_str_radix(v, 16).ok())
+                    .ok_or_else(|| format!("invalid percent-encoding in path {}", value))?;
+                buf.push(v);
+            }
+            Ok(String::from_utf8(buf)?)
+        }
+
+        #[allow(dead_code)]
+        async fn read_payload(mut payload: web::Payload) -> Result<Bytes, BoxError> {
+            let mut buf = bytes::BytesMut::new();
+            while let Some(chunk) = payload.next().await {
+                buf.extend_from_slice(&chunk?);
+            }
+            Ok(buf.freeze())
+        }
+    }
+}
+
+/// Helpers of form encoded values, which are used by queries and form
+/// bodies.
+fn form() -> TokenStream {
+    quote! {
+        #[allow(dead_code)]
+        fn form_value<'a>(form: &'a [(String, String)], name: &str) -> Option<&'a str> {
+            form.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+        }
+
+        #[allow(dead_code)]
+        fn form_values<'a>(form: &'a [(String, String)], name: &str) -> Vec<&'a str> {
+            form.iter().filter(|(k, _)| k == name).map(|(_, v)| v.as_str()).collect()
+        }
+
+        #[allow(dead_code)]
+        fn parse_all<T>(name: &str, values: Vec<&str>) -> Result<Vec<T>, BoxError>
+        where
+            T: FromStr,
+            T::Err: std::fmt::Display,
+        {
+            values
+                .into_iter()
+                .map(|v| v.parse().map_err(|e| format!("invalid parameter {}: {}", name, e).into()))
+                .collect()
+        }
+
+        /// Parse all values of an exploded parameter, which is missing if
+        /// there are no values.
+        #[allow(dead_code)]
+        fn parse_exploded<T>(name: &str, values: Vec<&str>) -> Result<Option<Vec<T>>, BoxError>
+        where
+            T: FromStr,
+            T::Err: std::fmt::Display,
+        {
+            if values.is_empty() {
+                return Ok(None);
+            }
+            parse_all(name, values).map(Some)
+        }
+    }
+}
+
+fn multipart() -> TokenStream {
+    quote! {
+        use reqwest::multipart::Part;
+
+        #[allow(dead_code)]
+        async fn read_field(field: &mut actix_multipart::Field) -> Result<String, BoxError> {
+            let mut buf = Vec::new();
+            while let Some(chunk) = field.next().await {
+                buf.extend_from_slice(&chunk.map_err(|e| e.to_string())?);
+            }
+            Ok(String::from_utf8(buf)?)
+        }
+
+        #[allow(dead_code)]
+        fn stream_part(body: Body) -> Part {
+            match body.len() {
+                Some(size) => Part::stream_with_length(reqwest::Body::wrap_stream(body), size),
+                None => Part::stream(reqwest::Body::wrap_stream(body)),
+            }
+        }
+
+        impl Body {
+            /// Forward a multipart field into a bounded channel, parts after this
+            /// field are reported as an error at the end of the stream since they
+            /// could not be read anymore.
+            #[allow(dead_code)]
+            fn from_multipart(mut field: actix_multipart::Field, mut multipart: actix_multipart::Multipart) -> Self {
+                let (mut tx, rx) = futures::channel::mpsc::channel(16);
+                actix_web::rt::spawn(async move {
+                    while let Some(chunk) = field.next().await {
+                        let chunk = chunk.map_err(|e| BoxError::from(e.to_string()));
+                        if tx.send(chunk).await.is_err() {
+                            return;
+                        }
+                    }
+                    // Multipart waits until the previous field is dropped.
+                    let name = field.name().unwrap_or_default().to_string();
+                    drop(field);
+                    if let Some(next) = multipart.next().await {
+                        let next = next.map(|v| v.name().unwrap_or_default().to_string());
+                        let e = match next {
+                            Ok(v) => format!("unexpected part {} after streaming part {}", v, name),
+                            Err(e) => e.to_string(),
+                        };
+                        let _ = tx.send(Err(BoxError::from(e))).await;
+                    }
+                });
+                Body::new(rx)
+            }
+        }
+    }
+}
+
+fn body() -> TokenStream {
+    quote! {
+        /// Body is a stream of bytes with an optional known length.
+        pub struct Body {
+            inner: Pin<Box<dyn Stream<Item = Result<Bytes, BoxError>> + Send>>,
+            size: Option<u64>,
+        }
+
+        impl Body {
+            pub fn new<S>(stream: S) -> Self
+            where
+                S: Stream<Item = Result<Bytes, BoxError>> + Send + 'static,
+            {
+                Body {
+                    inner: Box::pin(stream),
+                    size: None,
+                }
+            }
+
+            pub fn with_length<S>(stream: S, size: u64) -> Self
+            where
+                S: Stream<Item = Result<Bytes, BoxError>> + Send + 'static,
+            {
+                Body {
+                    inner: Box::pin(stream),
+                    size: Some(size),
+                }
+            }
+
+            pub fn empty() -> Self {
+                Body::with_length(futures::stream::empty(), 0)
+            }
+
+            /// Returns the length of this body if known.
+            pub fn len(&self) -> Option<u64> {
+                self.size
+            }
+
+            /// Forward actix's payload, which is not `Send`, into a bounded channel.
+            #[allow(dead_code)]
+            fn from_payload(req: &HttpRequest, mut payload: web::Payload) -> Self {
+                let size = req
+                    .headers()
+                    .get("content-length")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse().ok());
+                let (mut tx, rx) = futures::channel::mpsc::channel(16);
+                actix_web::rt::spawn(async move {
+                    while let Some(chunk) = payload.next().await {
+                        let chunk = chunk.map_err(|e| BoxError::from(e.to_string()));
+                        if tx.send(chunk).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+                Body { inner: Box::pin(rx), size }
+            }
+
+            #[allow(dead_code)]
+            fn from_response(resp: reqwest::Response) -> Self {
+                let size = resp.content_length();
+                Body {
+                    inner: Box::pin(resp.bytes_stream().map_err(BoxError::from)),
+                    size,
+                }
+            }
+        }
+
+        impl Default for Body {
+            fn default() -> Self {
+                Body::empty()
+            }
+        }
+
+        impl std::fmt::Debug for Body {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct("Body").field("size", &self.size).finish()
+            }
+        }
+
+        impl From<Bytes> for Body {
+            fn from(v: Bytes) -> Self {
+                let size = v.len() as u64;
+                Body::with_length(futures::stream::once(async move { Ok(v) }), size)
+            }
+        }
+
+        impl From<Vec<u8>> for Body {
+            fn from(v: Vec<u8>) -> Self {
+                Body::from(Bytes::from(v))
+            }
+        }
+
+        impl Stream for Body {
+            type Item = Result<Bytes, BoxError>;
+
+            fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                self.inner.as_mut().poll_next(cx)
+            }
+        }
+    }
+}
+
+fn client(security: bool) -> TokenStream {
+    let (field, init) = if security {
+        (
+            Some(quote!(credentials: Option<std::sync::Arc<dyn CredentialProvider>>,)),
+            Some(quote!(credentials: None,)),
+        )
+    } else {
+        (None, None)
+    };
+
+    quote! {
+        pub struct Client {
+            http: reqwest::Client,
+            endpoint: String,
+            #field
+        }
+
+        impl Client {
+            pub fn new(endpoint: impl Into<String>) -> Self {
+                Client::with_http_client(reqwest::Client::new(), endpoint)
+            }
+
+            pub fn with_http_client(http: reqwest::Client, endpoint: impl Into<String>) -> Self {
+                Client {
+                    http,
+                    endpoint: endpoint.into().trim_end_matches('/').to_string(),
+                    #init
+                }
+            }
+        }
+    }
+}
+
+fn security() -> TokenStream {
+    quote! {
+        /// Security requirement of an operation, which is satisfied by any of
+        /// its alternatives, and an alternative by all of its schemes with the
+        /// scopes.
+        pub type Security = &'static [&'static [(&'static str, &'static [&'static str])]];
+
+        /// Credential of a security scheme.
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum Credential {
+            /// Key of an `apiKey` scheme.
+            ApiKey(String),
+            /// Username and password of the http `basic` scheme.
+            Basic {
+                username: String,
+                password: Option<String>,
+            },
+            /// Token of other http schemes like `bearer`, and `oauth2` and
+            /// `openIdConnect` schemes, which is sent in the `Authorization`
+            /// header.
+            Bearer(String),
+        }
     }
-    fn generate_parse_request(&self) {}
-    fn generate_format_response(&self) {}
-    fn generate_handle(&self) {}
 }
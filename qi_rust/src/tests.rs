@@ -0,0 +1,1134 @@
+use super::*;
+use std::error::Error;
+
+use qi_ir::{Model, ModelKind, Service};
+use std::path::{Path, PathBuf};
+
+use crate::load::{parse_value, sniff_format};
+
+fn load(path: &str) -> Result<Service, Box<dyn Error>> {
+    let spec = super::load(Path::new(path), None)?;
+
+    Ok(Service::new(spec)?)
+}
+
+#[test]
+fn generate_s3() -> Result<(), Box<dyn Error>> {
+    let srv = load("tests/s3.yaml")?;
+
+    let g = ActixWebGenerator::new(Generator::new(srv));
+    let code = g.generate()?;
+
+    assert!(code.contains("pub struct Body {"));
+    assert!(code.contains("pub trait Server: 'static {"));
+    assert!(code.contains("pub struct Client {"));
+    // Path parameters like object keys are percent-encoded.
+    assert!(code.contains("encode_path(self.bucket.to_string())"));
+    assert!(code.contains(".map(decode_path)"));
+
+    Ok(())
+}
+
+#[test]
+fn form_and_multipart_encoding() -> Result<(), Box<dyn Error>> {
+    let srv = load("tests/upload.yaml")?;
+
+    let upload = srv
+        .operations
+        .iter()
+        .find(|v| v.id == "uploadFile")
+        .unwrap();
+    assert_eq!(
+        Some("multipart/form-data"),
+        upload.input.content_type.as_deref()
+    );
+    let file = &upload.input.encoding["file"];
+    assert_eq!("image/png", file.content_type);
+    assert_eq!("X-Rate-Limit-Limit", file.headers[0].name);
+    assert_eq!(
+        "application/json",
+        upload.input.encoding["meta"].content_type
+    );
+
+    let login = srv.operations.iter().find(|v| v.id == "login").unwrap();
+    assert!(login.input.encoding["scopes"].explode);
+    assert!(!login.input.encoding["ids"].explode);
+
+    let code = ActixWebGenerator::new(Generator::new(srv)).generate()?;
+    assert!(code.contains("req = req.multipart(form);"));
+    assert!(code.contains("req = req.form(&form);"));
+
+    // Headers of parts are fields of the input.
+    assert!(code.contains("pub file_x_rate_limit_limit: Option<isize>,"));
+    assert!(code.contains(
+        ".get(\"file\")\n                    .and_then(|v| v.get(\"X-Rate-Limit-Limit\"))"
+    ));
+    assert!(code.contains(".headers(headers.clone())"));
+    assert!(code.contains("unexpected part {} after streaming part {}"));
+    // Required properties are checked.
+    assert!(code.contains("for name in [\"name\"] {"));
+    assert!(code.contains(
+        "user: required(\"user\", parse_value(\"user\", form_value(&form, \"user\"))?)?,"
+    ));
+    // Arrays which are not exploded are joined by the delimiter of style.
+    assert!(code.contains("parse_values(\"ids\", form_value(&form, \"ids\"), ',')?"));
+    assert!(code.contains("format_values(&self.body.roles, '|')"));
+
+    Ok(())
+}
+
+#[test]
+fn reject_form_encoding() -> Result<(), Box<dyn Error>> {
+    let generate = |content_type: &str, properties: &str, encoding: &str| {
+        let content = format!(
+            r##"
+openapi: 3.0.3
+info:
+  title: Form
+  version: 1.0.0
+paths:
+  /files:
+    post:
+      operationId: upload
+      requestBody:
+        content:
+          {}:
+            schema:
+              type: object
+              properties: {}
+            encoding: {}
+      responses:
+        '204':
+          description: Uploaded
+"##,
+            content_type, properties, encoding
+        );
+        let srv = Service::new(qi_openapi::v3::from_yaml_reader(content.as_bytes())?)?;
+        ActixWebGenerator::new(Generator::new(srv)).generate()
+    };
+    let binary = "{type: string, format: binary}";
+
+    let err = generate(
+        "application/x-www-form-urlencoded",
+        &format!("{{file: {}}}", binary),
+        "{}",
+    )
+    .unwrap_err();
+    assert_eq!(
+        "binary property file could not be sent in form of operation upload",
+        err.to_string()
+    );
+
+    let err = generate(
+        "application/x-www-form-urlencoded",
+        "{filter: {type: object}}",
+        "{filter: {style: deepObject}}",
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("style deepObject of property filter"));
+
+    let err = generate(
+        "multipart/form-data",
+        &format!("{{a: {}, b: {}}}", binary, binary),
+        "{}",
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("binary property b in multipart body"));
+
+    // Styles are ignored in multipart bodies.
+    generate(
+        "multipart/form-data",
+        "{filter: {type: object}}",
+        "{filter: {style: deepObject}}",
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn parameter_styles() -> Result<(), Box<dyn Error>> {
+    let generate = |parameter: &str| {
+        let content = format!(
+            r##"
+openapi: 3.0.3
+info:
+  title: Params
+  version: 1.0.0
+paths:
+  /items/{{id}}:
+    get:
+      operationId: list
+      parameters:
+        - {{name: id, in: path, required: true, schema: {{type: string}}}}
+        - {}
+      responses:
+        '204':
+          description: Listed
+"##,
+            parameter
+        );
+        let srv = Service::new(qi_openapi::v3::from_yaml_reader(content.as_bytes())?)?;
+        ActixWebGenerator::new(Generator::new(srv)).generate()
+    };
+    let array = "schema: {type: array, items: {type: integer}}";
+
+    let code = generate(&format!("{{name: ids, in: query, {}}}", array))?;
+    assert!(code.contains("ids: parse_exploded(\"ids\", form_values(&query, \"ids\"))?,"));
+    assert!(
+        code.contains("for v in v.iter() {\n                query.push((\"ids\", v.to_string()));")
+    );
+
+    let code = generate(&format!(
+        "{{name: ids, in: query, style: spaceDelimited, {}}}",
+        array
+    ))?;
+    assert!(code.contains("parse_values(\"ids\", form_value(&query, \"ids\"), ' ')?"));
+    assert!(code.contains("query.push((\"ids\", format_values(&v, ' ')));"));
+
+    let err = generate("{name: filter, in: query, style: deepObject, schema: {type: object}}")
+        .unwrap_err();
+    assert_eq!(
+        "style deepObject of parameter filter in operation list is not supported",
+        err.to_string()
+    );
+    let err = generate(&format!(
+        "{{name: ids, in: header, style: form, {}}}",
+        array
+    ))
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("exploded style form of parameter ids"));
+    let err = generate(&format!("{{name: ids, in: cookie, {}}}", array)).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("exploded style form of parameter ids"));
+
+    Ok(())
+}
+
+#[test]
+fn resolve_components() -> Result<(), Box<dyn Error>> {
+    let srv = load("tests/components.yaml")?;
+    let op = &srv.operations[0];
+
+    // Path item's parameters are overridden by operation's.
+    assert_eq!("id", op.input.path[0].name);
+    assert_eq!(ModelKind::Int64, op.input.path[0].model.kind);
+    assert_eq!(1, op.input.header.len());
+    assert!(op.input.header[0].mandatory);
+
+    assert_eq!(
+        Some("Item"),
+        op.input.body.as_ref().unwrap().name.as_deref()
+    );
+
+    assert_eq!(200, op.output.status_code);
+    assert_eq!("X-Rate-Limit", op.output.header[0].name);
+    assert_eq!(ModelKind::Int32, op.output.header[0].model.kind);
+    assert!(op.output.header[0].mandatory);
+
+    let errors: Vec<usize> = op.errors.iter().map(|v| v.status_code).collect();
+    assert_eq!(vec![404, 0], errors);
+    for e in op.errors.iter() {
+        assert_eq!(Some("Error"), e.body.as_ref().unwrap().name.as_deref());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn typed_errors() -> Result<(), Box<dyn Error>> {
+    let srv = load("tests/components.yaml")?;
+    let code = ActixWebGenerator::new(Generator::new(srv)).generate()?;
+
+    assert!(code.contains("pub enum PutItemError {"));
+    assert!(code.contains("Status404(models::Error),"));
+    assert!(code.contains("Default { status: u16, body: models::Error },"));
+    // Clients return errors of declared responses, which are sent by
+    // servers if handlers return them.
+    assert!(code.contains("404 => PutItemError::Status404(resp.json().await?),"));
+    assert!(code.contains("PutItemError::Default {\n"));
+    assert!(code.contains("match e.downcast::<PutItemError>() {"));
+    assert!(code.contains("Self::Status404(body) => resp.json(body),"));
+
+    Ok(())
+}
+
+#[test]
+fn escape_keywords() -> Result<(), Box<dyn Error>> {
+    let content = r##"
+openapi: 3.0.3
+info:
+  title: Keywords
+  version: 1.0.0
+paths: {}
+components:
+  schemas:
+    Self:
+      type: object
+      properties:
+        crate:
+          type: string
+        self:
+          type: string
+        super:
+          type: string
+        type:
+          type: string
+"##;
+    let srv = Service::new(qi_openapi::v3::from_yaml_reader(content.as_bytes())?)?;
+    let code = ActixWebGenerator::new(Generator::new(srv)).generate()?;
+
+    assert!(code.contains("pub struct Self_ {"));
+    for name in ["crate", "self", "super"] {
+        assert!(code.contains(&format!(
+            "#[serde(rename = \"{}\")]\n        pub {}_: String,",
+            name, name
+        )));
+    }
+    assert!(code.contains("pub r#type: String,"));
+
+    Ok(())
+}
+
+#[test]
+fn vendor_extensions() -> Result<(), Box<dyn Error>> {
+    let srv = load("tests/extensions.yaml")?;
+
+    // Operations with `x-qi-skip` are omitted.
+    assert_eq!(1, srv.operations.len());
+    assert!(srv.models.contains_key("Animal"));
+    assert!(!srv.models.contains_key("Pet"));
+
+    let code = ActixWebGenerator::new(Generator::new(srv)).generate()?;
+    assert!(code.contains("pub async fn fetch_animal("));
+    assert!(code.contains("pub struct FetchAnimalInput {"));
+    assert!(code.contains("pub id: String,"));
+    assert!(code.contains("#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]"));
+    assert!(code.contains("pub struct Animal {"));
+    assert!(code.contains("pub kind: String,"));
+    assert!(code.contains("pub addr: std::path::PathBuf,"));
+    assert!(!code.contains("pub struct Owner"));
+    assert!(!code.contains("delete_pet"));
+
+    Ok(())
+}
+
+#[test]
+fn format_generated_code() -> Result<(), Box<dyn Error>> {
+    let srv = load("tests/petstore.json")?;
+
+    let code = ActixWebGenerator::new(Generator::new(srv)).generate()?;
+    assert!(code.starts_with("// Code generated by qi. DO NOT EDIT.\n\n"));
+    assert!(syn::parse_file(&code).is_ok());
+    assert!(code.contains(
+        "    impl Client<'_> {\n        /// List all pets\n        ///\n        /// `GET /pets`\n        pub async fn list_pets("
+    ));
+
+    // Types set by `x-qi-type` are checked before generation.
+    let mut srv = load("tests/extensions.yaml")?;
+    for m in srv.models.values_mut() {
+        if let Some(v) = m.annotation.as_mut().filter(|v| v.type_path.is_some()) {
+            v.type_path = Some("std::path::".to_string());
+        }
+    }
+    let err = ActixWebGenerator::new(Generator::new(srv))
+        .generate()
+        .unwrap_err();
+    assert!(err.to_string().starts_with("invalid type std::path::"));
+
+    Ok(())
+}
+
+#[test]
+fn swagger_v2() -> Result<(), Box<dyn Error>> {
+    let srv = load("tests/petstore_v2.yaml")?;
+
+    let upload = srv
+        .operations
+        .iter()
+        .find(|v| v.id == "uploadPhoto")
+        .unwrap();
+    assert_eq!(
+        Some("multipart/form-data"),
+        upload.input.content_type.as_deref()
+    );
+    let download = srv
+        .operations
+        .iter()
+        .find(|v| v.id == "downloadPhoto")
+        .unwrap();
+    assert_eq!(Some(Model::byte_stream()), download.output.body);
+
+    let code = ActixWebGenerator::new(Generator::new(srv)).generate()?;
+    assert!(code.contains("pub async fn list_pets("));
+    assert!(code.contains("pub async fn put_pets_pet_id("));
+
+    Ok(())
+}
+
+#[test]
+fn openapi_v31() -> Result<(), Box<dyn Error>> {
+    let srv = load("tests/petstore_31.yaml")?;
+
+    let pet = &srv.models["Pet"].properties.as_ref().unwrap();
+    assert!(pet["tag"].nullable);
+    assert_eq!(ModelKind::String, pet["tag"].kind);
+    assert_eq!(ModelKind::String, pet["kind"].kind);
+    assert!(pet["owner"].nullable);
+    assert_eq!(ModelKind::Reference, pet["owner"].kind);
+    assert!(srv.models.contains_key("Address"));
+
+    let download = srv
+        .operations
+        .iter()
+        .find(|v| v.id == "downloadPhoto")
+        .unwrap();
+    assert_eq!(Some(Model::byte_stream()), download.output.body);
+
+    let code = ActixWebGenerator::new(Generator::new(srv)).generate()?;
+    assert!(code.contains("pub tag: Option<String>,"));
+    assert!(code.contains("pub owner: Option<models::Owner>,"));
+    assert!(code.contains("pub scores: Vec<Option<isize>>,"));
+
+    Ok(())
+}
+
+#[test]
+fn detect_input_format() -> Result<(), Box<dyn Error>> {
+    assert_eq!("json", sniff_format("\n  {\"openapi\": \"3.0.3\"}"));
+    assert_eq!("yaml", sniff_format("openapi: 3.0.3\n"));
+
+    let json = std::fs::read_to_string("tests/petstore.json")?;
+    let yaml = std::fs::read_to_string("tests/extensions.yaml")?;
+    // Unknown extensions are sniffed from content.
+    assert!(parse_value(Path::new("petstore.openapi"), &json, None).is_ok());
+    assert!(parse_value(Path::new("extensions.txt"), &yaml, None).is_ok());
+    assert!(parse_value(Path::new("-"), &yaml, None).is_ok());
+    // Explicit format overrides the extension.
+    assert!(parse_value(Path::new("petstore.yaml"), &json, Some("json")).is_ok());
+    assert!(parse_value(Path::new("extensions.json"), &yaml, Some("yaml")).is_ok());
+    assert!(parse_value(Path::new("extensions.yaml"), &yaml, Some("json")).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn builder_external_sources() -> Result<(), Box<dyn Error>> {
+    let path = Path::new("tests/external/api.yaml");
+    let value = parse_value(path, &std::fs::read_to_string(path)?, None)?;
+
+    let mut sources = std::collections::BTreeSet::new();
+    crate::builder::collect_sources(path, &value, &mut sources)?;
+    assert_eq!(
+        vec![
+            Path::new("tests/external/api.yaml"),
+            Path::new("tests/external/schemas/error.yaml"),
+            Path::new("tests/external/schemas/pet.yaml"),
+        ],
+        sources.iter().collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn validate_spec_file() -> Result<(), Box<dyn Error>> {
+    let (issues, spans) = validate_file(Path::new("tests/petstore.json"), None)?;
+    assert!(issues.is_empty());
+    assert!(spans.is_some());
+
+    // Relative references are bundled, but remote ones could not be built
+    // into the IR.
+    let (issues, _) = validate_file(Path::new("tests/external/api.yaml"), None)?;
+    assert!(issues.is_empty(), "{:?}", issues);
+    let (issues, _) = validate_file(Path::new("tests/external/remote.yaml"), None)?;
+    assert_eq!(1, issues.len());
+    assert_eq!("ir", issues[0].rule);
+    assert_eq!(
+        "/paths/~1pets/get/responses/200/content/application~1json/schema",
+        issues[0].pointer
+    );
+
+    Ok(())
+}
+
+#[test]
+fn builder_generate() -> Result<(), Box<dyn Error>> {
+    let out_dir = std::env::temp_dir().join("qi_rust_builder_generate");
+    std::fs::create_dir_all(&out_dir)?;
+
+    let outputs = Builder::new()
+        .spec("tests/petstore.json")
+        .spec("tests/s3.yaml")
+        .server(Server::None)
+        .out_dir(&out_dir)
+        .generate()?;
+    assert_eq!(
+        vec![out_dir.join("petstore.rs"), out_dir.join("s3.rs")],
+        outputs
+    );
+
+    let code = std::fs::read_to_string(out_dir.join("petstore.rs"))?;
+    assert!(!code.contains("pub trait Server"));
+    assert!(code.contains("pub struct Client {"));
+
+    let err = Builder::new()
+        .spec("tests/missing.yaml")
+        .out_dir(&out_dir)
+        .generate()
+        .unwrap_err();
+    assert!(
+        matches!(err, crate::Error::Io { ref path, .. } if path == Path::new("tests/missing.yaml"))
+    );
+
+    // Remote references are not resolved.
+    let err = Builder::new()
+        .spec("tests/external/remote.yaml")
+        .out_dir(&out_dir)
+        .rerun_if_changed(false)
+        .generate()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        crate::Error::Parse { ref path, ref message }
+            if path == Path::new("tests/external/remote.yaml")
+                && message.contains("external reference https://example.com/schemas/pet.yaml#/Pets")
+    ));
+
+    let err = Builder::new()
+        .spec("tests/petstore.json")
+        .spec("tests/config/petstore.yaml")
+        .out_dir(&out_dir)
+        .generate()
+        .unwrap_err();
+    assert!(
+        matches!(err, crate::Error::Generate { ref path, .. } if path == Path::new("tests/config/petstore.yaml"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn builder_generate_external() -> Result<(), Box<dyn Error>> {
+    let builder = Builder::new()
+        .spec("tests/external/api.yaml")
+        .server(Server::None)
+        .out_dir(std::env::temp_dir());
+
+    let mut rerun = Vec::new();
+    let outputs = builder.render(&mut rerun)?;
+    assert_eq!(
+        "cargo:rerun-if-changed=tests/external/api.yaml\n\
+         cargo:rerun-if-changed=tests/external/schemas/error.yaml\n\
+         cargo:rerun-if-changed=tests/external/schemas/pet.yaml\n",
+        String::from_utf8(rerun)?
+    );
+
+    // Schemas in other files are named after their pointers or files, and
+    // one referenced by both files is generated once.
+    let code = &outputs[0].1;
+    assert!(code.contains("pub struct Pet {"));
+    assert!(code.contains("pub error: models::Error,"));
+    assert_eq!(1, code.matches("pub struct Error {").count());
+    assert!(code.contains("pub body: Vec<models::Pet>,"));
+
+    let mut rerun = Vec::new();
+    builder.rerun_if_changed(false).render(&mut rerun)?;
+    assert!(rerun.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn bundle_references() -> Result<(), Box<dyn Error>> {
+    let spec = crate::load(Path::new("tests/external/api.yaml"), None)?;
+    let schemas = spec.components.as_ref().unwrap().schemas.as_ref().unwrap();
+    assert_eq!(
+        vec!["Pet", "Pets", "error"],
+        schemas.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Some("#/components/schemas/Pet"),
+        schemas["Pets"].items.as_ref().unwrap().ref_.as_deref()
+    );
+
+    let dir = std::env::temp_dir().join("qi_rust_bundle_references");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    // Taken names get a number, other objects are inlined, and references
+    // back to the spec become local ones.
+    std::fs::write(
+        dir.join("api.yaml"),
+        r##"{openapi: 3.0.3, info: {title: t, version: v}, paths: {/pets: {$ref: 'paths.yaml#/pets'}},
+        components: {schemas: {Pet: {type: string}}}}"##,
+    )?;
+    std::fs::write(
+        dir.join("paths.yaml"),
+        r##"{pets: {get: {responses: {'200': {$ref: '#/responses/Pets'}}}},
+        responses: {Pets: {description: Pets, content: {application/json: {schema: {$ref: '#/Pet'}}}}},
+        Pet: {type: object, properties: {name: {$ref: 'api.yaml#/components/schemas/Pet'}}}}"##,
+    )?;
+    let spec = crate::load(&dir.join("api.yaml"), None)?;
+    let schemas = spec.components.as_ref().unwrap().schemas.as_ref().unwrap();
+    assert_eq!(vec!["Pet", "Pet2"], schemas.keys().collect::<Vec<_>>());
+    assert_eq!(
+        Some("#/components/schemas/Pet"),
+        schemas["Pet2"].properties.as_ref().unwrap()["name"]
+            .ref_
+            .as_deref()
+    );
+    let op = &spec.paths.items["/pets"].operations[&qi_openapi::v3::Method::Get];
+    let response = &op.responses.as_ref().unwrap().items["200"];
+    assert_eq!(Some("Pets"), response.description.as_deref());
+
+    std::fs::write(dir.join("paths.yaml"), r##"{pets: {$ref: '#/pets'}}"##)?;
+    let err = crate::load(&dir.join("api.yaml"), None).unwrap_err();
+    assert!(
+        err.to_string().contains("circular reference #/pets"),
+        "{}",
+        err
+    );
+
+    std::fs::remove_file(dir.join("paths.yaml"))?;
+    let err = crate::load(&dir.join("api.yaml"), None).unwrap_err();
+    assert!(err.to_string().contains("paths.yaml: "), "{}", err);
+
+    Ok(())
+}
+
+#[test]
+fn builder_check() -> Result<(), Box<dyn Error>> {
+    let out_dir = std::env::temp_dir().join("qi_rust_builder_check");
+    let _ = std::fs::remove_dir_all(&out_dir);
+    std::fs::create_dir_all(&out_dir)?;
+
+    let builder = Builder::new()
+        .spec("tests/petstore.json")
+        .out_dir(&out_dir)
+        .rerun_if_changed(false);
+
+    // Missing files are out of date.
+    assert_eq!(1, builder.check()?.len());
+
+    builder.generate()?;
+    assert!(builder.check()?.is_empty());
+
+    // Unchanged files are not rewritten.
+    let output = out_dir.join("petstore.rs");
+    let modified = std::fs::metadata(&output)?.modified()?;
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    builder.generate()?;
+    assert_eq!(modified, std::fs::metadata(&output)?.modified()?);
+
+    let code = std::fs::read_to_string(&output)?;
+    std::fs::write(&output, code.replace("pub tag: String,", "pub tag: i64,"))?;
+    let diffs = builder.check()?;
+    assert_eq!(1, diffs.len());
+    assert!(diffs[0].contains("-        pub tag: i64,\n+        pub tag: String,\n"));
+    // Nothing is written by check.
+    assert!(std::fs::read_to_string(&output)?.contains("pub tag: i64,"));
+
+    Ok(())
+}
+
+#[test]
+fn watch_changes() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join("qi_rust_watch_changes");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("schemas"))?;
+    let spec = dir.join("api.yaml");
+    std::fs::copy("tests/external/api.yaml", &spec)?;
+    std::fs::copy(
+        "tests/external/schemas/pet.yaml",
+        dir.join("schemas/pet.yaml"),
+    )?;
+    std::fs::copy(
+        "tests/external/schemas/error.yaml",
+        dir.join("schemas/error.yaml"),
+    )?;
+
+    let dir = std::fs::canonicalize(&dir)?;
+    let mut set = WatchSet::new();
+    set.update(&spec);
+    assert_eq!(
+        vec![
+            dir.join("api.yaml"),
+            dir.join("schemas/error.yaml"),
+            dir.join("schemas/pet.yaml"),
+        ],
+        set.files().into_iter().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![dir.clone(), dir.join("schemas")],
+        set.dirs().into_iter().collect::<Vec<_>>()
+    );
+
+    // Only specs referencing changed files are affected.
+    let other = dir.join("other.yaml");
+    std::fs::write(
+        &other,
+        "{openapi: 3.0.3, info: {title: t, version: v}, paths: {},\n\
+         components: {schemas: {Error: {$ref: 'schemas/error.yaml'}}}}",
+    )?;
+    let mut both = WatchSet::new();
+    both.update(&spec);
+    both.update(&other);
+    let event = |path: PathBuf| notify::Event::default().add_path(path);
+    let changed = |path: &str| {
+        both.changed_specs(&event(dir.join(path)))
+            .into_iter()
+            .map(Path::to_path_buf)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec![spec.clone()], changed("schemas/pet.yaml"));
+    assert_eq!(vec![spec.clone()], changed("api.yaml"));
+    assert_eq!(
+        vec![spec.clone(), other.clone()],
+        changed("schemas/error.yaml")
+    );
+    assert_eq!(vec![other.clone()], changed("other.yaml"));
+    assert!(changed("schemas/other.yaml").is_empty());
+    assert!(changed("api.rs").is_empty());
+
+    // Referenced files are still watched while the spec is broken.
+    std::fs::write(&spec, "openapi: [")?;
+    set.update(&spec);
+    assert_eq!(3, set.files().len());
+
+    std::fs::write(
+        &spec,
+        "openapi: 3.0.3\ninfo: {title: t, version: v}\npaths: {}\n",
+    )?;
+    set.update(&spec);
+    assert_eq!(
+        vec![dir.as_path()],
+        set.dirs().iter().map(PathBuf::as_path).collect::<Vec<_>>()
+    );
+    assert!(set
+        .changed_specs(&event(dir.join("schemas/pet.yaml")))
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn debounce_changes() {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let period = Duration::from_millis(100);
+    let (tx, rx) = mpsc::channel();
+
+    // Unrelated events are skipped, and events following a change are
+    // drained together.
+    for v in [0, 1, 1, 1] {
+        tx.send(v).unwrap();
+    }
+    assert_eq!(Some(vec![1, 1, 1]), debounce(&rx, period, |v| *v == 1));
+    assert!(rx.try_recv().is_err());
+
+    // Events in the period extend it.
+    let sender = tx.clone();
+    let writer = std::thread::spawn(move || {
+        for v in 0..5 {
+            sender.send(v).unwrap();
+            std::thread::sleep(Duration::from_millis(30));
+        }
+    });
+    assert_eq!(
+        Some(vec![0, 1, 2, 3, 4]),
+        debounce(&rx, period, |v| *v == 0)
+    );
+    writer.join().unwrap();
+    assert!(rx.try_recv().is_err());
+
+    // Events after the period are left for the next change.
+    tx.send(1).unwrap();
+    let sender = tx.clone();
+    let writer = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(300));
+        sender.send(2).unwrap();
+    });
+    assert_eq!(Some(vec![1]), debounce(&rx, period, |v| *v == 1));
+    writer.join().unwrap();
+    assert_eq!(Ok(2), rx.try_recv());
+
+    tx.send(0).unwrap();
+    drop(tx);
+    assert_eq!(None, debounce(&rx, period, |v| *v == 1));
+}
+
+#[test]
+fn load_config() -> Result<(), Box<dyn Error>> {
+    let cfg = Config::load(Path::new("tests/config/qi.toml"))?;
+    assert_eq!(
+        vec!["petstore", "s3"],
+        cfg.targets.keys().collect::<Vec<_>>()
+    );
+
+    let target = &cfg.targets["petstore"];
+    // Paths are relative to the config file.
+    assert_eq!(Path::new("tests/config/../petstore.json"), target.spec);
+    assert_eq!(Path::new("tests/config/generated"), target.out);
+    assert_eq!(Server::None, target.server);
+    assert!(target.client);
+    assert_eq!(Server::Actix, cfg.targets["s3"].server);
+
+    assert_eq!(
+        Some(Path::new("tests/config/qi.toml").to_path_buf()),
+        Config::discover(Path::new("tests/config"))
+    );
+
+    // Unknown keys are rejected with expected ones.
+    let err = Config::parse("[target.api]\nspec = \"api.yaml\"\nout = \"src\"\nderive = []\n")
+        .unwrap_err();
+    assert!(err.contains("unknown field `derive`"));
+    assert!(err.contains("`derives`"));
+    let err =
+        Config::parse("[target.api]\nspec = \"api.yaml\"\nout = \"src\"\nserver = \"axum\"\n")
+            .unwrap_err();
+    assert!(err.contains("unknown variant `axum`"));
+
+    Ok(())
+}
+
+#[test]
+fn builder_customize() -> Result<(), Box<dyn Error>> {
+    let out_dir = std::env::temp_dir().join("qi_rust_builder_customize");
+    std::fs::create_dir_all(&out_dir)?;
+
+    let mut target = Config::load(Path::new("tests/config/qi.toml"))?.targets["petstore"].clone();
+    target.out = out_dir.clone();
+    target.builder().rerun_if_changed(false).generate()?;
+
+    let code = std::fs::read_to_string(out_dir.join("petstore.rs"))?;
+    assert!(code.contains("pub async fn list("));
+    assert!(code.contains("pub struct Animal {"));
+    assert!(code.contains("pub body: Vec<models::Animal>,"));
+    assert!(code.contains("#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]"));
+    // Mapped schemas are not generated.
+    assert!(!code.contains("pub struct Error {"));
+
+    let err = Builder::new()
+        .spec("tests/petstore.json")
+        .out_dir(&out_dir)
+        .rename("listPet", "list")
+        .generate()
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .ends_with("rename of unknown operation or schema listPet"));
+
+    Ok(())
+}
+
+#[test]
+fn builder_rename() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join("qi_rust_builder_rename");
+    std::fs::create_dir_all(&dir)?;
+    let spec = dir.join("rename.yaml");
+    std::fs::write(
+        &spec,
+        r##"
+openapi: 3.0.3
+info: {title: t, version: v}
+paths:
+  /pets:
+    post:
+      operationId: createPet
+      parameters:
+        - {name: session, in: cookie, schema: {$ref: '#/components/schemas/Session'}}
+      requestBody:
+        content:
+          multipart/form-data:
+            schema: {type: object, properties: {name: {type: string}}}
+            encoding:
+              name: {headers: {X-Trace: {schema: {$ref: '#/components/schemas/Trace'}}}}
+      responses:
+        '201': {description: Created}
+        default:
+          description: Error
+          content: {application/json: {schema: {$ref: '#/components/schemas/Problem'}}}
+components:
+  schemas:
+    Session: {type: string}
+    Trace: {type: integer}
+    Problem: {type: object, properties: {message: {type: string}}}
+"##,
+    )?;
+
+    // References in cookies, headers of encodings and errors are renamed
+    // too, or the code could not be generated.
+    let code = Builder::new()
+        .spec(&spec)
+        .out_dir(&dir)
+        .rerun_if_changed(false)
+        .rename("Session", "Token")
+        .rename("Trace", "TraceId")
+        .rename("Problem", "Failure")
+        .render(&mut Vec::new())?
+        .remove(0)
+        .1;
+    assert!(code.contains("pub struct Failure {"));
+    assert!(code.contains("Default { status: u16, body: models::Failure },"));
+    assert!(!code.contains("Problem"));
+
+    Ok(())
+}
+
+#[test]
+fn generate_unknown_reference() -> Result<(), Box<dyn Error>> {
+    let mut srv = load("tests/petstore.json")?;
+    srv.models.remove("Pet");
+
+    let err = ActixWebGenerator::new(Generator::new(srv))
+        .generate()
+        .unwrap_err();
+    assert_eq!("reference to unknown model Pet", err.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn builder_filter() -> Result<(), Box<dyn Error>> {
+    let out_dir = std::env::temp_dir().join("qi_rust_builder_filter");
+    std::fs::create_dir_all(&out_dir)?;
+
+    let cfg = Config::parse(
+        r#"
+[target.s3]
+spec = "s3.yaml"
+out = "generated"
+
+[[target.s3.filter.include]]
+operation_id = "*Object"
+methods = ["get", "put"]
+
+[[target.s3.filter.exclude]]
+operation_id = "Copy*"
+"#,
+    )?;
+    let mut target = cfg.targets["s3"].clone();
+    target.spec = Path::new("tests").join(&target.spec);
+    target.out = out_dir.clone();
+    target.builder().rerun_if_changed(false).generate()?;
+
+    let code = std::fs::read_to_string(out_dir.join("s3.rs"))?;
+    assert!(code.contains("pub async fn get_object("));
+    assert!(code.contains("pub async fn put_object("));
+    assert!(!code.contains("pub async fn delete_object("));
+    assert!(!code.contains("pub async fn copy_object("));
+    assert!(!code.contains("pub async fn list_buckets("));
+    // Models only used by dropped operations are pruned.
+    assert!(!code.contains("pub struct Bucket {"));
+    assert!(syn::parse_file(&code).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn group_operations_by_tags() -> Result<(), Box<dyn Error>> {
+    let srv = load("tests/tags.yaml")?;
+    assert_eq!(
+        Some(vec!["Buckets".to_string(), "admin".to_string()]),
+        srv.operations[1].tags
+    );
+
+    let code = ActixWebGenerator::new(Generator::new(srv)).generate()?;
+    // Operations are grouped by their first tags, ignoring cases.
+    assert!(code.contains("pub mod buckets {"));
+    assert!(code.contains("pub mod objects {"));
+    assert!(!code.contains("pub mod admin {"));
+    assert!(code.contains("pub trait Server: buckets::Server + objects::Server + 'static {"));
+    assert!(code.contains(
+        "pub fn buckets(&self) -> buckets::Client<'_> {\n        buckets::Client { client: self }"
+    ));
+    assert!(code.contains("web::post().to(buckets::handle_create_bucket::<S>)"));
+    // Untagged operations are generated at the top level.
+    assert!(code.contains("async fn health(&self, input: HealthInput)"));
+    assert!(code.contains("web::get().to(handle_health::<S>)"));
+
+    let mut srv = load("tests/tags.yaml")?;
+    srv.operations[0].tags = Some(vec!["models".to_string()]);
+    let err = ActixWebGenerator::new(Generator::new(srv))
+        .generate()
+        .unwrap_err();
+    assert_eq!(
+        "tag of operation listBuckets conflicts with module models",
+        err.to_string()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn deprecated_items() -> Result<(), Box<dyn Error>> {
+    let srv = load("tests/deprecated.yaml")?;
+    let list = &srv.operations[0];
+    assert!(list.annotation.as_ref().unwrap().deprecated);
+    assert!(list.input.query[0].model.deprecated());
+    assert!(list.output.header[0].model.deprecated());
+    assert!(srv.models["Pet"].deprecated());
+    assert!(srv.models["Pet"].properties.as_ref().unwrap()["tag"].deprecated());
+    assert!(!srv.models["Shelter"].deprecated());
+
+    let code = ActixWebGenerator::new(Generator::new(srv)).generate()?;
+    assert!(code.contains(
+        "#[deprecated(note = \"operation listPets is deprecated\")]\n    pub async fn list_pets("
+    ));
+    assert!(code.contains(
+        "#[deprecated(note = \"parameter limit is deprecated\")]\n    pub limit: Option<i32>,"
+    ));
+    assert!(code.contains("#[deprecated(note = \"header x-next is deprecated\")]"));
+    assert!(
+        code.contains("#[deprecated(note = \"schema Pet is deprecated\")]\n    pub struct Pet {")
+    );
+    assert!(code.contains(
+        "#[deprecated(note = \"property tag is deprecated\")]\n        pub tag: String,"
+    ));
+    // Generated code which uses deprecated items never warns.
+    assert!(code.contains("#[allow(deprecated)]\n    pub struct Shelter {"));
+    assert!(code.contains("#[allow(deprecated)]\npub struct CreatePetInput {"));
+    assert!(code.contains("#[allow(deprecated)]\nimpl ListPetsInput {"));
+    assert!(!code.contains("#[allow(deprecated)]\npub struct CreatePetOutput {"));
+
+    Ok(())
+}
+
+#[test]
+fn convert_descriptions() {
+    use crate::doc::rustdoc;
+
+    assert_eq!(
+        "Lists **all** `objects`.\n\nSee [docs](https://example.com?a=1&b=2).",
+        rustdoc(
+            "<p>Lists <b>all</b> <code>objects</code>.</p> \
+             <p>See <a href=\"https://example.com?a=1&amp;b=2\">docs</a>.</p>"
+        )
+    );
+    assert_eq!(
+        "Modes:\n\n1. Enabled\n2. max\\_keys \\< 1000",
+        rustdoc("Modes:<ol><li>Enabled</li><li>max_keys &lt; 1000</li></ol>")
+    );
+    assert_eq!(
+        "```text\nlet a = 1;\n```\n\n```rust,ignore\nlet b = 2;\n```",
+        rustdoc("```\nlet a = 1;\n```\n\n```rust\nlet b = 2;\n```")
+    );
+    assert_eq!(
+        "Run:\n\n```text\nqi generate\n```\n\nDone.",
+        rustdoc("Run:\n\n    qi generate\n\nDone.")
+    );
+    // Indented lines in lists are continuations instead of code.
+    assert_eq!("- item\n\n    more", rustdoc("- item\n\n    more"));
+    assert_eq!(
+        "See <https://example.com>, \\[not\\] a [link][1] or `[code]`.\n\n[1]: https://example.com/link",
+        rustdoc("See https://example.com, [not] a [link][1] or `[code]`.\n\n[1]: https://example.com/link")
+    );
+    // Autolinks and generics are not HTML.
+    assert_eq!(
+        "<https://example.com> returns Vec\\<T>",
+        rustdoc("<https://example.com> returns Vec<T>")
+    );
+}
+
+#[test]
+fn generate_docs() -> Result<(), Box<dyn Error>> {
+    let srv = load("tests/docs.yaml")?;
+    let show = &srv.operations[0];
+    assert_eq!(Some("A single pet"), show.summary.as_deref());
+    assert_eq!(
+        Some("Requests left in the window"),
+        show.output.header[0].description.as_deref()
+    );
+    let update = &srv.operations[1];
+    assert_eq!(Some("Update a pet"), update.summary.as_deref());
+    assert_eq!(
+        Some(&serde_json::json!({"name": "Kitty"})),
+        update.input.example.as_ref()
+    );
+    // Examples of schemas are used if bodies have none.
+    assert_eq!(
+        Some(&serde_json::json!({"name": "Tom"})),
+        show.output.example.as_ref()
+    );
+
+    let code = ActixWebGenerator::new(Generator::new(srv)).generate()?;
+    assert!(code.contains(
+        "    /// A pet in the **store**.\n    ///\n    /// Tags of a pet:\n    ///\n    /// - `cat`\n    /// - dog & \\[puppy\\]\n"
+    ));
+    assert!(code.contains("        /// Name of the pet.\n        pub name: String,"));
+    assert!(code.contains(
+        "    /// The id of the pet, see <https://example.com/ids>.\n    pub pet_id: String,"
+    ));
+    assert!(code.contains("    /// The pet to update\n    pub body: models::Pet,"));
+    assert!(code.contains("/// The pet\n#[derive(Debug, Default)]\npub struct ShowPetByIdOutput {"));
+    assert!(code.contains(
+        "    /// A single pet\n    ///\n    /// Pets are found by [their ids](https://example.com/ids).\n    ///\n    /// `GET /pets/{petId}`\n"
+    ));
+    assert!(code.contains("    /// ```text\n    /// pet.name = \"Kitty\"\n    /// ```\n"));
+    assert!(code.contains(
+        "    /// Request example:\n    ///\n    /// ```json\n    /// {\n    ///   \"name\": \"Kitty\"\n    /// }\n    /// ```\n    pub async fn update_pet("
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn security_schemes() -> Result<(), Box<dyn Error>> {
+    use qi_ir::{KeyLocation, SecurityKind};
+
+    let srv = load("tests/security.yaml")?;
+    let schemes = &srv.security_schemes;
+    assert_eq!(7, schemes.len());
+    assert_eq!(Some(KeyLocation::Cookie), schemes["session"].location);
+    assert_eq!(Some("basic"), schemes["basic"].scheme.as_deref());
+    assert_eq!(SecurityKind::OAuth2, schemes["petstore_auth"].kind);
+    assert_eq!(
+        Some("https://example.com/oauth/token"),
+        schemes["petstore_auth"].flows["clientCredentials"]
+            .token_url
+            .as_deref()
+    );
+
+    // Operations use the top level security unless they override it.
+    let security = |id: &str| {
+        let op = srv.operations.iter().find(|v| v.id == id).unwrap();
+        serde_json::to_string(&op.security).unwrap()
+    };
+    assert_eq!(r#"[{"api_key":[]},{"basic":[]}]"#, security("showPetById"));
+    assert_eq!(
+        r#"[{"petstore_auth":["write:pets"],"session":[]}]"#,
+        security("createPet")
+    );
+    assert_eq!("[]", security("health"));
+
+    let code = ActixWebGenerator::new(Generator::new(srv.clone())).generate()?;
+    assert!(code.contains("async fn authenticate(\n        &self,\n        req: &HttpRequest,"));
+    assert!(code.contains("pub trait CredentialProvider: Send + Sync {"));
+    // Anonymous alternatives are tried last.
+    assert!(code.contains(r#".authorize(req, &[&[("bearer", &[])], &[]])"#));
+    assert!(code.contains("pub(super) async fn handle_list_pets<S: super::Server>("));
+    assert!(code.contains(r#""session" => {"#));
+    assert!(code.contains(r#"("token", Credential::ApiKey(v)) => Ok(req.query(&[("token", v)])),"#));
+    // Operations without security are not authorized.
+    let health = code.find("async fn handle_health").unwrap();
+    assert!(!code[health..health + 300].contains("authorize("));
+
+    let mut srv = srv;
+    srv.security_schemes.remove("bearer");
+    let err = ActixWebGenerator::new(Generator::new(srv))
+        .generate()
+        .unwrap_err();
+    assert_eq!(
+        "operation listPets requires unknown security scheme bearer",
+        err.to_string()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+use crate::builder::sources;
+
+/// WatchSet tracks specs and files referenced by them, paths are absolute
+/// like those of events from the watcher.
+///
+/// Code is emitted without templates, so there is no template directory to
+/// watch.
+#[derive(Debug, Default)]
+pub struct WatchSet {
+    files: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl WatchSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refresh files of the spec. Previous files are kept if the spec is
+    /// broken, so that fixing it triggers regeneration.
+    pub fn update(&mut self, spec: &Path) {
+        let watched = self.files.entry(spec.to_path_buf()).or_default();
+        if let Ok(sources) = sources(spec) {
+            *watched = sources.iter().filter_map(|v| watch_path(v)).collect();
+        } else if watched.is_empty() {
+            watched.extend(watch_path(spec));
+        }
+    }
+
+    /// Returns all watched files.
+    pub fn files(&self) -> BTreeSet<&Path> {
+        self.files
+            .values()
+            .flatten()
+            .map(PathBuf::as_path)
+            .collect()
+    }
+
+    /// Returns parent directories of watched files, which are watched
+    /// instead of files, since editors may replace files on saving which
+    /// drops watches on them.
+    pub fn dirs(&self) -> BTreeSet<PathBuf> {
+        self.files()
+            .iter()
+            .filter_map(|v| v.parent().map(Path::to_path_buf))
+            .collect()
+    }
+
+    /// Returns specs whose files are changed by the event, as they are
+    /// passed to `update`.
+    pub fn changed_specs(&self, event: &notify::Event) -> BTreeSet<&Path> {
+        self.files
+            .iter()
+            .filter(|(_, files)| event.paths.iter().any(|v| files.contains(v)))
+            .map(|(spec, _)| spec.as_path())
+            .collect()
+    }
+}
+
+/// Returns the absolute path of a file, which is the same as paths of
+/// events from the watcher.
+fn watch_path(path: &Path) -> Option<PathBuf> {
+    let dir = match path.parent() {
+        Some(v) if v != Path::new("") => v,
+        _ => Path::new("."),
+    };
+
+    Some(fs::canonicalize(dir).ok()?.join(path.file_name()?))
+}
+
+/// Wait for an event accepted by `changed`, and then drain events until
+/// nothing happens in the `period`, since editors may write a file several
+/// times on saving. Returns the accepted event and the drained ones, or
+/// `None` if the sender is dropped before any change.
+pub fn debounce<T>(
+    rx: &Receiver<T>,
+    period: Duration,
+    changed: impl Fn(&T) -> bool,
+) -> Option<Vec<T>> {
+    let mut events = loop {
+        match rx.recv() {
+            Ok(event) if changed(&event) => break vec![event],
+            Ok(_) => continue,
+            Err(_) => return None,
+        }
+    };
+
+    while let Ok(event) = rx.recv_timeout(period) {
+        events.push(event);
+    }
+    Some(events)
+}